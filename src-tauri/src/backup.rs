@@ -0,0 +1,207 @@
+//! Snapshots of the cards database.
+//!
+//! Snapshots are taken with SQLite's online backup API, so they are consistent even while the
+//! generation queue is writing. One is taken at startup and before operations that destroy data,
+//! bring in a lot of it or rewrite many cards at once (purging the trash, deleting sub-collections,
+//! imports, restores, merges, splits and bulk edits). The undo journal covers those too, but only
+//! while its history lasts and only if nothing touched the rows since. Files are
+//! named `cards-<UTC timestamp>-<reason>.db`; the name doubles as the backup id.
+
+use crate::error::AppError;
+
+/// Snapshots kept in the backups folder; older ones are deleted as new ones are taken.
+const BACKUP_LIMIT: usize = 10;
+
+fn backups_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
+    let path = crate::app_data_dir(app)?.join("backups");
+    std::fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
+/// Snapshot the live database into the backups folder and rotate old snapshots. `reason` is a short
+/// slug such as "startup" or "import". Repeats of the same reason within a minute (e.g. importing
+/// several collections from one file) reuse the previous snapshot instead of pushing older ones out.
+pub(crate) fn snapshot(app: &tauri::AppHandle, reason: &str) -> Result<(), AppError> {
+    let dir = backups_dir(app)?;
+    let conn = crate::open_db(app)?;
+    let minute_ago: String = conn
+        .query_row("SELECT strftime('%Y%m%d-%H%M%S', 'now', '-1 minute')", [], |row| row.get(0))?;
+    let recent = backup_ids(&dir)?.into_iter().next().is_some_and(|id| {
+        id.strip_prefix("cards-").is_some_and(|rest| rest.get(..15) >= Some(minute_ago.as_str()))
+            && parse_backup_id(&id).is_some_and(|(_, r)| r == reason)
+    });
+    if recent {
+        return Ok(());
+    }
+    write_snapshot(app, reason)?;
+    rotate(&dir)
+}
+
+fn write_snapshot(app: &tauri::AppHandle, reason: &str) -> Result<(), AppError> {
+    let conn = crate::open_db(app)?;
+    let stamp: String = conn.query_row("SELECT strftime('%Y%m%d-%H%M%S', 'now')", [], |row| row.get(0))?;
+    let dir = backups_dir(app)?;
+    let mut id = format!("cards-{stamp}-{reason}.db");
+    let mut n = 1;
+    while dir.join(&id).exists() {
+        n += 1;
+        id = format!("cards-{stamp}-{reason}-{n}.db");
+    }
+    // Write under a temporary name so an interrupted backup never shows up in the list.
+    let partial = dir.join(format!("{id}.partial"));
+    conn.backup(rusqlite::DatabaseName::Main, &partial, None)?;
+    std::fs::rename(&partial, dir.join(&id))?;
+    Ok(())
+}
+
+fn backup_ids(dir: &std::path::Path) -> Result<Vec<String>, AppError> {
+    let mut ids: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with("cards-") && name.ends_with(".db"))
+        .collect();
+    // Timestamps sort lexically; newest first.
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(ids)
+}
+
+fn rotate(dir: &std::path::Path) -> Result<(), AppError> {
+    for id in backup_ids(dir)?.into_iter().skip(BACKUP_LIMIT) {
+        std::fs::remove_file(dir.join(id))?;
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct BackupInfo {
+    id: String,
+    /// UTC, in the same `YYYY-MM-DD HH:MM:SS` form as the database timestamps.
+    created_at: String,
+    reason: String,
+    size: u64,
+}
+
+/// Split `cards-20250101-120000-reason.db` into its creation time and reason.
+fn parse_backup_id(id: &str) -> Option<(String, String)> {
+    let rest = id.strip_prefix("cards-")?.strip_suffix(".db")?;
+    let (date, rest) = rest.split_at_checked(8)?;
+    let (time, reason) = rest.strip_prefix('-')?.split_at_checked(6)?;
+    let reason = reason.strip_prefix('-').unwrap_or(reason);
+    let created_at = format!(
+        "{}-{}-{} {}:{}:{}",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        &time[..2],
+        &time[2..4],
+        &time[4..]
+    );
+    Some((created_at, reason.to_string()))
+}
+
+#[tauri::command]
+pub fn list_backups(app: tauri::AppHandle) -> Result<Vec<BackupInfo>, AppError> {
+    let dir = backups_dir(&app)?;
+    Ok(backup_ids(&dir)?
+        .into_iter()
+        .filter_map(|id| {
+            let (created_at, reason) = parse_backup_id(&id)?;
+            let size = std::fs::metadata(dir.join(&id)).ok()?.len();
+            Some(BackupInfo { id, created_at, reason, size })
+        })
+        .collect())
+}
+
+/// Check that a backup file is an intact cards database before it replaces the live one.
+fn validate_backup(path: &std::path::Path) -> Result<(), AppError> {
+    let conn = rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| AppError::Db(format!("Cannot open backup: {e}")))?;
+    let result: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| AppError::Db(format!("Backup is not a valid database: {e}")))?;
+    if result != "ok" {
+        return Err(AppError::Db(format!("Backup failed the integrity check: {result}")));
+    }
+    let tables: u32 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('cards', 'collections')",
+            [],
+            |row| row.get(0),
+        )?;
+    if tables != 2 {
+        return Err(AppError::Db("Backup does not contain a cards database".to_string()));
+    }
+    Ok(())
+}
+
+/// Replace the live database with a backup. The backup is validated first and the current database is
+/// snapshotted, so a restore can itself be reverted from the list.
+#[tauri::command]
+pub fn restore_backup(app: tauri::AppHandle, id: String) -> Result<(), AppError> {
+    let dir = backups_dir(&app)?;
+    if !backup_ids(&dir)?.contains(&id) {
+        return Err(AppError::not_found(format!("Backup \"{id}\" not found")));
+    }
+    let path = dir.join(&id);
+    validate_backup(&path)?;
+    // Rotate only after restoring; the backup being restored may be the oldest one kept.
+    write_snapshot(&app, "pre-restore")?;
+    let mut conn = rusqlite::Connection::open(crate::db_path(&app)?)?;
+    conn.restore(rusqlite::DatabaseName::Main, &path, None::<fn(rusqlite::backup::Progress)>)?;
+    // Bring snapshots from older versions up to the current schema.
+    crate::init_db(&conn)?;
+    rotate(&dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("backup-test-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn backup_ids_give_time_and_reason() {
+        assert_eq!(
+            parse_backup_id("cards-20250102-030405-purge-trash.db"),
+            Some(("2025-01-02 03:04:05".to_string(), "purge-trash".to_string()))
+        );
+        assert_eq!(parse_backup_id("cards-20250102-030405-import-2.db").unwrap().1, "import-2");
+        assert_eq!(parse_backup_id("cards-2025.db"), None);
+        assert_eq!(parse_backup_id("other-20250102-030405-startup.db"), None);
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest() {
+        let dir = temp_dir("rotate");
+        for day in 10..10 + BACKUP_LIMIT + 2 {
+            std::fs::write(dir.join(format!("cards-202501{day:02}-000000-startup.db")), b"").unwrap();
+        }
+        std::fs::write(dir.join("unrelated.txt"), b"").unwrap();
+        rotate(&dir).unwrap();
+        let ids = backup_ids(&dir).unwrap();
+        assert_eq!(ids.len(), BACKUP_LIMIT);
+        assert_eq!(ids[0], format!("cards-202501{:02}-000000-startup.db", 10 + BACKUP_LIMIT + 1));
+        assert!(dir.join("unrelated.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn only_intact_cards_databases_validate() {
+        let dir = temp_dir("validate");
+        let good = dir.join("good.db");
+        let conn = rusqlite::Connection::open(&good).unwrap();
+        crate::init_db(&conn).unwrap();
+        drop(conn);
+        assert!(validate_backup(&good).is_ok());
+        let other = dir.join("other.db");
+        rusqlite::Connection::open(&other).unwrap().execute("CREATE TABLE t (x)", []).unwrap();
+        assert!(validate_backup(&other).is_err());
+        let junk = dir.join("junk.db");
+        std::fs::write(&junk, b"not a database at all, just some text").unwrap();
+        assert!(validate_backup(&junk).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
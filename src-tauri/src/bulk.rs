@@ -0,0 +1,259 @@
+//! Editing many cards at once.
+//!
+//! `bulk_update_cards` selects cards with a `CardFilter` and applies one `BulkOperation` to all of
+//! them. In preview mode it only reports what would change, so the UI can show before and after.
+
+use crate::error::AppError;
+use crate::journal;
+
+/// Which live cards an operation applies to. Every condition given must match.
+#[derive(serde::Deserialize)]
+pub struct CardFilter {
+    collection_id: i64,
+    /// This sub collection and everything below it.
+    #[serde(default)]
+    sub_collection_id: Option<i64>,
+    /// Only these cards, e.g. the ones selected in the list.
+    #[serde(default)]
+    card_ids: Option<Vec<i64>>,
+    /// Case-insensitive text that must appear in the question, answer or hint.
+    #[serde(default)]
+    search: Option<String>,
+}
+
+#[derive(Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardField {
+    Question,
+    Answer,
+    Hint,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BulkOperation {
+    /// Regex find and replace in one field; `replacement` may use `$1` or `${name}` for groups.
+    Replace {
+        field: CardField,
+        pattern: String,
+        replacement: String,
+        #[serde(default)]
+        ignore_case: bool,
+    },
+    SetHint { hint: String },
+    SetSuspended { suspended: bool },
+    SetBuried { buried: bool },
+    /// Move the cards to another sub collection of their collection.
+    SetSubCollection { sub_collection_id: i64 },
+}
+
+/// The parts of a card a bulk operation can change.
+#[derive(Clone, PartialEq, serde::Serialize)]
+pub struct CardFields {
+    question: String,
+    answer: String,
+    hint: String,
+    suspended: bool,
+    buried: bool,
+    sub_collection_id: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct CardChange {
+    id: i64,
+    before: CardFields,
+    after: CardFields,
+}
+
+#[derive(serde::Serialize)]
+pub struct BulkUpdateReport {
+    /// Cards the operation changes (or would change, in preview mode); matched cards it leaves as they
+    /// are are not listed.
+    changes: Vec<CardChange>,
+    /// False in preview mode.
+    applied: bool,
+}
+
+fn matching_cards(conn: &rusqlite::Connection, filter: &CardFilter) -> Result<Vec<(i64, CardFields)>, AppError> {
+    let card_ids = filter.card_ids.as_ref().map(serde_json::to_string).transpose()?;
+    let search = filter.search.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, question, answer, COALESCE(hint, ''), suspended_at IS NOT NULL, buried_at IS NOT NULL, sub_collection_id FROM cards
+         WHERE collection_id = ?1 AND deleted_at IS NULL
+           AND (?2 IS NULL OR sub_collection_id IN {})
+           AND (?3 IS NULL OR id IN (SELECT value FROM json_each(?3)))
+           AND (?4 IS NULL OR instr(lower(question || char(10) || answer || char(10) || COALESCE(hint, '')), lower(?4)) > 0)
+         ORDER BY id",
+        crate::hierarchy::subtree("?2")
+    ))?;
+    let rows = stmt
+        .query_map(rusqlite::params![filter.collection_id, filter.sub_collection_id, card_ids, search], |row| {
+            Ok((
+                row.get(0)?,
+                CardFields {
+                    question: row.get(1)?,
+                    answer: row.get(2)?,
+                    hint: row.get(3)?,
+                    suspended: row.get(4)?,
+                    buried: row.get(5)?,
+                    sub_collection_id: row.get(6)?,
+                },
+            ))
+        })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// The card as the operation leaves it.
+fn apply(operation: &BulkOperation, regex: Option<&regex::Regex>, card: &CardFields) -> CardFields {
+    let mut after = card.clone();
+    match operation {
+        BulkOperation::Replace { field, replacement, .. } => {
+            let text = match field {
+                CardField::Question => &mut after.question,
+                CardField::Answer => &mut after.answer,
+                CardField::Hint => &mut after.hint,
+            };
+            if let Some(regex) = regex {
+                *text = regex.replace_all(text, replacement.as_str()).into_owned();
+            }
+        }
+        BulkOperation::SetHint { hint } => after.hint = hint.trim().to_string(),
+        BulkOperation::SetSuspended { suspended } => after.suspended = *suspended,
+        BulkOperation::SetBuried { buried } => after.buried = *buried,
+        BulkOperation::SetSubCollection { sub_collection_id } => after.sub_collection_id = *sub_collection_id,
+    }
+    after
+}
+
+/// Apply `operation` to every card matching `filter`, in one transaction and one undo entry. With
+/// `preview` nothing is written. Fails without changing anything if a card would end up with an empty
+/// question or answer, or identical to another card in its sub collection.
+#[tauri::command]
+pub fn bulk_update_cards(
+    app: tauri::AppHandle,
+    filter: CardFilter,
+    operation: BulkOperation,
+    preview: Option<bool>,
+) -> Result<BulkUpdateReport, AppError> {
+    let regex = match &operation {
+        BulkOperation::Replace { pattern, ignore_case, .. } => {
+            if pattern.is_empty() {
+                return Err(AppError::validation("pattern", "Enter text or a pattern to find"));
+            }
+            let regex = regex::RegexBuilder::new(pattern)
+                .case_insensitive(*ignore_case)
+                .build()
+                .map_err(|e| AppError::validation("pattern", format!("Invalid pattern: {e}")))?;
+            Some(regex)
+        }
+        _ => None,
+    };
+
+    let preview = preview.unwrap_or(false);
+    if !preview {
+        crate::backup::snapshot(&app, "bulk-edit")?;
+    }
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    if let BulkOperation::SetSubCollection { sub_collection_id } = operation {
+        crate::check_sub_collection(&tx, filter.collection_id, sub_collection_id)?;
+    }
+    let changes: Vec<CardChange> = matching_cards(&tx, &filter)?
+        .into_iter()
+        .filter_map(|(id, before)| {
+            let after = apply(&operation, regex.as_ref(), &before);
+            (after != before).then_some(CardChange { id, before, after })
+        })
+        .collect();
+    if let Some(change) = changes.iter().find(|c| c.after.question.trim().is_empty() || c.after.answer.trim().is_empty()) {
+        return Err(AppError::validation(
+            "replacement",
+            format!("Card {} would be left without a question or answer", change.id),
+        ));
+    }
+    if preview {
+        return Ok(BulkUpdateReport { changes, applied: false });
+    }
+
+    let noun = if changes.len() == 1 { "card" } else { "cards" };
+    let mut op = journal::Operation::new(format!("Edit {} {noun}", changes.len()));
+    for change in &changes {
+        op.track(&tx, "cards", "id = ?1", rusqlite::params![change.id])?;
+        let after = &change.after;
+        tx.execute(
+            "UPDATE cards SET question = ?1, answer = ?2, hint = ?3,
+                suspended_at = CASE WHEN ?4 THEN COALESCE(suspended_at, datetime('now')) END,
+                buried_at = CASE WHEN ?5 THEN COALESCE(buried_at, datetime('now')) END,
+                sub_collection_id = ?6
+             WHERE id = ?7",
+            rusqlite::params![after.question, after.answer, after.hint, after.suspended, after.buried, after.sub_collection_id, change.id],
+        )
+        .map_err(|e| AppError::from(e).context(format!("Card {}", change.id)))?;
+        crate::card_types::sync_card_prompts(&tx, Some(&mut op), change.id)?;
+    }
+    op.commit(&tx)?;
+    tx.commit()?;
+    Ok(BulkUpdateReport { changes, applied: true })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(question: &str, hint: &str) -> CardFields {
+        CardFields {
+            question: question.to_string(),
+            answer: "A".to_string(),
+            hint: hint.to_string(),
+            suspended: false,
+            buried: false,
+            sub_collection_id: 1,
+        }
+    }
+
+    fn operation(json: serde_json::Value) -> BulkOperation {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn replace_uses_groups_in_one_field() {
+        let replace = operation(serde_json::json!({
+            "kind": "replace", "field": "question", "pattern": r"(\w+) of (\w+)", "replacement": "$2's $1"
+        }));
+        let regex = regex::Regex::new(r"(\w+) of (\w+)").unwrap();
+        let after = apply(&replace, Some(&regex), &fields("capital of France?", "capital of"));
+        assert_eq!(after.question, "France's capital?");
+        assert_eq!(after.hint, "capital of");
+    }
+
+    #[test]
+    fn set_operations_change_only_their_field() {
+        let card = fields("Q", "old");
+        assert_eq!(apply(&operation(serde_json::json!({ "kind": "set_hint", "hint": " new " })), None, &card).hint, "new");
+        let suspended = apply(&operation(serde_json::json!({ "kind": "set_suspended", "suspended": true })), None, &card);
+        assert!(suspended.suspended && !suspended.buried);
+        let moved = apply(&operation(serde_json::json!({ "kind": "set_sub_collection", "sub_collection_id": 3 })), None, &card);
+        assert!(moved.sub_collection_id == 3 && moved.question == card.question);
+    }
+
+    #[test]
+    fn filters_combine() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A');
+             INSERT INTO sub_collections (id, name, collection_id, parent_id) VALUES (1, '- None -', 1, NULL), (2, 'Ch1', 1, NULL), (3, 'Sec', 1, 2);
+             INSERT INTO cards (id, question, answer, hint, collection_id, sub_collection_id, deleted_at) VALUES
+                 (1, 'Mitosis', 'A', '', 1, 1, NULL), (2, 'Meiosis', 'B', 'like MITOSIS', 1, 3, NULL),
+                 (3, 'Mitosis', 'C', '', 1, 2, datetime('now')), (4, 'Cells', 'D', '', 1, 2, NULL);",
+        )
+        .unwrap();
+        let ids = |json| -> Vec<i64> {
+            let filter: CardFilter = serde_json::from_value(json).unwrap();
+            matching_cards(&conn, &filter).unwrap().into_iter().map(|(id, _)| id).collect()
+        };
+        assert_eq!(ids(serde_json::json!({ "collection_id": 1, "search": " mitosis " })), vec![1, 2]);
+        assert_eq!(ids(serde_json::json!({ "collection_id": 1, "sub_collection_id": 2 })), vec![2, 4]);
+        assert_eq!(ids(serde_json::json!({ "collection_id": 1, "card_ids": [1, 4], "search": "cell" })), vec![4]);
+    }
+}
@@ -0,0 +1,484 @@
+//! Card types beyond a plain question and answer.
+//!
+//! A card's `card_type` decides which prompts it is studied as:
+//! - `basic`: question → answer.
+//! - `reverse`: question → answer, and answer → question.
+//! - `cloze`: the question holds deletions like `{{c1::Paris}}` or `{{c1::Paris::city}}`; each cloze
+//!   number is one prompt hiding the deletions with that number. The answer is extra text for the back
+//!   and may be empty.
+//! - `multiple_choice`: question → answer, picked from the answer and the card's `choices` (the
+//!   distractors).
+//!
+//! Prompts are rows of `card_prompts`, each with its own scheduling state. They follow from the card's
+//! type and text: every place that writes a card's type or question calls `sync_card_prompts`, and
+//! opening the database creates the prompts of cards that have none.
+
+use std::sync::OnceLock;
+
+use crate::error::AppError;
+use crate::journal;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardType {
+    #[default]
+    Basic,
+    Reverse,
+    Cloze,
+    MultipleChoice,
+}
+
+impl CardType {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            CardType::Basic => "basic",
+            CardType::Reverse => "reverse",
+            CardType::Cloze => "cloze",
+            CardType::MultipleChoice => "multiple_choice",
+        }
+    }
+
+    /// Unknown values read as basic, so a newer database still opens.
+    pub(crate) fn parse(value: &str) -> CardType {
+        match value {
+            "reverse" => CardType::Reverse,
+            "cloze" => CardType::Cloze,
+            "multiple_choice" => CardType::MultipleChoice,
+            _ => CardType::Basic,
+        }
+    }
+}
+
+pub(crate) fn init_card_type_tables(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    crate::add_column_if_missing(conn, "cards", "card_type", "TEXT NOT NULL DEFAULT 'basic'")?;
+    // JSON array of wrong answers offered alongside the answer, for multiple choice cards.
+    crate::add_column_if_missing(conn, "cards", "choices", "TEXT")?;
+    // `ordinal` is 0 for the question → answer prompt, 1 for the reverse, and the cloze number for
+    // cloze cards. `due` is NULL until the first review.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS card_prompts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id INTEGER NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+            ordinal INTEGER NOT NULL,
+            state TEXT NOT NULL DEFAULT 'new',
+            due TEXT,
+            interval_days REAL NOT NULL DEFAULT 0,
+            ease REAL NOT NULL DEFAULT 2.5,
+            reps INTEGER NOT NULL DEFAULT 0,
+            lapses INTEGER NOT NULL DEFAULT 0,
+            last_reviewed_at TEXT,
+            UNIQUE (card_id, ordinal)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn cloze_regex() -> &'static regex::Regex {
+    static CLOZE: OnceLock<regex::Regex> = OnceLock::new();
+    CLOZE.get_or_init(|| regex::Regex::new(r"\{\{c(\d+)::(.*?)(?:::(.*?))?\}\}").expect("valid cloze pattern"))
+}
+
+/// Cloze numbers used in the text, ascending and without repeats.
+pub(crate) fn cloze_numbers(text: &str) -> Vec<i64> {
+    let mut numbers: Vec<i64> = cloze_regex()
+        .captures_iter(text)
+        .filter_map(|c| c[1].parse().ok())
+        .collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers
+}
+
+/// Ordinals of the prompts a card is studied as.
+pub(crate) fn prompt_ordinals(card_type: CardType, question: &str) -> Vec<i64> {
+    match card_type {
+        CardType::Basic | CardType::MultipleChoice => vec![0],
+        CardType::Reverse => vec![0, 1],
+        CardType::Cloze => cloze_numbers(question),
+    }
+}
+
+/// Create the card's missing prompts and delete those that no longer apply, e.g. after a cloze was
+/// removed from the text. Called wherever a card's type or question is written. With `op`, the prompts
+/// are journaled so undoing the edit brings them back with their review state. Reviews of a deleted
+/// prompt stay in the log for the card.
+pub(crate) fn sync_card_prompts(conn: &rusqlite::Connection, mut op: Option<&mut journal::Operation>, card_id: i64) -> Result<(), AppError> {
+    let (card_type, question): (String, String) = conn
+        .query_row("SELECT card_type, question FROM cards WHERE id = ?1", rusqlite::params![card_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+    let ordinals = prompt_ordinals(CardType::parse(&card_type), &question);
+    let ordinals_json = serde_json::to_string(&ordinals)?;
+    let stale = "card_id = ?1 AND ordinal NOT IN (SELECT value FROM json_each(?2))";
+    if let Some(op) = op.as_deref_mut() {
+        op.track(
+            conn,
+            "review_log",
+            &format!("prompt_id IN (SELECT id FROM card_prompts WHERE {stale})"),
+            rusqlite::params![card_id, ordinals_json],
+        )?;
+        op.track(conn, "card_prompts", stale, rusqlite::params![card_id, ordinals_json])?;
+    }
+    conn.execute(&format!("DELETE FROM card_prompts WHERE {stale}"), rusqlite::params![card_id, ordinals_json])?;
+    for ordinal in ordinals {
+        let inserted = conn
+            .execute("INSERT OR IGNORE INTO card_prompts (card_id, ordinal) VALUES (?1, ?2)", rusqlite::params![card_id, ordinal])?;
+        if let (1, Some(op)) = (inserted, op.as_deref_mut()) {
+            op.track_inserted("card_prompts", conn.last_insert_rowid());
+        }
+    }
+    Ok(())
+}
+
+/// Create the prompts of cards that have none, e.g. cards from before card types existed.
+pub(crate) fn fill_missing_prompts(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    let cards: Vec<i64> = conn
+        .prepare("SELECT id FROM cards WHERE NOT EXISTS (SELECT 1 FROM card_prompts WHERE card_id = cards.id)")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for id in cards {
+        sync_card_prompts(conn, None, id)?;
+    }
+    Ok(())
+}
+
+/// Give `into` the review state of `from`'s prompts wherever `into` has not been reviewed yet, and
+/// move `from`'s review history to `into`'s matching prompts, so folding one card into another keeps
+/// what was learned on either.
+pub(crate) fn fold_prompts(conn: &rusqlite::Connection, op: &mut journal::Operation, from: i64, into: i64) -> Result<(), AppError> {
+    let reviewed: Vec<i64> = conn
+        .prepare("SELECT ordinal FROM card_prompts WHERE card_id = ?1 AND reps > 0")?
+        .query_map(rusqlite::params![from], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for ordinal in reviewed {
+        let existing: Option<(i64, i64)> = match conn.query_row(
+            "SELECT id, reps FROM card_prompts WHERE card_id = ?1 AND ordinal = ?2",
+            rusqlite::params![into, ordinal],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ) {
+            Ok(row) => Some(row),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e.into()),
+        };
+        match existing {
+            Some((_, reps)) if reps > 0 => {}
+            Some((id, _)) => {
+                op.track(conn, "card_prompts", "id = ?1", rusqlite::params![id])?;
+                conn.execute(
+                    "UPDATE card_prompts SET
+                         state = src.state, step = src.step, due = src.due, interval_days = src.interval_days, ease = src.ease,
+                         reps = src.reps, lapses = src.lapses, last_reviewed_at = src.last_reviewed_at
+                     FROM (SELECT * FROM card_prompts WHERE card_id = ?1 AND ordinal = ?2) AS src
+                     WHERE card_prompts.id = ?3",
+                    rusqlite::params![from, ordinal, id],
+                )?;
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO card_prompts (card_id, ordinal, state, step, due, interval_days, ease, reps, lapses, last_reviewed_at)
+                     SELECT ?2, ordinal, state, step, due, interval_days, ease, reps, lapses, last_reviewed_at
+                     FROM card_prompts WHERE card_id = ?1 AND ordinal = ?3",
+                    rusqlite::params![from, into, ordinal],
+                )?;
+                op.track_inserted("card_prompts", conn.last_insert_rowid());
+            }
+        }
+    }
+    // Tracked after the prompts inserted above, so undo puts the history back before deleting them.
+    op.track(conn, "review_log", "card_id = ?1", rusqlite::params![from])?;
+    conn.execute(
+        "UPDATE review_log SET card_id = ?2, prompt_id = p.id
+         FROM card_prompts src JOIN card_prompts p ON p.card_id = ?2 AND p.ordinal = src.ordinal
+         WHERE review_log.card_id = ?1 AND src.id = review_log.prompt_id",
+        rusqlite::params![from, into],
+    )?;
+    Ok(())
+}
+
+/// A prompt as shown to the learner.
+pub(crate) struct Rendered {
+    pub(crate) front: String,
+    pub(crate) back: String,
+    /// What the learner should come up with: the answer, the question for a reverse prompt, or the
+    /// hidden text of a cloze.
+    pub(crate) expected: String,
+    /// Answer and distractors in a stable shuffled order; empty unless multiple choice.
+    pub(crate) choices: Vec<String>,
+}
+
+/// FNV-1a, for a shuffle that stays the same between calls.
+fn stable_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+pub(crate) fn render(card_type: CardType, question: &str, answer: &str, choices: &[String], ordinal: i64) -> Rendered {
+    match card_type {
+        CardType::Basic => Rendered { front: question.to_string(), back: answer.to_string(), expected: answer.to_string(), choices: Vec::new() },
+        CardType::Reverse if ordinal == 1 => {
+            Rendered { front: answer.to_string(), back: question.to_string(), expected: question.to_string(), choices: Vec::new() }
+        }
+        CardType::Reverse => Rendered { front: question.to_string(), back: answer.to_string(), expected: answer.to_string(), choices: Vec::new() },
+        CardType::MultipleChoice => {
+            let mut options: Vec<String> = std::iter::once(answer.to_string()).chain(choices.iter().cloned()).collect();
+            options.sort_by_key(|option| stable_hash(&format!("{question}\n{option}")));
+            Rendered { front: question.to_string(), back: answer.to_string(), expected: answer.to_string(), choices: options }
+        }
+        CardType::Cloze => {
+            let mut hidden: Vec<String> = Vec::new();
+            let front = cloze_regex().replace_all(question, |c: &regex::Captures| {
+                if c[1].parse::<i64>().ok() == Some(ordinal) {
+                    hidden.push(c[2].to_string());
+                    match c.get(3) {
+                        Some(hint) => format!("[{}]", hint.as_str()),
+                        None => "[...]".to_string(),
+                    }
+                } else {
+                    c[2].to_string()
+                }
+            });
+            let revealed = cloze_regex().replace_all(question, "$2");
+            let back = if answer.trim().is_empty() { revealed.into_owned() } else { format!("{revealed}\n\n{answer}") };
+            Rendered { front: front.into_owned(), back, expected: hidden.join(", "), choices: Vec::new() }
+        }
+    }
+}
+
+/// Distractors stored for a card; malformed JSON reads as none.
+pub(crate) fn parse_choices(json: Option<&str>) -> Vec<String> {
+    json.and_then(|j| serde_json::from_str(j).ok()).unwrap_or_default()
+}
+
+/// Distractors as stored: a JSON array, or NULL when there are none.
+pub(crate) fn choices_json(choices: &[String]) -> Result<Option<String>, AppError> {
+    if choices.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::to_string(choices)?))
+}
+
+/// Check that the card's text fits the type, returning the distractors to store (JSON).
+pub(crate) fn check_card_type(card_type: CardType, question: &str, answer: &str, choices: Option<Vec<String>>) -> Result<Option<String>, AppError> {
+    match card_type {
+        CardType::Cloze if cloze_numbers(question).is_empty() => Err(AppError::validation(
+            "question",
+            "A cloze card needs at least one deletion in the question, e.g. {{c1::Paris}}",
+        )),
+        CardType::MultipleChoice => {
+            let mut distractors: Vec<String> = Vec::new();
+            for choice in choices.unwrap_or_default() {
+                let choice = choice.trim().to_string();
+                if !choice.is_empty() && choice != answer.trim() && !distractors.contains(&choice) {
+                    distractors.push(choice);
+                }
+            }
+            if distractors.is_empty() {
+                return Err(AppError::validation("choices", "A multiple choice card needs at least one wrong answer"));
+            }
+            choices_json(&distractors)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Change a card's type. `choices` are the wrong answers for a multiple choice card and are ignored
+/// otherwise. Changing to or from cloze resets the card's review state, since the prompts no longer
+/// correspond.
+#[tauri::command]
+pub fn set_card_type(app: tauri::AppHandle, card_id: i64, card_type: CardType, choices: Option<Vec<String>>) -> Result<(), AppError> {
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    let (question, answer, current): (String, String, String) = tx
+        .query_row(
+            "SELECT question, answer, card_type FROM cards WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![card_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|_| AppError::not_found("Card not found"))?;
+    let choices = check_card_type(card_type, &question, &answer, choices)?;
+    let current = CardType::parse(&current);
+
+    let mut op = journal::Operation::new(format!("Change type of card \"{}\"", crate::truncate_label(&question)));
+    if (current == CardType::Cloze) != (card_type == CardType::Cloze) {
+        op.track(&tx, "review_log", "card_id = ?1", rusqlite::params![card_id])?;
+        op.track(&tx, "card_prompts", "card_id = ?1", rusqlite::params![card_id])?;
+        tx.execute("DELETE FROM card_prompts WHERE card_id = ?1", rusqlite::params![card_id])?;
+    }
+    op.track(&tx, "cards", "id = ?1", rusqlite::params![card_id])?;
+    tx.execute(
+        "UPDATE cards SET card_type = ?1, choices = ?2 WHERE id = ?3",
+        rusqlite::params![card_type.as_str(), choices, card_id],
+    )?;
+    sync_card_prompts(&tx, Some(&mut op), card_id)?;
+    op.commit(&tx)?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct StudyPrompt {
+    /// Prompt id, for recording reviews.
+    pub(crate) id: i64,
+    card_id: i64,
+    ordinal: i64,
+    card_type: CardType,
+    front: String,
+    back: String,
+    expected: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    choices: Vec<String>,
+    hint: String,
+    /// The card as stored, for editing it from study.
+    question: String,
+    answer: String,
+    pub(crate) sub_collection_id: i64,
+    pub(crate) state: String,
+    pub(crate) due: Option<String>,
+    pub(crate) reps: i64,
+    pub(crate) lapses: i64,
+    pub(crate) last_reviewed_at: Option<String>,
+}
+
+/// Prompts of the collection's live cards that are not suspended or buried, rendered, in creation order; with
+/// `sub_collection_id`, only cards in that sub collection or below it.
+pub(crate) fn study_prompts(conn: &rusqlite::Connection, collection_id: i64, sub_collection_id: Option<i64>) -> Result<Vec<StudyPrompt>, AppError> {
+    crate::suspend::unbury_expired(conn)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT p.id, c.id, p.ordinal, c.card_type, c.question, c.answer, c.choices, COALESCE(c.hint, ''), c.sub_collection_id, p.state, p.due,
+                p.reps, p.lapses, p.last_reviewed_at
+         FROM card_prompts p JOIN cards c ON c.id = p.card_id
+         WHERE c.collection_id = ?1 AND c.deleted_at IS NULL AND c.suspended_at IS NULL AND c.buried_at IS NULL
+           AND (?2 IS NULL OR c.sub_collection_id IN {})
+         ORDER BY c.created_at, c.id, p.ordinal",
+        crate::hierarchy::subtree("?2")
+    ))?;
+    let rows = stmt.query_map(rusqlite::params![collection_id, sub_collection_id], |row| {
+        let card_type = CardType::parse(&row.get::<_, String>(3)?);
+        let ordinal: i64 = row.get(2)?;
+        let question: String = row.get(4)?;
+        let answer: String = row.get(5)?;
+        let choices = parse_choices(row.get::<_, Option<String>>(6)?.as_deref());
+        let rendered = render(card_type, &question, &answer, &choices, ordinal);
+        Ok(StudyPrompt {
+            id: row.get(0)?,
+            card_id: row.get(1)?,
+            ordinal,
+            card_type,
+            front: rendered.front,
+            back: rendered.back,
+            expected: rendered.expected,
+            choices: rendered.choices,
+            hint: row.get(7)?,
+            question,
+            answer,
+            sub_collection_id: row.get(8)?,
+            state: row.get(9)?,
+            due: row.get(10)?,
+            reps: row.get(11)?,
+            lapses: row.get(12)?,
+            last_reviewed_at: row.get(13)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompts_follow_the_type() {
+        let cloze = "{{c2::Paris}} is the capital of {{c1::France::country}}, on the {{c2::Seine}}";
+        assert_eq!(cloze_numbers(cloze), vec![1, 2]);
+        assert_eq!(prompt_ordinals(CardType::Cloze, cloze), vec![1, 2]);
+        assert_eq!(prompt_ordinals(CardType::Reverse, cloze), vec![0, 1]);
+        assert_eq!(prompt_ordinals(CardType::MultipleChoice, "Q"), vec![0]);
+        assert!(prompt_ordinals(CardType::Cloze, "no deletions").is_empty());
+        assert!(CardType::parse("from_the_future") == CardType::Basic);
+    }
+
+    #[test]
+    fn cloze_hides_only_its_number() {
+        let cloze = "{{c2::Paris}} is the capital of {{c1::France::country}}, on the {{c2::Seine}}";
+        let first = render(CardType::Cloze, cloze, "", &[], 1);
+        assert_eq!(first.front, "Paris is the capital of [country], on the Seine");
+        assert_eq!(first.back, "Paris is the capital of France, on the Seine");
+        assert_eq!(first.expected, "France");
+        let second = render(CardType::Cloze, cloze, "Since 508", &[], 2);
+        assert_eq!(second.front, "[...] is the capital of France, on the [...]");
+        assert_eq!(second.back, "Paris is the capital of France, on the Seine\n\nSince 508");
+        assert_eq!(second.expected, "Paris, Seine");
+    }
+
+    #[test]
+    fn reverse_and_multiple_choice_render() {
+        let reverse = render(CardType::Reverse, "Q", "A", &[], 1);
+        assert_eq!((reverse.front.as_str(), reverse.expected.as_str()), ("A", "Q"));
+        let choices = vec!["B".to_string(), "C".to_string()];
+        let first = render(CardType::MultipleChoice, "Q", "A", &choices, 0);
+        let mut sorted = first.choices.clone();
+        sorted.sort();
+        assert_eq!(sorted, vec!["A", "B", "C"]);
+        assert_eq!(render(CardType::MultipleChoice, "Q", "A", &choices, 0).choices, first.choices);
+    }
+
+    #[test]
+    fn card_text_must_fit_the_type() {
+        assert!(check_card_type(CardType::Cloze, "no deletions", "", None).is_err());
+        assert_eq!(check_card_type(CardType::Cloze, "{{c1::x}}", "", None).unwrap(), None);
+        let choices = vec![" B ".to_string(), "A".to_string(), "B".to_string(), "".to_string()];
+        assert_eq!(check_card_type(CardType::MultipleChoice, "Q", "A", Some(choices)).unwrap().as_deref(), Some(r#"["B"]"#));
+        assert!(check_card_type(CardType::MultipleChoice, "Q", "A", Some(vec!["A".to_string()])).is_err());
+        assert_eq!(parse_choices(Some("not json")), Vec::<String>::new());
+    }
+
+    #[test]
+    fn removing_a_cloze_keeps_its_reviews_and_undo_restores_the_prompt() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A');
+             INSERT INTO sub_collections (id, name, collection_id) VALUES (1, '- None -', 1);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id, card_type) VALUES
+                 (1, '{{c1::Paris}} on the {{c2::Seine}}', '', 1, 1, 'cloze');",
+        )
+        .unwrap();
+        fill_missing_prompts(&conn).unwrap();
+        let second: i64 = conn.query_row("SELECT id FROM card_prompts WHERE card_id = 1 AND ordinal = 2", [], |r| r.get(0)).unwrap();
+        conn.execute(
+            "INSERT INTO review_log (prompt_id, card_id, grade, state_before) VALUES (?1, 1, 'good', 'new')",
+            rusqlite::params![second],
+        )
+        .unwrap();
+        let ordinals = |conn: &rusqlite::Connection| -> Vec<i64> {
+            conn.prepare("SELECT ordinal FROM card_prompts WHERE card_id = 1 ORDER BY ordinal")
+                .unwrap()
+                .query_map([], |r| r.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        let logged = |conn: &rusqlite::Connection| -> Option<i64> {
+            conn.query_row("SELECT prompt_id FROM review_log WHERE card_id = 1", [], |r| r.get(0)).unwrap()
+        };
+
+        let mut op = journal::Operation::new("Edit");
+        op.track(&conn, "cards", "id = 1", []).unwrap();
+        conn.execute("UPDATE cards SET question = '{{c1::Paris}} on the Seine' WHERE id = 1", []).unwrap();
+        sync_card_prompts(&conn, Some(&mut op), 1).unwrap();
+        op.commit(&conn).unwrap();
+        assert_eq!(ordinals(&conn), vec![1]);
+        assert_eq!(logged(&conn), None);
+
+        journal::apply_next(&conn, true).unwrap().unwrap();
+        assert_eq!(ordinals(&conn), vec![1, 2]);
+        assert_eq!(logged(&conn), Some(second));
+
+        // Switching to a plain card replaces the prompts, and the reviews still stay.
+        conn.execute("UPDATE cards SET card_type = 'reverse' WHERE id = 1", []).unwrap();
+        sync_card_prompts(&conn, None, 1).unwrap();
+        assert_eq!(ordinals(&conn), vec![0, 1]);
+        let reviews: i64 = conn.query_row("SELECT COUNT(*) FROM review_log", [], |r| r.get(0)).unwrap();
+        assert_eq!(reviews, 1);
+    }
+}
@@ -0,0 +1,264 @@
+//! Encrypted storage for LLM provider API keys.
+//!
+//! Keys are sealed with XChaCha20-Poly1305 and written to `credentials.json` in the app data dir.
+//! The master key lives in the OS keychain when one is reachable; otherwise (e.g. headless Linux
+//! without a Secret Service) it falls back to a `credentials.key` file readable only by the user. A
+//! keychain that is there but fails (e.g. locked) is an error rather than a reason to make a new key.
+//! Plaintext keys never leave the backend: the frontend can set, test, delete and query status only.
+
+use crate::error::AppError;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+const CREDENTIALS_FILE: &str = "credentials.json";
+const MASTER_KEY_FILE: &str = "credentials.key";
+const KEYRING_SERVICE: &str = "flashcard-beast";
+const KEYRING_USER: &str = "credentials-master-key";
+
+/// Providers we know how to store keys for, with the environment variable checked as a fallback.
+const PROVIDERS: &[(&str, &str)] = &[("ollama", "OLLAMA_API_KEY")];
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SealedKey {
+    nonce: String,
+    ciphertext: String,
+}
+
+type SealedKeys = std::collections::BTreeMap<String, SealedKey>;
+
+#[derive(serde::Serialize)]
+pub struct CredentialStatus {
+    provider: String,
+    configured: bool,
+    /// "stored" when an encrypted key is saved, "environment" when only the env var is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+}
+
+fn env_var_for(provider: &str) -> Result<&'static str, AppError> {
+    PROVIDERS
+        .iter()
+        .find(|(name, _)| *name == provider)
+        .map(|(_, var)| *var)
+        .ok_or_else(|| AppError::validation("provider", format!("Unknown provider: {provider}")))
+}
+
+fn credentials_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
+    Ok(crate::app_data_dir(app)?.join(CREDENTIALS_FILE))
+}
+
+fn read_sealed_keys(app: &tauri::AppHandle) -> Result<SealedKeys, AppError> {
+    let path = credentials_path(app)?;
+    if !path.exists() {
+        return Ok(SealedKeys::new());
+    }
+    let json = std::fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn write_sealed_keys(app: &tauri::AppHandle, keys: &SealedKeys) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(keys)?;
+    write_private_file(&credentials_path(app)?, json.as_bytes())
+}
+
+/// Write a file that only the current user can read (0600 on Unix).
+fn write_private_file(path: &std::path::Path, contents: &[u8]) -> Result<(), AppError> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    Ok(())
+}
+
+fn decode_master_key(encoded: &str) -> Result<Key, AppError> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(AppError::io)?;
+    if bytes.len() != 32 {
+        return Err(AppError::io("Stored master key has an invalid length"));
+    }
+    Ok(*Key::from_slice(&bytes))
+}
+
+/// Load the master key from the OS keychain or the fallback file, creating one on first use.
+fn master_key(app: &tauri::AppHandle) -> Result<Key, AppError> {
+    let key_file = crate::app_data_dir(app)?.join(MASTER_KEY_FILE);
+    let keyring_entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).ok();
+    let stored = keyring_entry.as_ref().map(|entry| entry.get_password());
+    load_master_key(stored, &key_file, |encoded| {
+        keyring_entry.is_some_and(|entry| entry.set_password(encoded).is_ok())
+    })
+}
+
+/// The master key from what the keychain returned (`None` when there is no keychain) or the fallback
+/// file. A new key is created only when the keychain has no entry and there is no file: any other
+/// keychain error (locked, unreachable) is returned, since a new key would orphan the sealed ones.
+/// `store` saves a new key in the keychain and reports whether it could; otherwise it goes to the file.
+fn load_master_key(
+    stored: Option<keyring::Result<String>>,
+    key_file: &std::path::Path,
+    store: impl FnOnce(&str) -> bool,
+) -> Result<Key, AppError> {
+    match stored {
+        Some(Ok(encoded)) => return decode_master_key(&encoded),
+        Some(Err(keyring::Error::NoEntry)) | None => {}
+        Some(Err(e)) if !key_file.exists() => {
+            return Err(AppError::io(format!("Cannot read the master key from the keychain: {e}")));
+        }
+        Some(Err(_)) => {}
+    }
+    if key_file.exists() {
+        let encoded = std::fs::read_to_string(key_file)?;
+        return decode_master_key(&encoded);
+    }
+
+    let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+    let encoded = base64::engine::general_purpose::STANDARD.encode(key);
+    if !store(&encoded) {
+        write_private_file(key_file, encoded.as_bytes())?;
+    }
+    Ok(key)
+}
+
+fn seal(key: &Key, provider: &str, secret: &str) -> Result<SealedKey, AppError> {
+    let cipher = XChaCha20Poly1305::new(key);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: secret.as_bytes(), aad: provider.as_bytes() })
+        .map_err(|_| AppError::io("Failed to encrypt API key"))?;
+    let engine = base64::engine::general_purpose::STANDARD;
+    Ok(SealedKey {
+        nonce: engine.encode(nonce),
+        ciphertext: engine.encode(ciphertext),
+    })
+}
+
+fn open(key: &Key, provider: &str, sealed: &SealedKey) -> Result<String, AppError> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let nonce = engine.decode(&sealed.nonce).map_err(AppError::io)?;
+    let ciphertext = engine.decode(&sealed.ciphertext).map_err(AppError::io)?;
+    if nonce.len() != 24 {
+        return Err(AppError::io("Stored API key is corrupted"));
+    }
+    let cipher = XChaCha20Poly1305::new(key);
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: provider.as_bytes() })
+        .map_err(|_| AppError::io("Stored API key could not be decrypted"))?;
+    String::from_utf8(plaintext).map_err(AppError::io)
+}
+
+/// Returns the API key for a provider: the stored key if present, else the provider's env var.
+/// Backend-only; never expose the result to the frontend.
+pub fn api_key(app: &tauri::AppHandle, provider: &str) -> Result<Option<String>, AppError> {
+    let env_var = env_var_for(provider)?;
+    if let Some(sealed) = read_sealed_keys(app)?.get(provider) {
+        return open(&master_key(app)?, provider, sealed).map(Some);
+    }
+    Ok(std::env::var(env_var).ok().filter(|k| !k.trim().is_empty()))
+}
+
+#[tauri::command]
+pub fn set_api_key(app: tauri::AppHandle, provider: String, api_key: String) -> Result<CredentialStatus, AppError> {
+    env_var_for(&provider)?;
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err(AppError::validation("apiKey", "API key cannot be empty"));
+    }
+    let sealed = seal(&master_key(&app)?, &provider, api_key)?;
+    let mut keys = read_sealed_keys(&app)?;
+    keys.insert(provider.clone(), sealed);
+    write_sealed_keys(&app, &keys)?;
+    get_credential_status(app, provider)
+}
+
+#[tauri::command]
+pub fn delete_api_key(app: tauri::AppHandle, provider: String) -> Result<CredentialStatus, AppError> {
+    env_var_for(&provider)?;
+    let mut keys = read_sealed_keys(&app)?;
+    if keys.remove(&provider).is_some() {
+        write_sealed_keys(&app, &keys)?;
+    }
+    get_credential_status(app, provider)
+}
+
+/// Report whether a key is configured for the provider, without revealing it.
+#[tauri::command]
+pub fn get_credential_status(app: tauri::AppHandle, provider: String) -> Result<CredentialStatus, AppError> {
+    let env_var = env_var_for(&provider)?;
+    let source = if read_sealed_keys(&app)?.contains_key(&provider) {
+        Some("stored".to_string())
+    } else if std::env::var(env_var).map(|k| !k.trim().is_empty()).unwrap_or(false) {
+        Some("environment".to_string())
+    } else {
+        None
+    };
+    Ok(CredentialStatus {
+        provider,
+        configured: source.is_some(),
+        source,
+    })
+}
+
+/// Check the configured key against the provider; errors if it is missing or rejected.
+#[tauri::command]
+pub async fn test_api_key(app: tauri::AppHandle, provider: String) -> Result<(), AppError> {
+    let key = api_key(&app, &provider)?.ok_or_else(|| AppError::not_found(format!("No API key configured for {provider}")))?;
+    match provider.as_str() {
+        "ollama" => crate::llm::check_ollama_cloud_key(&key).await,
+        _ => Err(AppError::validation("provider", format!("Unknown provider: {provider}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_key_opens_only_for_its_provider() {
+        let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+        let sealed = seal(&key, "ollama", "secret").unwrap();
+        assert_eq!(open(&key, "ollama", &sealed).unwrap(), "secret");
+        assert!(open(&key, "other", &sealed).is_err());
+        let other_key = XChaCha20Poly1305::generate_key(&mut OsRng);
+        assert!(open(&other_key, "ollama", &sealed).is_err());
+    }
+
+    #[test]
+    fn master_key_must_be_32_bytes() {
+        let engine = base64::engine::general_purpose::STANDARD;
+        assert!(decode_master_key(&format!("{}\n", engine.encode([7u8; 32]))).is_ok());
+        assert!(decode_master_key(&engine.encode([7u8; 16])).is_err());
+        assert!(decode_master_key("not base64!").is_err());
+    }
+
+    #[test]
+    fn keychain_failures_do_not_replace_the_key() {
+        let key_file = std::env::temp_dir().join(format!("credentials-key-{}", std::process::id()));
+        let locked = || Some(Err(keyring::Error::NoStorageAccess("locked".into())));
+        assert!(load_master_key(locked(), &key_file, |_| panic!("must not create a key")).is_err());
+        assert!(!key_file.exists());
+
+        let created = load_master_key(Some(Err(keyring::Error::NoEntry)), &key_file, |_| false).unwrap();
+        assert!(key_file.exists());
+        assert_eq!(load_master_key(locked(), &key_file, |_| panic!("must not create a key")).unwrap(), created);
+        assert_eq!(load_master_key(None, &key_file, |_| panic!("must not create a key")).unwrap(), created);
+        std::fs::remove_file(&key_file).unwrap();
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let stored = engine.encode([7u8; 32]);
+        assert_eq!(load_master_key(Some(Ok(stored)), &key_file, |_| panic!("must not create a key")).unwrap(), *Key::from_slice(&[7u8; 32]));
+    }
+
+    #[test]
+    fn unknown_providers_are_rejected() {
+        assert_eq!(env_var_for("ollama").unwrap(), "OLLAMA_API_KEY");
+        assert!(env_var_for("nope").is_err());
+    }
+}
@@ -0,0 +1,247 @@
+//! Duplicate and near-duplicate cards.
+//!
+//! Cards are compared on their normalized question and answer: lowercased, accents and punctuation
+//! dropped and whitespace collapsed. Similarity is the Jaccard index of the character trigrams of that
+//! text, so "What is the capital of France?" and "what's the capital of france" score high while
+//! sharing few exact words. Cards similar enough to each other, directly or through a chain of similar
+//! cards, form a cluster.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::AppError;
+use crate::journal;
+use crate::text::normalize;
+
+/// Similarity at or above which two cards count as duplicates when the caller gives no threshold.
+const DEFAULT_THRESHOLD: f64 = 0.8;
+
+/// Character trigrams of normalized text, padded so short words still produce some.
+fn trigrams(normalized: &str) -> HashSet<[char; 3]> {
+    let chars: Vec<char> = format!("  {normalized} ").chars().collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+fn jaccard(a: &HashSet<[char; 3]>, b: &HashSet<[char; 3]>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// Union-find root of `i`, halving paths on the way.
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+#[derive(serde::Serialize)]
+pub struct DuplicateCard {
+    id: i64,
+    question: String,
+    answer: String,
+    hint: String,
+    sub_collection_id: i64,
+    sub_collection_path: String,
+    created_at: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct DuplicateCluster {
+    /// Oldest first, so the first card is the natural one to keep.
+    cards: Vec<DuplicateCard>,
+    /// Lowest similarity among the pairs that joined the cluster; 1 when every card is an exact
+    /// normalized match.
+    similarity: f64,
+}
+
+/// Clusters of live cards in the collection that look like the same card. `threshold` is the lowest
+/// similarity, from 0 to 1, for two cards to be linked (default 0.8). Clusters are returned largest
+/// first; cards without a duplicate are left out.
+#[tauri::command]
+pub fn find_duplicates(app: tauri::AppHandle, collection_id: i64, threshold: Option<f64>) -> Result<Vec<DuplicateCluster>, AppError> {
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(AppError::validation("threshold", "Similarity must be above 0 and at most 1"));
+    }
+    let conn = crate::open_db(&app)?;
+    let paths: HashMap<i64, String> = crate::hierarchy::sub_collection_paths(&conn, collection_id)?
+        .into_iter()
+        .map(|s| (s.id, s.path))
+        .collect();
+    let mut stmt = conn.prepare(
+        "SELECT id, question, answer, COALESCE(hint, ''), sub_collection_id, created_at FROM cards
+         WHERE collection_id = ?1 AND deleted_at IS NULL ORDER BY created_at, id",
+    )?;
+    let cards: Vec<DuplicateCard> = stmt
+        .query_map(rusqlite::params![collection_id], |row| {
+            let sub_collection_id: i64 = row.get(4)?;
+            Ok(DuplicateCard {
+                id: row.get(0)?,
+                question: row.get(1)?,
+                answer: row.get(2)?,
+                hint: row.get(3)?,
+                sub_collection_id,
+                sub_collection_path: paths.get(&sub_collection_id).cloned().unwrap_or_default(),
+                created_at: row.get(5)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(cluster(cards, threshold))
+}
+
+/// Group cards, oldest first, into clusters of cards linked at `threshold` or above, largest first.
+fn cluster(cards: Vec<DuplicateCard>, threshold: f64) -> Vec<DuplicateCluster> {
+    let texts: Vec<String> = cards.iter().map(|c| normalize(&format!("{} {}", c.question, c.answer))).collect();
+    let grams: Vec<HashSet<[char; 3]>> = texts.iter().map(|t| trigrams(t)).collect();
+    let mut parents: Vec<usize> = (0..cards.len()).collect();
+    // Lowest linking similarity per cluster root.
+    let mut weakest: HashMap<usize, f64> = HashMap::new();
+    for i in 0..cards.len() {
+        for j in i + 1..cards.len() {
+            let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+            if a == b {
+                continue;
+            }
+            // Jaccard is at most the ratio of the set sizes, so skip pairs that cannot reach the threshold.
+            let (small, large) = (grams[i].len().min(grams[j].len()), grams[i].len().max(grams[j].len()));
+            if large > 0 && (small as f64 / large as f64) < threshold {
+                continue;
+            }
+            let similarity = if texts[i] == texts[j] { 1.0 } else { jaccard(&grams[i], &grams[j]) };
+            if similarity < threshold {
+                continue;
+            }
+            let linked = similarity.min(weakest.remove(&a).unwrap_or(1.0)).min(weakest.remove(&b).unwrap_or(1.0));
+            parents[b] = a;
+            weakest.insert(a, linked);
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<DuplicateCard>> = HashMap::new();
+    let roots: Vec<usize> = (0..cards.len()).map(|i| root(&mut parents, i)).collect();
+    for (card, r) in cards.into_iter().zip(roots) {
+        members.entry(r).or_default().push(card);
+    }
+    let mut clusters: Vec<DuplicateCluster> = members
+        .into_iter()
+        .filter(|(_, cards)| cards.len() > 1)
+        .map(|(r, cards)| DuplicateCluster { cards, similarity: weakest.get(&r).copied().unwrap_or(1.0) })
+        .collect();
+    clusters.sort_by(|a, b| b.cards.len().cmp(&a.cards.len()).then(a.cards[0].id.cmp(&b.cards[0].id)));
+    clusters
+}
+
+#[derive(serde::Serialize)]
+pub struct DuplicateMergeReport {
+    kept: i64,
+    merged: u32,
+}
+
+/// Fold `card_ids` into `keep_id` and put them in the trash, as one undo entry. The kept card takes
+/// any hint, source or review state it lacks from them, and notes that produced one of them now point
+/// at the kept card.
+#[tauri::command]
+pub fn merge_duplicate_cards(app: tauri::AppHandle, keep_id: i64, card_ids: Vec<i64>) -> Result<DuplicateMergeReport, AppError> {
+    crate::backup::snapshot(&app, "merge-duplicates")?;
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    let collection_id: i64 = tx
+        .query_row(
+            "SELECT collection_id FROM cards WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![keep_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::not_found("Card not found"))?;
+    let mut merge_ids: Vec<i64> = Vec::new();
+    for id in card_ids {
+        if id != keep_id && !merge_ids.contains(&id) {
+            merge_ids.push(id);
+        }
+    }
+    if merge_ids.is_empty() {
+        return Err(AppError::validation("cardIds", "Choose at least one other card to merge"));
+    }
+
+    let noun = if merge_ids.len() == 1 { "card" } else { "cards" };
+    let mut op = journal::Operation::new(format!("Merge {} duplicate {noun}", merge_ids.len()));
+    let now: String = tx.query_row("SELECT datetime('now')", [], |row| row.get(0))?;
+    for &id in &merge_ids {
+        let same_collection: bool = tx
+            .query_row(
+                "SELECT collection_id = ?2 FROM cards WHERE id = ?1 AND deleted_at IS NULL",
+                rusqlite::params![id, collection_id],
+                |row| row.get(0),
+            )
+            .map_err(|_| AppError::not_found(format!("Card {id} not found")))?;
+        if !same_collection {
+            return Err(AppError::validation("cardIds", format!("Card {id} is in another collection")));
+        }
+        crate::transfer::merge_into(&tx, &mut op, id, keep_id)?;
+        op.track(&tx, "note_cards", "card_id = ?1", rusqlite::params![id])?;
+        tx.execute("UPDATE note_cards SET card_id = ?1 WHERE card_id = ?2", rusqlite::params![keep_id, id])?;
+        op.track(&tx, "cards", "id = ?1", rusqlite::params![id])?;
+        tx.execute("UPDATE cards SET deleted_at = ?1 WHERE id = ?2", rusqlite::params![now, id])?;
+    }
+    op.commit(&tx)?;
+    tx.commit()?;
+    Ok(DuplicateMergeReport { kept: keep_id, merged: merge_ids.len() as u32 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(texts: &[(&str, &str)]) -> Vec<DuplicateCard> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, (question, answer))| DuplicateCard {
+                id: i as i64 + 1,
+                question: question.to_string(),
+                answer: answer.to_string(),
+                hint: String::new(),
+                sub_collection_id: 1,
+                sub_collection_path: String::new(),
+                created_at: String::new(),
+            })
+            .collect()
+    }
+
+    fn ids(clusters: &[DuplicateCluster]) -> Vec<Vec<i64>> {
+        clusters.iter().map(|c| c.cards.iter().map(|card| card.id).collect()).collect()
+    }
+
+    #[test]
+    fn similar_text_shares_most_trigrams() {
+        let a = trigrams(&normalize("What is the capital of France? Paris"));
+        let b = trigrams(&normalize("what's the capital of france  Paris"));
+        let c = trigrams(&normalize("Who wrote Hamlet? Shakespeare"));
+        assert!(jaccard(&a, &b) >= DEFAULT_THRESHOLD);
+        assert!(jaccard(&a, &c) < 0.2);
+        assert_eq!(jaccard(&a, &a), 1.0);
+        assert!(trigrams("a").contains(&[' ', ' ', 'a']));
+    }
+
+    #[test]
+    fn clusters_form_through_chains_and_largest_come_first() {
+        let clusters = cluster(
+            cards(&[
+                ("Who wrote Hamlet?", "Shakespeare"),
+                ("What is the capital of France?", "Paris"),
+                ("who wrote hamlet", "Shakespeare"),
+                ("What's the capital of France", "Paris"),
+                ("What is the capital of France", "Paris."),
+                ("Unrelated", "card"),
+            ]),
+            DEFAULT_THRESHOLD,
+        );
+        assert_eq!(ids(&clusters), vec![vec![2, 4, 5], vec![1, 3]]);
+        assert!(clusters[0].similarity >= DEFAULT_THRESHOLD && clusters[0].similarity < 1.0);
+        assert_eq!(clusters[1].similarity, 1.0);
+        assert!(cluster(cards(&[("Who wrote Hamlet?", "Shakespeare"), ("Who wrote Macbeth?", "Shakespeare")]), 0.95).is_empty());
+    }
+}
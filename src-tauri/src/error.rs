@@ -0,0 +1,187 @@
+//! Error type returned by every command.
+//!
+//! Serialized as `{ "code": "not_found", "message": "…" }`, plus `"field"` for validation errors, so
+//! the UI can branch on `code` and show or translate `message`.
+
+#[derive(Debug)]
+pub enum AppError {
+    /// The requested row, file or setting does not exist.
+    NotFound(String),
+    /// A row with the same unique key (collection name, card text, …) already exists.
+    Duplicate(String),
+    /// An argument is invalid; `field` names it in the command's camelCase form, e.g. `subCollectionId`.
+    Validation { field: String, message: String },
+    /// Reading or writing a file, or talking to another program or service, failed.
+    Io(String),
+    /// SQLite reported an error.
+    Db(String),
+    /// The name or row is reserved by the app, e.g. the default sub collection.
+    Reserved(String),
+    /// The data changed since the operation was prepared, e.g. rows edited after an undo entry was recorded.
+    Conflict(String),
+}
+
+impl AppError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound(message.into())
+    }
+
+    pub fn validation(field: &str, message: impl Into<String>) -> Self {
+        AppError::Validation { field: field.to_string(), message: message.into() }
+    }
+
+    pub fn io(message: impl std::fmt::Display) -> Self {
+        AppError::Io(message.to_string())
+    }
+
+    /// The same error with `context` put in front of its message, e.g. `Cannot undo "Delete card": …`.
+    pub fn context(self, context: impl std::fmt::Display) -> Self {
+        let prefix = |message: String| format!("{context}: {message}");
+        match self {
+            AppError::NotFound(message) => AppError::NotFound(prefix(message)),
+            AppError::Duplicate(message) => AppError::Duplicate(prefix(message)),
+            AppError::Validation { field, message } => AppError::Validation { field, message: prefix(message) },
+            AppError::Io(message) => AppError::Io(prefix(message)),
+            AppError::Db(message) => AppError::Db(prefix(message)),
+            AppError::Reserved(message) => AppError::Reserved(prefix(message)),
+            AppError::Conflict(message) => AppError::Conflict(prefix(message)),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Duplicate(_) => "duplicate",
+            AppError::Validation { .. } => "validation",
+            AppError::Io(_) => "io",
+            AppError::Db(_) => "db",
+            AppError::Reserved(_) => "reserved",
+            AppError::Conflict(_) => "conflict",
+        }
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::NotFound(message)
+            | AppError::Duplicate(message)
+            | AppError::Validation { message, .. }
+            | AppError::Io(message)
+            | AppError::Db(message)
+            | AppError::Reserved(message)
+            | AppError::Conflict(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl serde::Serialize for AppError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let field = match self {
+            AppError::Validation { field, .. } => Some(field),
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("AppError", 2 + field.is_some() as usize)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(field) = field {
+            state.serialize_field("field", field)?;
+        }
+        state.end()
+    }
+}
+
+/// Friendly message for a UNIQUE violation, from the table or index SQLite names in its message.
+fn duplicate_message(detail: &str) -> String {
+    if detail.contains("sub_collections") {
+        "A sub collection with this name already exists here.".to_string()
+    } else if detail.contains("collections.name") {
+        "A collection with this name already exists.".to_string()
+    } else if detail.contains("cards") {
+        "A card with this question and answer already exists in this sub-collection.".to_string()
+    } else {
+        detail.to_string()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        match &e {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound("Not found".to_string()),
+            rusqlite::Error::SqliteFailure(failure, detail)
+                if failure.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
+                    || failure.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_PRIMARYKEY =>
+            {
+                AppError::Duplicate(duplicate_message(detail.as_deref().unwrap_or_default()))
+            }
+            _ => AppError::Db(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::NotFound(e.to_string()),
+            _ => AppError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Io(format!("Invalid JSON: {e}"))
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        AppError::Io(e.to_string())
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for AppError {
+    fn from(e: std::sync::PoisonError<T>) -> Self {
+        AppError::Io(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_serialize_with_code_message_and_field() {
+        assert_eq!(
+            serde_json::to_value(AppError::not_found("No card")).unwrap(),
+            serde_json::json!({ "code": "not_found", "message": "No card" })
+        );
+        assert_eq!(
+            serde_json::to_value(AppError::validation("subCollectionId", "Wrong").context("Cannot move")).unwrap(),
+            serde_json::json!({ "code": "validation", "message": "Cannot move: Wrong", "field": "subCollectionId" })
+        );
+    }
+
+    #[test]
+    fn unique_violations_become_duplicates() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute("INSERT INTO collections (name) VALUES ('Bio')", []).unwrap();
+        let error = AppError::from(conn.execute("INSERT INTO collections (name) VALUES ('Bio')", []).unwrap_err());
+        assert_eq!(error.code(), "duplicate");
+        assert_eq!(error.to_string(), "A collection with this name already exists.");
+        let error = AppError::from(conn.execute("INSERT INTO collections (nope) VALUES (1)", []).unwrap_err());
+        assert_eq!(error.code(), "db");
+        let error = AppError::from(conn.query_row("SELECT 1 WHERE 0", [], |row| row.get::<_, i64>(0)).unwrap_err());
+        assert_eq!(error.code(), "not_found");
+    }
+
+    #[test]
+    fn missing_files_are_not_found() {
+        assert_eq!(AppError::from(std::fs::read("/no/such/file").unwrap_err()).code(), "not_found");
+        assert_eq!(AppError::from(serde_json::from_str::<i64>("x").unwrap_err()).code(), "io");
+    }
+}
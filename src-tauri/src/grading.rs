@@ -0,0 +1,239 @@
+//! Checking typed answers.
+//!
+//! `check_answer` compares what the learner typed with what the prompt expects. Both sides are
+//! normalized (case, whitespace, punctuation and diacritics don't count) and only an exact match is
+//! correct. Otherwise the score is the character edit similarity, or for the right words in another
+//! order at most the `Hard` threshold, so a typo and a reordered phrase both come out nearly right. The
+//! score maps to a suggested grade; a character diff of the original text shows the learner what was
+//! missing or extra.
+
+use crate::card_types::{self, CardType};
+use crate::error::AppError;
+use crate::schedule::Grade;
+use crate::text::{fold_char, normalize};
+use unicode_normalization::UnicodeNormalization;
+
+/// Lowest similarity that still suggests `Hard` rather than `Again`, e.g. one typo in a ten letter word.
+const HARD_SIMILARITY: f64 = 0.8;
+/// Above this many character comparisons the diff is not worked out character by character.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Edit distance between two character sequences (insertions, deletions and substitutions).
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Share of words the two texts have in common, counting repeats (the F1 score of the word lists).
+fn word_overlap(a: &str, b: &str) -> f64 {
+    let a: Vec<&str> = a.split_whitespace().collect();
+    let mut b: Vec<&str> = b.split_whitespace().collect();
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let total = a.len() + b.len();
+    let mut common = 0;
+    for word in a {
+        if let Some(i) = b.iter().position(|w| *w == word) {
+            b.swap_remove(i);
+            common += 1;
+        }
+    }
+    2.0 * common as f64 / total as f64
+}
+
+/// Similarity of normalized texts from 0 to 1; 1 only when they are equal. Word overlap can lift the
+/// score up to `HARD_SIMILARITY`, no further: the right words in the wrong order are not the answer.
+fn similarity(typed: &str, expected: &str) -> f64 {
+    if typed == expected {
+        return 1.0;
+    }
+    let (a, b): (Vec<char>, Vec<char>) = (typed.chars().collect(), expected.chars().collect());
+    let longest = a.len().max(b.len());
+    let by_characters = 1.0 - levenshtein(&a, &b) as f64 / longest as f64;
+    by_characters.max(word_overlap(typed, expected) * HARD_SIMILARITY)
+}
+
+fn suggested_grade(similarity: f64) -> Grade {
+    if similarity >= 1.0 {
+        Grade::Good
+    } else if similarity >= HARD_SIMILARITY {
+        Grade::Hard
+    } else {
+        Grade::Again
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    /// In both the typed and the expected answer.
+    Same,
+    /// In the expected answer only.
+    Missing,
+    /// Typed but not in the expected answer.
+    Extra,
+}
+
+#[derive(serde::Serialize)]
+pub struct DiffSegment {
+    kind: DiffKind,
+    text: String,
+}
+
+fn push_segment(segments: &mut Vec<DiffSegment>, kind: DiffKind, c: char) {
+    match segments.last_mut() {
+        Some(last) if last.kind == kind => last.text.push(c),
+        _ => segments.push(DiffSegment { kind, text: c.to_string() }),
+    }
+}
+
+/// Character diff of the typed against the expected answer, ignoring case and diacritics. `Same`
+/// segments use the expected spelling. Both sides are composed (NFC) first, so an accent typed as a
+/// separate combining mark stays on its letter.
+fn diff(typed: &str, expected: &str) -> Vec<DiffSegment> {
+    let (typed, expected): (String, String) = (typed.nfc().collect(), expected.nfc().collect());
+    let (typed, expected) = (typed.as_str(), expected.as_str());
+    let a: Vec<char> = typed.chars().collect();
+    let b: Vec<char> = expected.chars().collect();
+    if (a.len() + 1) * (b.len() + 1) > MAX_DIFF_CELLS {
+        let mut segments = Vec::new();
+        for (kind, text) in [(DiffKind::Extra, typed), (DiffKind::Missing, expected)] {
+            if !text.is_empty() {
+                segments.push(DiffSegment { kind, text: text.to_string() });
+            }
+        }
+        return segments;
+    }
+    let a_keys: Vec<String> = a.iter().map(|&c| fold_char(c)).collect();
+    let b_keys: Vec<String> = b.iter().map(|&c| fold_char(c)).collect();
+    // common[i][j]: longest common subsequence of a[i..] and b[j..].
+    let width = b.len() + 1;
+    let mut common = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i * width + j] = if a_keys[i] == b_keys[j] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+    let mut segments = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a_keys[i] == b_keys[j] {
+            push_segment(&mut segments, DiffKind::Same, b[j]);
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || common[i * width + j + 1] >= common[(i + 1) * width + j]) {
+            push_segment(&mut segments, DiffKind::Missing, b[j]);
+            j += 1;
+        } else {
+            push_segment(&mut segments, DiffKind::Extra, a[i]);
+            i += 1;
+        }
+    }
+    segments
+}
+
+#[derive(serde::Serialize)]
+pub struct AnswerCheck {
+    /// The answer the prompt expects, as written on the card.
+    expected: String,
+    /// The typed answer matches once normalized.
+    correct: bool,
+    /// From 0 to 1.
+    similarity: f64,
+    suggested_grade: Grade,
+    diff: Vec<DiffSegment>,
+}
+
+/// Check a typed answer to one of a card's prompts: `ordinal` 1 for the reverse side, the cloze number
+/// for a cloze card, and by default the card's first prompt.
+#[tauri::command]
+pub fn check_answer(app: tauri::AppHandle, card_id: i64, typed: String, ordinal: Option<i64>) -> Result<AnswerCheck, AppError> {
+    let conn = crate::open_db(&app)?;
+    let (card_type, question, answer, choices): (String, String, String, Option<String>) = conn
+        .query_row(
+            "SELECT card_type, question, answer, choices FROM cards WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![card_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|_| AppError::not_found("Card not found"))?;
+    let card_type = CardType::parse(&card_type);
+    let ordinals = card_types::prompt_ordinals(card_type, &question);
+    let ordinal = match ordinal {
+        Some(ordinal) if ordinals.contains(&ordinal) => ordinal,
+        Some(_) => return Err(AppError::validation("ordinal", "The card has no such side or cloze")),
+        None => ordinals.first().copied().unwrap_or_default(),
+    };
+    let choices = card_types::parse_choices(choices.as_deref());
+    let expected = card_types::render(card_type, &question, &answer, &choices, ordinal).expected;
+
+    let typed = typed.trim();
+    let (normalized_typed, normalized_expected) = (normalize(typed), normalize(&expected));
+    let correct = !typed.is_empty() && normalized_typed == normalized_expected;
+    let similarity = if typed.is_empty() { 0.0 } else { similarity(&normalized_typed, &normalized_expected) };
+    Ok(AnswerCheck {
+        diff: diff(typed, &expected),
+        correct,
+        similarity,
+        suggested_grade: suggested_grade(similarity),
+        expected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(typed: &str, expected: &str) -> (f64, Grade) {
+        let similarity = similarity(&normalize(typed), &normalize(expected));
+        (similarity, suggested_grade(similarity))
+    }
+
+    #[test]
+    fn only_equal_answers_score_one() {
+        assert_eq!(check("Mitochondrion!", "mitochondrion").1, Grade::Good);
+        assert_eq!(check("re\u{301}sume\u{301}", "Résumé").1, Grade::Good);
+        let (score, grade) = check("man bites dog", "dog bites man");
+        assert!(score < 1.0);
+        assert_eq!(grade, Grade::Hard);
+    }
+
+    #[test]
+    fn typos_are_hard_and_wrong_answers_again() {
+        assert_eq!(check("mitochondrian", "mitochondrion").1, Grade::Hard);
+        assert_eq!(check("ribosome", "mitochondrion").1, Grade::Again);
+        assert_eq!(check("dog", "dog bites man").1, Grade::Again);
+    }
+
+    #[test]
+    fn word_overlap_counts_repeats() {
+        assert_eq!(word_overlap("a a b", "a b b"), 2.0 * 2.0 / 6.0);
+        assert_eq!(word_overlap("", "a"), 0.0);
+        assert_eq!(levenshtein(&['k', 'i', 't'], &['s', 'i', 't', 's']), 2);
+    }
+
+    #[test]
+    fn diff_marks_missing_and_extra() {
+        let segments: Vec<(DiffKind, String)> = diff("Resume x", "résumé").into_iter().map(|s| (s.kind, s.text)).collect();
+        assert_eq!(
+            segments,
+            vec![(DiffKind::Same, "résumé".to_string()), (DiffKind::Extra, " x".to_string())]
+        );
+        let decomposed = diff("re\u{301}sume\u{301}", "résumé");
+        assert_eq!(decomposed.len(), 1);
+        assert!(decomposed[0].kind == DiffKind::Same);
+    }
+}
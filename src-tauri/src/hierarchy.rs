@@ -0,0 +1,380 @@
+//! Nested sub-collections.
+//!
+//! A sub-collection may have a `parent_id` in the same collection, so a deck can be organised as
+//! Chapter → Section → Topic. Names are unique among siblings, and a sub-collection is addressed
+//! outside the database by its path, e.g. "Chapter 1/Section 2". The default "no sub-collection" row
+//! always stays at the top level and never has children.
+
+use std::sync::OnceLock;
+
+use crate::error::AppError;
+use crate::journal;
+use crate::NULL_SUB_COLLECTION_NAME;
+
+/// Separates the levels of a sub-collection path.
+pub(crate) const PATH_SEPARATOR: char = '/';
+
+pub(crate) fn init_sub_collection_tree(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    crate::add_column_if_missing(conn, "sub_collections", "parent_id", "INTEGER REFERENCES sub_collections(id) ON DELETE CASCADE")?;
+    // Names used to be unique per collection; now they only need to be unique among siblings.
+    let sql: String = conn
+        .query_row("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'sub_collections'", [], |row| row.get(0))?;
+    static UNIQUE_NAME: OnceLock<regex::Regex> = OnceLock::new();
+    let unique_name = UNIQUE_NAME
+        .get_or_init(|| regex::Regex::new(r",\s*UNIQUE\s*\(\s*collection_id\s*,\s*name\s*\)").expect("valid unique constraint pattern"));
+    if unique_name.is_match(&sql) {
+        crate::rebuild_table(conn, "sub_collections", &unique_name.replace(&sql, ""))?;
+    }
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS sub_collections_uniq_sibling_name
+         ON sub_collections(collection_id, COALESCE(parent_id, 0), name)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Turn sub-collections named like a path, e.g. "Biology/Cells" from notes imported before names could
+/// nest, into a chain of nested ones, so they can be renamed and are found again by path. A level that
+/// already exists takes the row's cards and children instead. Runs after every table that refers to
+/// sub-collections exists.
+pub(crate) fn split_path_names(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    let rows: Vec<(i64, i64, Option<i64>, String)> = conn
+        .prepare("SELECT id, collection_id, parent_id, name FROM sub_collections WHERE instr(name, ?1) > 0 ORDER BY id")?
+        .query_map(rusqlite::params![PATH_SEPARATOR.to_string()], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?
+        .collect::<Result<_, _>>()?;
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let tx = conn.unchecked_transaction()?;
+    for (id, collection_id, parent_id, name) in rows {
+        let names: Vec<&str> = name.split(PATH_SEPARATOR).map(str::trim).filter(|n| !n.is_empty()).collect();
+        let Some((last, levels)) = names.split_last() else { continue };
+        if names.contains(&NULL_SUB_COLLECTION_NAME) {
+            continue;
+        }
+        let mut parent = parent_id;
+        for level in levels {
+            parent = Some(match child_named(&tx, collection_id, parent, level)? {
+                Some(existing) => existing,
+                None => {
+                    tx.execute(
+                        "INSERT INTO sub_collections (name, collection_id, parent_id) VALUES (?1, ?2, ?3)",
+                        rusqlite::params![level, collection_id, parent],
+                    )?;
+                    tx.last_insert_rowid()
+                }
+            });
+        }
+        match child_named(&tx, collection_id, parent, last)? {
+            Some(existing) if existing != id => fold_sub_collection(&tx, id, existing)?,
+            _ => {
+                tx.execute(
+                    "UPDATE sub_collections SET name = ?1, parent_id = ?2 WHERE id = ?3",
+                    rusqlite::params![last, parent, id],
+                )?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn child_named(conn: &rusqlite::Connection, collection_id: i64, parent_id: Option<i64>, name: &str) -> Result<Option<i64>, AppError> {
+    match conn.query_row(
+        "SELECT id FROM sub_collections WHERE collection_id = ?1 AND parent_id IS ?2 AND name = ?3",
+        rusqlite::params![collection_id, parent_id, name],
+        |row| row.get(0),
+    ) {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Fold sub-collection `from` into `into` of the same collection and delete it. Children move over,
+/// merging with a child of the same name; a card identical to one already in `into` is trashed.
+fn fold_sub_collection(conn: &rusqlite::Connection, from: i64, into: i64) -> Result<(), AppError> {
+    let children: Vec<(i64, i64, String)> = conn
+        .prepare("SELECT id, collection_id, name FROM sub_collections WHERE parent_id = ?1")?
+        .query_map(rusqlite::params![from], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<_, _>>()?;
+    for (child, collection_id, name) in children {
+        match child_named(conn, collection_id, Some(into), &name)? {
+            Some(existing) => fold_sub_collection(conn, child, existing)?,
+            None => {
+                conn.execute("UPDATE sub_collections SET parent_id = ?1 WHERE id = ?2", rusqlite::params![into, child])?;
+            }
+        }
+    }
+    conn.execute(
+        "UPDATE cards SET deleted_at = datetime('now')
+         WHERE sub_collection_id = ?1 AND deleted_at IS NULL AND EXISTS (
+             SELECT 1 FROM cards c WHERE c.sub_collection_id = ?2 AND c.question = cards.question
+                 AND c.answer = cards.answer AND c.deleted_at IS NULL)",
+        rusqlite::params![from, into],
+    )?;
+    conn.execute("UPDATE cards SET sub_collection_id = ?1 WHERE sub_collection_id = ?2", rusqlite::params![into, from])?;
+    conn.execute(
+        "UPDATE study_sessions SET sub_collection_id = ?1 WHERE sub_collection_id = ?2",
+        rusqlite::params![into, from],
+    )?;
+    conn.execute("DELETE FROM sub_collections WHERE id = ?1", rusqlite::params![from])?;
+    Ok(())
+}
+
+/// SQL for the ids of the sub-collection bound to `param` and all its descendants, for use as
+/// `sub_collection_id IN {subtree("?2")}`.
+pub(crate) fn subtree(param: &str) -> String {
+    format!(
+        "(WITH RECURSIVE subtree(id) AS (
+             SELECT {param}
+             UNION ALL SELECT s.id FROM sub_collections s JOIN subtree ON s.parent_id = subtree.id
+         ) SELECT id FROM subtree)"
+    )
+}
+
+pub(crate) struct SubCollectionPath {
+    pub(crate) id: i64,
+    pub(crate) parent_id: Option<i64>,
+    pub(crate) name: String,
+    pub(crate) path: String,
+}
+
+/// Every sub-collection of the collection with its path, sorted by path so parents come before their
+/// children.
+pub(crate) fn sub_collection_paths(conn: &rusqlite::Connection, collection_id: i64) -> Result<Vec<SubCollectionPath>, AppError> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE tree(id, parent_id, name, path) AS (
+             SELECT id, parent_id, name, name FROM sub_collections WHERE collection_id = ?1 AND parent_id IS NULL
+             UNION ALL
+             SELECT s.id, s.parent_id, s.name, tree.path || ?2 || s.name FROM sub_collections s JOIN tree ON s.parent_id = tree.id
+         )
+         SELECT id, parent_id, name, path FROM tree ORDER BY path",
+    )?;
+    let rows = stmt
+        .query_map(rusqlite::params![collection_id, PATH_SEPARATOR.to_string()], |row| {
+            Ok(SubCollectionPath { id: row.get(0)?, parent_id: row.get(1)?, name: row.get(2)?, path: row.get(3)? })
+        })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Path of one sub-collection, e.g. "Chapter 1/Section 2".
+pub(crate) fn sub_collection_path(conn: &rusqlite::Connection, id: i64) -> Result<String, AppError> {
+    Ok(conn.query_row(
+        "WITH RECURSIVE up(id, parent_id, path) AS (
+             SELECT id, parent_id, name FROM sub_collections WHERE id = ?1
+             UNION ALL
+             SELECT s.id, s.parent_id, s.name || ?2 || up.path FROM sub_collections s JOIN up ON s.id = up.parent_id
+         )
+         SELECT path FROM up WHERE parent_id IS NULL",
+        rusqlite::params![id, PATH_SEPARATOR.to_string()],
+        |row| row.get(0),
+    )?)
+}
+
+/// Get or create the sub-collection at `path`, creating missing levels; `created` is called with the id
+/// of each new row. A single name is a top-level sub-collection.
+pub(crate) fn get_or_create_path(
+    conn: &rusqlite::Connection,
+    collection_id: i64,
+    path: &str,
+    mut created: impl FnMut(i64),
+) -> Result<i64, AppError> {
+    let names: Vec<&str> = path.split(PATH_SEPARATOR).map(str::trim).filter(|n| !n.is_empty()).collect();
+    if names.is_empty() {
+        return Err(AppError::validation("name", "Sub collection name cannot be empty"));
+    }
+    let mut parent: Option<i64> = None;
+    for name in names {
+        if parent.is_some() && name == NULL_SUB_COLLECTION_NAME {
+            return Err(AppError::Reserved("The default sub collection cannot have sub collections.".to_string()));
+        }
+        let existing = conn.query_row(
+            "SELECT id FROM sub_collections WHERE collection_id = ?1 AND parent_id IS ?2 AND name = ?3",
+            rusqlite::params![collection_id, parent, name],
+            |row| row.get(0),
+        );
+        let id = match existing {
+            Ok(id) => id,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                conn.execute(
+                    "INSERT INTO sub_collections (name, collection_id, parent_id) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![name, collection_id, parent],
+                )?;
+                let id = conn.last_insert_rowid();
+                created(id);
+                id
+            }
+            Err(e) => return Err(e.into()),
+        };
+        parent = Some(id);
+    }
+    Ok(parent.unwrap_or_default())
+}
+
+/// Check that `parent_id` can hold `id` (or a new sub-collection when `id` is `None`): it is in the
+/// same collection, is not the default sub-collection, and is not `id` itself or one of its descendants.
+pub(crate) fn check_parent(conn: &rusqlite::Connection, collection_id: i64, id: Option<i64>, parent_id: i64) -> Result<(), AppError> {
+    let name: String = conn
+        .query_row(
+            "SELECT name FROM sub_collections WHERE id = ?1 AND collection_id = ?2",
+            rusqlite::params![parent_id, collection_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::validation("parentId", "The parent must be a sub collection of the same collection"))?;
+    if name == NULL_SUB_COLLECTION_NAME {
+        return Err(AppError::Reserved("The default sub collection cannot have sub collections.".to_string()));
+    }
+    if let Some(id) = id {
+        let inside: bool = conn
+            .query_row(
+                &format!("SELECT ?2 IN {}", subtree("?1")),
+                rusqlite::params![id, parent_id],
+                |row| row.get(0),
+            )?;
+        if inside {
+            return Err(AppError::validation("parentId", "A sub collection cannot be moved inside itself"));
+        }
+    }
+    Ok(())
+}
+
+/// Move a sub-collection, with everything below it, under `parent_id`, or to the top level when `None`.
+#[tauri::command]
+pub fn move_sub_collection(app: tauri::AppHandle, id: i64, parent_id: Option<i64>) -> Result<(), AppError> {
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    move_under(&tx, id, parent_id)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Move `id` under `parent_id` on `tx`, journaled; the caller commits.
+fn move_under(tx: &rusqlite::Connection, id: i64, parent_id: Option<i64>) -> Result<(), AppError> {
+    let (name, collection_id): (String, i64) = tx
+        .query_row(
+            "SELECT name, collection_id FROM sub_collections WHERE id = ?1",
+            rusqlite::params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| AppError::not_found("Sub collection not found"))?;
+    if name == NULL_SUB_COLLECTION_NAME {
+        return Err(AppError::Reserved("The default sub collection cannot be moved.".to_string()));
+    }
+    if let Some(parent_id) = parent_id {
+        check_parent(tx, collection_id, Some(id), parent_id)?;
+    }
+    let mut op = journal::Operation::new(format!("Move sub collection \"{name}\""));
+    op.track(tx, "sub_collections", "id = ?1", rusqlite::params![id])?;
+    tx.execute("UPDATE sub_collections SET parent_id = ?1 WHERE id = ?2", rusqlite::params![parent_id, id])?;
+    op.commit(tx)
+}
+
+#[derive(serde::Serialize)]
+pub struct SubCollectionNode {
+    id: i64,
+    name: String,
+    /// Live cards directly in this sub-collection.
+    card_count: u32,
+    /// Live cards in this sub-collection and all below it.
+    total_card_count: u32,
+    children: Vec<SubCollectionNode>,
+}
+
+/// The collection's sub-collections as a tree, siblings sorted by name.
+#[tauri::command]
+pub fn get_sub_collection_tree(app: tauri::AppHandle, collection_id: i64) -> Result<Vec<SubCollectionNode>, AppError> {
+    let conn = crate::open_db(&app)?;
+    sub_collection_tree(&conn, collection_id)
+}
+
+fn sub_collection_tree(conn: &rusqlite::Connection, collection_id: i64) -> Result<Vec<SubCollectionNode>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.parent_id, s.name,
+             (SELECT COUNT(*) FROM cards WHERE sub_collection_id = s.id AND deleted_at IS NULL)
+         FROM sub_collections s WHERE s.collection_id = ?1 ORDER BY s.name",
+    )?;
+    let rows: Vec<(i64, Option<i64>, String, u32)> = stmt
+        .query_map(rusqlite::params![collection_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+        .collect::<Result<_, _>>()?;
+
+    fn children(rows: &[(i64, Option<i64>, String, u32)], parent: Option<i64>) -> Vec<SubCollectionNode> {
+        rows.iter()
+            .filter(|(_, p, _, _)| *p == parent)
+            .map(|(id, _, name, card_count)| {
+                let children = children(rows, Some(*id));
+                let total_card_count = card_count + children.iter().map(|c| c.total_card_count).sum::<u32>();
+                SubCollectionNode { id: *id, name: name.clone(), card_count: *card_count, total_card_count, children }
+            })
+            .collect()
+    }
+    Ok(children(&rows, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collection 1 with Ch1 > Sec > Topic, a second Sec at the top and the default sub collection; cards
+    /// 1 and 2 in Sec under Ch1, 3 in Topic, 4 trashed in Ch1.
+    fn db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A'), (2, 'B');
+             INSERT INTO sub_collections (id, name, collection_id, parent_id) VALUES
+                 (1, '- None -', 1, NULL), (2, 'Ch1', 1, NULL), (3, 'Sec', 1, 2), (4, 'Topic', 1, 3), (5, 'Sec', 1, NULL),
+                 (6, '- None -', 2, NULL), (7, 'Other', 2, NULL);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id, deleted_at) VALUES
+                 (1, 'Q1', 'A', 1, 3, NULL), (2, 'Q2', 'A', 1, 3, NULL), (3, 'Q3', 'A', 1, 4, NULL),
+                 (4, 'Q4', 'A', 1, 2, datetime('now'));",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn parent(conn: &rusqlite::Connection, id: i64) -> Option<i64> {
+        conn.query_row("SELECT parent_id FROM sub_collections WHERE id = ?1", rusqlite::params![id], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn sub_collections_cannot_move_inside_themselves() {
+        let conn = db();
+        assert_eq!(move_under(&conn, 2, Some(2)).unwrap_err().code(), "validation");
+        assert_eq!(move_under(&conn, 2, Some(4)).unwrap_err().code(), "validation");
+        assert_eq!(move_under(&conn, 4, Some(1)).unwrap_err().code(), "reserved");
+        assert_eq!(move_under(&conn, 1, Some(5)).unwrap_err().code(), "reserved");
+        assert_eq!(check_parent(&conn, 1, Some(4), 7).unwrap_err().code(), "validation");
+        assert_eq!(parent(&conn, 2), None);
+
+        move_under(&conn, 4, Some(5)).unwrap();
+        assert_eq!(parent(&conn, 4), Some(5));
+    }
+
+    #[test]
+    fn sub_collections_move_to_the_top_unless_a_sibling_has_the_name() {
+        let conn = db();
+        move_under(&conn, 4, None).unwrap();
+        assert_eq!(parent(&conn, 4), None);
+        assert_eq!(sub_collection_path(&conn, 4).unwrap(), "Topic");
+
+        assert_eq!(move_under(&conn, 3, None).unwrap_err().code(), "duplicate");
+        assert_eq!(parent(&conn, 3), Some(2));
+    }
+
+    #[test]
+    fn tree_totals_count_live_cards_below() {
+        let conn = db();
+        let tree = sub_collection_tree(&conn, 1).unwrap();
+        let names: Vec<&str> = tree.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["- None -", "Ch1", "Sec"]);
+        let ch1 = &tree[1];
+        assert_eq!((ch1.card_count, ch1.total_card_count), (0, 3));
+        let sec = &ch1.children[0];
+        assert_eq!((sec.id, sec.card_count, sec.total_card_count), (3, 2, 3));
+        assert_eq!((sec.children[0].card_count, sec.children[0].total_card_count), (1, 1));
+        assert_eq!(tree[2].total_card_count, 0);
+    }
+}
@@ -0,0 +1,293 @@
+//! Database consistency check and repair.
+//!
+//! Besides SQLite's own `PRAGMA integrity_check`, this looks for rows the commands assume cannot exist:
+//! collections without their "no sub-collection" row, cards filed under another collection's
+//! sub-collection, and cards or links pointing at rows that are gone.
+
+use crate::error::AppError;
+use crate::NULL_SUB_COLLECTION_NAME;
+
+/// Collection that cards whose collection no longer exists are moved into.
+const RECOVERED_COLLECTION_NAME: &str = "Recovered cards";
+
+#[derive(serde::Serialize)]
+pub struct DatabaseIssue {
+    /// Stable identifier of the kind of problem, e.g. `sub_collection_mismatch`.
+    kind: &'static str,
+    description: String,
+    /// Affected rows: collection ids for `missing_null_sub_collection`, sub-collection ids for
+    /// `sub_collection_parent_mismatch` and `orphan_sub_collection`, note ids for `orphan_note_link`,
+    /// card ids otherwise.
+    ids: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct DatabaseReport {
+    /// Messages from `PRAGMA integrity_check`; empty when SQLite reports "ok".
+    integrity_errors: Vec<String>,
+    issues: Vec<DatabaseIssue>,
+    /// Whether the issues listed were fixed (`repair_database`) or only found (`check_database`).
+    repaired: bool,
+    /// Cards that duplicated a live card once moved, and were put in the trash instead.
+    trashed_duplicates: u32,
+}
+
+struct Check {
+    kind: &'static str,
+    description: &'static str,
+    /// Selects the ids of the affected rows.
+    query: &'static str,
+}
+
+const CHECKS: &[Check] = &[
+    Check {
+        kind: "missing_null_sub_collection",
+        description: "Collections without their \"no sub collection\" row",
+        query: "SELECT id FROM collections c WHERE NOT EXISTS
+                (SELECT 1 FROM sub_collections s WHERE s.collection_id = c.id AND s.name = ?1)",
+    },
+    Check {
+        kind: "missing_collection",
+        description: "Cards whose collection no longer exists",
+        query: "SELECT id FROM cards WHERE collection_id NOT IN (SELECT id FROM collections)",
+    },
+    Check {
+        kind: "sub_collection_mismatch",
+        description: "Cards filed under a sub collection of a different collection",
+        query: "SELECT k.id FROM cards k JOIN sub_collections s ON s.id = k.sub_collection_id
+                WHERE s.collection_id != k.collection_id
+                  AND k.collection_id IN (SELECT id FROM collections)",
+    },
+    Check {
+        kind: "missing_sub_collection",
+        description: "Cards whose sub collection no longer exists",
+        query: "SELECT id FROM cards WHERE sub_collection_id NOT IN (SELECT id FROM sub_collections)
+                AND collection_id IN (SELECT id FROM collections)",
+    },
+    Check {
+        kind: "sub_collection_parent_mismatch",
+        description: "Sub collections nested under a sub collection of a different collection, or one that no longer exists",
+        query: "SELECT s.id FROM sub_collections s WHERE s.parent_id IS NOT NULL AND NOT EXISTS
+                (SELECT 1 FROM sub_collections p WHERE p.id = s.parent_id AND p.collection_id = s.collection_id)",
+    },
+    Check {
+        kind: "orphan_sub_collection",
+        description: "Sub collections whose collection no longer exists",
+        query: "SELECT id FROM sub_collections WHERE collection_id NOT IN (SELECT id FROM collections)",
+    },
+    Check {
+        kind: "orphan_note_link",
+        description: "Markdown note links to cards or collections that no longer exist",
+        query: "SELECT note_id FROM note_cards WHERE (card_id NOT IN (SELECT id FROM cards)
+                OR collection_id NOT IN (SELECT id FROM collections))",
+    },
+];
+
+fn integrity_errors(conn: &rusqlite::Connection) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let messages = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(messages.into_iter().filter(|m| m != "ok").collect())
+}
+
+fn issue_id(row: &rusqlite::Row) -> rusqlite::Result<String> {
+    Ok(match row.get_ref(0)? {
+        rusqlite::types::ValueRef::Integer(i) => i.to_string(),
+        value => value.as_str().unwrap_or_default().to_string(),
+    })
+}
+
+fn find_issues(conn: &rusqlite::Connection) -> Result<Vec<DatabaseIssue>, AppError> {
+    let mut issues = Vec::new();
+    for check in CHECKS {
+        let mut stmt = conn.prepare(check.query)?;
+        let rows = if stmt.parameter_count() == 0 {
+            stmt.query_map([], issue_id)
+        } else {
+            stmt.query_map(rusqlite::params![NULL_SUB_COLLECTION_NAME], issue_id)
+        };
+        let ids = rows?
+            .collect::<Result<Vec<_>, _>>()?;
+        if !ids.is_empty() {
+            issues.push(DatabaseIssue { kind: check.kind, description: check.description.to_string(), ids });
+        }
+    }
+    Ok(issues)
+}
+
+/// Move a card, or trash it if it would then duplicate a live card. Returns whether it was trashed.
+fn move_card(conn: &rusqlite::Connection, card_id: i64, collection_id: i64, sub_collection_id: i64) -> Result<bool, AppError> {
+    let moved = conn.execute(
+        "UPDATE cards SET collection_id = ?1, sub_collection_id = ?2 WHERE id = ?3",
+        rusqlite::params![collection_id, sub_collection_id, card_id],
+    );
+    match moved {
+        Ok(_) => Ok(false),
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE => {
+            conn.execute(
+                "UPDATE cards SET collection_id = ?1, sub_collection_id = ?2, deleted_at = datetime('now') WHERE id = ?3",
+                rusqlite::params![collection_id, sub_collection_id, card_id],
+            )?;
+            Ok(true)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn card_ids(conn: &rusqlite::Connection, query: &str) -> Result<Vec<(i64, i64)>, AppError> {
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Fix every issue `find_issues` reports. Returns the number of cards trashed as duplicates.
+fn repair(conn: &rusqlite::Connection) -> Result<u32, AppError> {
+    let mut trashed = 0;
+    conn.execute(
+        "INSERT INTO sub_collections (name, collection_id)
+         SELECT ?1, id FROM collections c WHERE NOT EXISTS
+             (SELECT 1 FROM sub_collections s WHERE s.collection_id = c.id AND s.name = ?1)",
+        rusqlite::params![NULL_SUB_COLLECTION_NAME],
+    )?;
+
+    let lost = card_ids(conn, "SELECT id, 0 FROM cards WHERE collection_id NOT IN (SELECT id FROM collections)")?;
+    if !lost.is_empty() {
+        conn.execute(
+            "INSERT OR IGNORE INTO collections (name) VALUES (?1)",
+            rusqlite::params![RECOVERED_COLLECTION_NAME],
+        )?;
+        let recovered: i64 = conn
+            .query_row(
+                "UPDATE collections SET deleted_at = NULL WHERE name = ?1 RETURNING id",
+                rusqlite::params![RECOVERED_COLLECTION_NAME],
+                |row| row.get(0),
+            )?;
+        let sub = crate::get_or_create_sub_collection(conn, recovered, NULL_SUB_COLLECTION_NAME)?;
+        for (card_id, _) in lost {
+            trashed += move_card(conn, card_id, recovered, sub)? as u32;
+        }
+    }
+
+    // Keep the grouping: file the card under the sub-collection of the same name in its own collection.
+    let mismatched = card_ids(
+        conn,
+        "SELECT k.id, k.collection_id FROM cards k JOIN sub_collections s ON s.id = k.sub_collection_id
+         WHERE s.collection_id != k.collection_id",
+    )?;
+    for (card_id, collection_id) in mismatched {
+        let name: String = conn
+            .query_row(
+                "SELECT s.name FROM cards k JOIN sub_collections s ON s.id = k.sub_collection_id WHERE k.id = ?1",
+                rusqlite::params![card_id],
+                |row| row.get(0),
+            )?;
+        let sub = crate::get_or_create_sub_collection(conn, collection_id, &name)?;
+        trashed += move_card(conn, card_id, collection_id, sub)? as u32;
+    }
+
+    let homeless = card_ids(
+        conn,
+        "SELECT id, collection_id FROM cards WHERE sub_collection_id NOT IN (SELECT id FROM sub_collections)",
+    )?;
+    for (card_id, collection_id) in homeless {
+        let sub = crate::get_null_sub_collection_id(conn, collection_id)?;
+        trashed += move_card(conn, card_id, collection_id, sub)? as u32;
+    }
+
+    // Move misplaced sub-collections to the top level of their own collection.
+    conn.execute(
+        "UPDATE sub_collections SET parent_id = NULL WHERE parent_id IS NOT NULL AND NOT EXISTS
+             (SELECT 1 FROM sub_collections p WHERE p.id = sub_collections.parent_id AND p.collection_id = sub_collections.collection_id)",
+        [],
+    )?;
+    conn.execute("DELETE FROM sub_collections WHERE collection_id NOT IN (SELECT id FROM collections)", [])?;
+    conn.execute(
+        "DELETE FROM note_cards WHERE card_id NOT IN (SELECT id FROM cards)
+         OR collection_id NOT IN (SELECT id FROM collections)",
+        [],
+    )?;
+    Ok(trashed)
+}
+
+/// Report problems without changing anything.
+#[tauri::command]
+pub fn check_database(app: tauri::AppHandle) -> Result<DatabaseReport, AppError> {
+    let conn = crate::open_db(&app)?;
+    Ok(DatabaseReport {
+        integrity_errors: integrity_errors(&conn)?,
+        issues: find_issues(&conn)?,
+        repaired: false,
+        trashed_duplicates: 0,
+    })
+}
+
+/// Fix the problems `check_database` finds, after taking a backup. Index corruption is rebuilt with
+/// `REINDEX`; `integrity_errors` lists whatever SQLite still reports afterwards, which only restoring a
+/// backup can fix.
+#[tauri::command]
+pub fn repair_database(app: tauri::AppHandle) -> Result<DatabaseReport, AppError> {
+    crate::backup::snapshot(&app, "repair")?;
+    let mut conn = crate::open_db(&app)?;
+    if !integrity_errors(&conn)?.is_empty() {
+        conn.execute("REINDEX", [])?;
+    }
+    let tx = conn.transaction()?;
+    let issues = find_issues(&tx)?;
+    let trashed_duplicates = repair(&tx)?;
+    tx.commit()?;
+    Ok(DatabaseReport { integrity_errors: integrity_errors(&conn)?, issues, repaired: true, trashed_duplicates })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A'), (2, 'B');
+             INSERT INTO sub_collections (id, name, collection_id) VALUES
+                 (1, '- None -', 1), (2, '- None -', 2), (3, 'Cells', 1), (4, 'Cells', 2);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn repair_refiles_cards_and_trashes_the_duplicates() {
+        let conn = db();
+        conn.execute_batch(
+            "INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES
+                 (1, 'Q', 'A', 1, 3), (2, 'Q', 'A', 1, 4), (3, 'R', 'B', 1, 4);
+             PRAGMA foreign_keys = OFF;
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES (4, 'S', 'C', 9, 1);
+             PRAGMA foreign_keys = ON;",
+        )
+        .unwrap();
+        let kinds: Vec<&str> = find_issues(&conn).unwrap().iter().map(|i| i.kind).collect();
+        assert_eq!(kinds, vec!["missing_collection", "sub_collection_mismatch"]);
+
+        assert_eq!(repair(&conn).unwrap(), 1);
+        assert!(find_issues(&conn).unwrap().is_empty());
+        let trashed: bool = conn.query_row("SELECT deleted_at IS NOT NULL FROM cards WHERE id = 2", [], |r| r.get(0)).unwrap();
+        assert!(trashed);
+        let sub: i64 = conn.query_row("SELECT sub_collection_id FROM cards WHERE id = 3", [], |r| r.get(0)).unwrap();
+        assert_eq!(sub, 3);
+        let recovered: String = conn
+            .query_row("SELECT c.name FROM cards k JOIN collections c ON c.id = k.collection_id WHERE k.id = 4", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(recovered, RECOVERED_COLLECTION_NAME);
+    }
+
+    #[test]
+    fn move_card_only_treats_unique_violations_as_duplicates() {
+        let conn = db();
+        conn.execute("INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES (1, 'Q', 'A', 1, 1)", [])
+            .unwrap();
+        assert!(move_card(&conn, 1, 1, 99).is_err());
+        let deleted: Option<String> = conn.query_row("SELECT deleted_at FROM cards WHERE id = 1", [], |r| r.get(0)).unwrap();
+        assert_eq!(deleted, None);
+    }
+}
@@ -0,0 +1,677 @@
+//! Background bulk-generation queue, persisted in SQLite so progress survives closing the app.
+//!
+//! Each file from bulk create gets one row in `generation_jobs`; PDFs get one row per text chunk, or
+//! per page for pages that need OCR (see `pdf`). OCR still runs in the webview
+//! (Tesseract.js) and stores its text on the job; once a job has text, the worker here runs the
+//! LLM for it with the configured concurrency, retrying failures with exponential backoff.
+//! Every state change is emitted as a `generation-job-updated` event so the UI only renders jobs;
+//! failures of the dispatcher itself, which belong to no job, go out as `generation-queue-error`.
+
+use crate::error::AppError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tauri::{Emitter, Manager};
+
+use crate::open_db;
+
+const JOB_UPDATED_EVENT: &str = "generation-job-updated";
+const QUEUE_UPDATED_EVENT: &str = "generation-queue-updated";
+const QUEUE_ERROR_EVENT: &str = "generation-queue-error";
+const RETRY_BASE_SECONDS: i64 = 5;
+const RETRY_MAX_SECONDS: i64 = 300;
+/// How long the dispatcher sleeps when nothing is scheduled; wakeups normally come via `notify`.
+const IDLE_WAIT: Duration = Duration::from_secs(60);
+
+/// Job table layout. `page` is 0 for image files and the 1-based page for PDFs; `chunk` numbers the
+/// text chunks within a page.
+const JOBS_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS generation_jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            page INTEGER NOT NULL DEFAULT 0,
+            chunk INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'pending',
+            source_text TEXT,
+            response TEXT NOT NULL DEFAULT '',
+            question TEXT NOT NULL DEFAULT '',
+            answer TEXT NOT NULL DEFAULT '',
+            error TEXT,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            source_hash TEXT,
+            generator TEXT,
+            UNIQUE (path, page, chunk)
+        )";
+
+pub(crate) fn init_jobs_tables(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    let has_page: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('generation_jobs') WHERE name = 'page'",
+            [],
+            |row| row.get(0),
+        )?;
+    let has_table: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'generation_jobs'",
+            [],
+            |row| row.get(0),
+        )?;
+    if has_table > 0 && has_page == 0 {
+        // Jobs used to be unique per path; rebuild the table so a PDF can have one job per chunk.
+        crate::add_column_if_missing(conn, "generation_jobs", "source_hash", "TEXT")?;
+        crate::add_column_if_missing(conn, "generation_jobs", "generator", "TEXT")?;
+        conn.execute_batch(&format!(
+            "BEGIN;
+             ALTER TABLE generation_jobs RENAME TO generation_jobs_old;
+             {JOBS_TABLE_SQL};
+             INSERT INTO generation_jobs (id, path, status, source_text, response, question, answer, error, attempts,
+                 next_attempt_at, created_at, updated_at, source_hash, generator)
+             SELECT id, path, status, source_text, response, question, answer, error, attempts,
+                 next_attempt_at, created_at, updated_at, source_hash, generator
+             FROM generation_jobs_old;
+             DROP TABLE generation_jobs_old;
+             COMMIT;"
+        ))?;
+    }
+    conn.execute(JOBS_TABLE_SQL, [])?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS generation_queue (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            paused INTEGER NOT NULL DEFAULT 0,
+            concurrency INTEGER NOT NULL DEFAULT 1,
+            max_attempts INTEGER NOT NULL DEFAULT 3,
+            host TEXT NOT NULL DEFAULT 'local',
+            model TEXT NOT NULL DEFAULT '',
+            prompt_prefix TEXT NOT NULL DEFAULT ''
+        )",
+        [],
+    )?;
+    conn.execute("INSERT OR IGNORE INTO generation_queue (id) VALUES (1)", [])?;
+    Ok(())
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct GenerationJob {
+    id: i64,
+    path: String,
+    /// PDF page the text comes from; `None` for image files.
+    page: Option<u32>,
+    /// One of pending, running, done, failed, cancelled, dismissed.
+    status: String,
+    /// OCR or extracted PDF text; `None` until the webview has processed the file.
+    source_text: Option<String>,
+    response: String,
+    question: String,
+    answer: String,
+    error: Option<String>,
+    attempts: i64,
+    /// SHA-256 of the file when it was queued; saved on the card as provenance.
+    source_hash: Option<String>,
+    /// Model and prompt version that produced `response`.
+    generator: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct GenerationQueueSettings {
+    paused: bool,
+    concurrency: u32,
+    max_attempts: u32,
+    host: crate::llm::OllamaHost,
+    model: String,
+    prompt_prefix: String,
+}
+
+impl GenerationJob {
+    pub(crate) fn is_dismissed(&self) -> bool {
+        self.status == "dismissed"
+    }
+}
+
+/// In-memory side of the queue: the wakeup signal and handles of jobs currently running.
+#[derive(Default)]
+pub struct GenerationQueue {
+    wake: tokio::sync::Notify,
+    running: Mutex<HashMap<i64, tauri::async_runtime::JoinHandle<()>>>,
+}
+
+impl GenerationQueue {
+    /// Ask the dispatcher to look for ready jobs now.
+    pub(crate) fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+const JOB_COLUMNS: &str = "id, path, status, source_text, response, question, answer, error, attempts, source_hash,
+    generator, NULLIF(page, 0)";
+
+fn row_to_job(row: &rusqlite::Row) -> rusqlite::Result<GenerationJob> {
+    Ok(GenerationJob {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        status: row.get(2)?,
+        source_text: row.get(3)?,
+        response: row.get(4)?,
+        question: row.get(5)?,
+        answer: row.get(6)?,
+        error: row.get(7)?,
+        attempts: row.get(8)?,
+        source_hash: row.get(9)?,
+        generator: row.get(10)?,
+        page: row.get(11)?,
+    })
+}
+
+fn get_job(conn: &rusqlite::Connection, id: i64) -> Result<GenerationJob, AppError> {
+    conn.query_row(
+        &format!("SELECT {JOB_COLUMNS} FROM generation_jobs WHERE id = ?1"),
+        rusqlite::params![id],
+        row_to_job,
+    )
+    .map_err(|_| AppError::not_found("Generation job not found"))
+}
+
+fn read_settings(conn: &rusqlite::Connection) -> Result<GenerationQueueSettings, AppError> {
+    Ok(conn.query_row(
+        "SELECT paused, concurrency, max_attempts, host, model, prompt_prefix FROM generation_queue WHERE id = 1",
+        [],
+        |row| {
+            let host: String = row.get(3)?;
+            Ok(GenerationQueueSettings {
+                paused: row.get::<_, i64>(0)? != 0,
+                concurrency: row.get(1)?,
+                max_attempts: row.get(2)?,
+                host: crate::llm::OllamaHost::from_name(&host).unwrap_or(crate::llm::OllamaHost::Local),
+                model: row.get(4)?,
+                prompt_prefix: row.get(5)?,
+            })
+        },
+    )?)
+}
+
+fn emit_job(app: &tauri::AppHandle, conn: &rusqlite::Connection, id: i64) {
+    if let Ok(job) = get_job(conn, id) {
+        let _ = app.emit(JOB_UPDATED_EVENT, job);
+    }
+}
+
+/// Seconds to wait before the next attempt: 5s, 10s, 20s, ... capped at five minutes.
+fn retry_delay_seconds(attempts: i64) -> i64 {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    (RETRY_BASE_SECONDS * 2i64.pow(exponent)).min(RETRY_MAX_SECONDS)
+}
+
+/// Pull `{"question": ..., "answer": ...}` out of the model reply; empty strings if it isn't valid.
+fn parse_flashcard(content: &str) -> (String, String) {
+    #[derive(serde::Deserialize)]
+    struct Flashcard {
+        question: String,
+        answer: String,
+    }
+    serde_json::from_str::<Flashcard>(content)
+        .map(|card| (card.question, card.answer))
+        .unwrap_or_default()
+}
+
+/// Reset jobs interrupted by a previous shutdown and start the dispatcher. Called from `setup`.
+pub fn start(app: &tauri::AppHandle) -> Result<(), AppError> {
+    let conn = open_db(app)?;
+    conn.execute(
+        "UPDATE generation_jobs SET status = 'pending', updated_at = datetime('now') WHERE status = 'running'",
+        [],
+    )?;
+
+    let queue = Arc::new(GenerationQueue::default());
+    app.manage(queue.clone());
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let wait = match dispatch_ready_jobs(&app, &queue) {
+                Ok(wait) => wait,
+                Err(e) => {
+                    let _ = app.emit(QUEUE_ERROR_EVENT, e.to_string());
+                    Duration::from_secs(RETRY_BASE_SECONDS as u64)
+                }
+            };
+            let _ = tokio::time::timeout(wait, queue.wake.notified()).await;
+        }
+    });
+    Ok(())
+}
+
+/// Start as many ready jobs as concurrency allows; returns how long to sleep before checking again.
+fn dispatch_ready_jobs(app: &tauri::AppHandle, queue: &Arc<GenerationQueue>) -> Result<Duration, AppError> {
+    let conn = open_db(app)?;
+    let settings = read_settings(&conn)?;
+    if settings.paused {
+        return Ok(IDLE_WAIT);
+    }
+    let in_flight = queue.running.lock()?.len() as u32;
+    let slots = settings.concurrency.max(1).saturating_sub(in_flight);
+    if slots > 0 {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id FROM generation_jobs
+                 WHERE status = 'pending' AND source_text IS NOT NULL
+                   AND (next_attempt_at IS NULL OR next_attempt_at <= datetime('now'))
+                 ORDER BY id LIMIT ?1",
+            )?;
+        let ids = stmt
+            .query_map(rusqlite::params![slots], |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        for id in ids {
+            spawn_job(app, queue, &conn, id)?;
+        }
+    }
+
+    // Sleep until the soonest scheduled retry, if any.
+    let next_retry: Option<f64> = conn
+        .query_row(
+            "SELECT (julianday(MIN(next_attempt_at)) - julianday('now')) * 86400 FROM generation_jobs
+             WHERE status = 'pending' AND source_text IS NOT NULL AND next_attempt_at > datetime('now')",
+            [],
+            |row| row.get(0),
+        )?;
+    Ok(next_retry
+        .map(|secs| Duration::from_secs(secs.max(1.0).ceil() as u64).min(IDLE_WAIT))
+        .unwrap_or(IDLE_WAIT))
+}
+
+fn spawn_job(
+    app: &tauri::AppHandle,
+    queue: &Arc<GenerationQueue>,
+    conn: &rusqlite::Connection,
+    id: i64,
+) -> Result<(), AppError> {
+    // Hold the lock across spawn so the task can't deregister itself before it is registered.
+    let mut running = queue.running.lock()?;
+    if running.contains_key(&id) {
+        return Ok(());
+    }
+    conn.execute(
+        "UPDATE generation_jobs SET status = 'running', updated_at = datetime('now') WHERE id = ?1",
+        rusqlite::params![id],
+    )?;
+    emit_job(app, conn, id);
+    let task_app = app.clone();
+    let task_queue = queue.clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        if let Err(e) = run_job(&task_app, id).await {
+            fail_job(&task_app, id, &e);
+        }
+        if let Ok(mut running) = task_queue.running.lock() {
+            running.remove(&id);
+        }
+        task_queue.wake.notify_one();
+    });
+    running.insert(id, handle);
+    Ok(())
+}
+
+async fn run_job(app: &tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    let (settings, text) = {
+        let conn = open_db(app)?;
+        let job = get_job(&conn, id)?;
+        (read_settings(&conn)?, job.source_text.unwrap_or_default())
+    };
+    let result = crate::llm::generate_flashcard_content(
+        app,
+        settings.host,
+        &settings.model,
+        settings.prompt_prefix.trim(),
+        &text,
+    )
+    .await;
+
+    let conn = open_db(app)?;
+    match result {
+        Ok(response) => {
+            let (question, answer) = parse_flashcard(&response);
+            let generator = crate::llm::generator_label(settings.host, &settings.model, settings.prompt_prefix.trim());
+            conn.execute(
+                "UPDATE generation_jobs SET status = 'done', response = ?1, question = ?2, answer = ?3, generator = ?4,
+                    error = NULL, attempts = attempts + 1, next_attempt_at = NULL, updated_at = datetime('now')
+                 WHERE id = ?5 AND status = 'running'",
+                rusqlite::params![response, question, answer, generator, id],
+            )?;
+        }
+        Err(error) => {
+            let error = error.to_string();
+            let attempts: i64 = conn
+                .query_row("SELECT attempts + 1 FROM generation_jobs WHERE id = ?1", rusqlite::params![id], |row| row.get(0))?;
+            if attempts < settings.max_attempts as i64 {
+                let delay = format!("+{} seconds", retry_delay_seconds(attempts));
+                conn.execute(
+                    "UPDATE generation_jobs SET status = 'pending', error = ?1, attempts = ?2,
+                        next_attempt_at = datetime('now', ?3), updated_at = datetime('now')
+                     WHERE id = ?4 AND status = 'running'",
+                    rusqlite::params![error, attempts, delay, id],
+                )?;
+            } else {
+                conn.execute(
+                    "UPDATE generation_jobs SET status = 'failed', error = ?1, attempts = ?2, updated_at = datetime('now')
+                     WHERE id = ?3 AND status = 'running'",
+                    rusqlite::params![error, attempts, id],
+                )?;
+            }
+        }
+    }
+    emit_job(app, &conn, id);
+    Ok(())
+}
+
+/// Mark a job failed with an error that stopped `run_job` before it could record the outcome itself.
+fn fail_job(app: &tauri::AppHandle, id: i64, error: &AppError) {
+    let Ok(conn) = open_db(app) else { return };
+    let _ = conn.execute(
+        "UPDATE generation_jobs SET status = 'failed', error = ?1, updated_at = datetime('now')
+         WHERE id = ?2 AND status = 'running'",
+        rusqlite::params![error.to_string(), id],
+    );
+    emit_job(app, &conn, id);
+}
+
+/// Abort the running task for a job, if any.
+fn abort_job(queue: &GenerationQueue, id: i64) {
+    if let Ok(mut running) = queue.running.lock() {
+        if let Some(handle) = running.remove(&id) {
+            handle.abort();
+        }
+    }
+}
+
+/// Insert one job unless it exists. It starts dismissed when the same content (same hash, even under
+/// another name, and same PDF page) already produced a card.
+fn insert_job(
+    conn: &rusqlite::Connection,
+    path: &str,
+    page: u32,
+    chunk: usize,
+    source_hash: Option<&str>,
+    source_text: Option<&str>,
+) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT OR IGNORE INTO generation_jobs (path, page, chunk, source_hash, source_text, status)
+         VALUES (?1, ?2, ?3, ?4, ?5, CASE WHEN EXISTS (
+             SELECT 1 FROM cards WHERE source_hash = ?4 AND IFNULL(source_page, 0) = ?2 AND deleted_at IS NULL
+         ) THEN 'dismissed' ELSE 'pending' END)",
+        rusqlite::params![path, page, chunk as i64, source_hash, source_text],
+    )?;
+    Ok(())
+}
+
+/// Add jobs for files and return the jobs for all given paths. Paths that already have jobs keep them;
+/// cancelled ones are put back in the queue. PDFs are split into text chunks here, so their jobs are
+/// ready to run except for image-only pages, which wait for OCR like image files.
+pub(crate) fn enqueue_paths(conn: &rusqlite::Connection, paths: &[String]) -> Result<Vec<GenerationJob>, AppError> {
+    let mut jobs = Vec::new();
+    for path in paths {
+        let known: i64 = conn
+            .query_row("SELECT COUNT(*) FROM generation_jobs WHERE path = ?1", rusqlite::params![path], |row| {
+                row.get(0)
+            })?;
+        if known == 0 {
+            let source_hash = crate::file_sha256(path).ok();
+            if crate::pdf::is_pdf(path) {
+                for page in crate::pdf::extract_pages(path)? {
+                    if page.needs_ocr() {
+                        insert_job(conn, path, page.number, 0, source_hash.as_deref(), None)?;
+                    }
+                    for (chunk, text) in page.chunks.iter().enumerate() {
+                        insert_job(conn, path, page.number, chunk, source_hash.as_deref(), Some(text))?;
+                    }
+                }
+            } else {
+                insert_job(conn, path, 0, 0, source_hash.as_deref(), None)?;
+            }
+        }
+        conn.execute(
+            "UPDATE generation_jobs SET status = 'pending', attempts = 0, next_attempt_at = NULL, updated_at = datetime('now')
+             WHERE path = ?1 AND status = 'cancelled'",
+            rusqlite::params![path],
+        )?;
+        let mut stmt = conn
+            .prepare(&format!("SELECT {JOB_COLUMNS} FROM generation_jobs WHERE path = ?1 ORDER BY page, chunk"))?;
+        let rows = stmt.query_map(rusqlite::params![path], row_to_job)?;
+        for row in rows {
+            jobs.push(row?);
+        }
+    }
+    Ok(jobs)
+}
+
+#[tauri::command]
+pub fn enqueue_generation_jobs(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, Arc<GenerationQueue>>,
+    paths: Vec<String>,
+) -> Result<Vec<GenerationJob>, AppError> {
+    let conn = open_db(&app)?;
+    let jobs = enqueue_paths(&conn, &paths)?;
+    queue.wake();
+    Ok(jobs)
+}
+
+/// All jobs except dismissed ones, in the order they were queued.
+#[tauri::command]
+pub fn list_generation_jobs(app: tauri::AppHandle) -> Result<Vec<GenerationJob>, AppError> {
+    let conn = open_db(&app)?;
+    let mut stmt = conn
+        .prepare(&format!("SELECT {JOB_COLUMNS} FROM generation_jobs WHERE status != 'dismissed' ORDER BY id"))?;
+    let rows = stmt.query_map([], row_to_job)?;
+    let mut jobs = Vec::new();
+    for row in rows {
+        jobs.push(row?);
+    }
+    Ok(jobs)
+}
+
+/// Store OCR (or user-edited) text for a job; pending jobs become eligible to run. `error` says why
+/// the file could not be read, which fails the job instead of leaving it with no text to generate from.
+#[tauri::command]
+pub fn set_generation_job_text(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, Arc<GenerationQueue>>,
+    id: i64,
+    text: String,
+    error: Option<String>,
+) -> Result<GenerationJob, AppError> {
+    let conn = open_db(&app)?;
+    conn.execute(
+        "UPDATE generation_jobs SET source_text = ?1, error = COALESCE(?2, error),
+            status = CASE WHEN ?2 IS NULL THEN status ELSE 'failed' END, updated_at = datetime('now')
+         WHERE id = ?3",
+        rusqlite::params![text, error, id],
+    )?;
+    let job = get_job(&conn, id)?;
+    let _ = app.emit(JOB_UPDATED_EVENT, job.clone());
+    queue.wake.notify_one();
+    Ok(job)
+}
+
+/// Run a job now, even if the queue is paused or the job already finished or failed.
+#[tauri::command]
+pub fn run_generation_job(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, Arc<GenerationQueue>>,
+    id: i64,
+) -> Result<(), AppError> {
+    let conn = open_db(&app)?;
+    let job = get_job(&conn, id)?;
+    if job.source_text.is_none() {
+        return Err(AppError::validation("id", "This file has not been read yet"));
+    }
+    abort_job(&queue, id);
+    conn.execute(
+        "UPDATE generation_jobs SET attempts = 0, error = NULL, next_attempt_at = NULL WHERE id = ?1",
+        rusqlite::params![id],
+    )?;
+    spawn_job(&app, queue.inner(), &conn, id)
+}
+
+/// Cancel pending and running jobs; all of them when `ids` is omitted.
+#[tauri::command]
+pub fn cancel_generation_jobs(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, Arc<GenerationQueue>>,
+    ids: Option<Vec<i64>>,
+) -> Result<(), AppError> {
+    let conn = open_db(&app)?;
+    let ids: Vec<i64> = match ids {
+        Some(ids) => ids,
+        None => conn
+            .prepare("SELECT id FROM generation_jobs WHERE status IN ('pending', 'running')")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?,
+    };
+    for id in ids {
+        abort_job(&queue, id);
+        conn.execute(
+            "UPDATE generation_jobs SET status = 'cancelled', updated_at = datetime('now')
+             WHERE id = ?1 AND status IN ('pending', 'running')",
+            rusqlite::params![id],
+        )?;
+        emit_job(&app, &conn, id);
+    }
+    Ok(())
+}
+
+/// Mark a job as handled (card saved or skipped) so it is hidden and its file is not queued again.
+#[tauri::command]
+pub fn dismiss_generation_job(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, Arc<GenerationQueue>>,
+    id: i64,
+) -> Result<(), AppError> {
+    let conn = open_db(&app)?;
+    abort_job(&queue, id);
+    conn.execute(
+        "UPDATE generation_jobs SET status = 'dismissed', updated_at = datetime('now') WHERE id = ?1",
+        rusqlite::params![id],
+    )?;
+    emit_job(&app, &conn, id);
+    queue.wake.notify_one();
+    Ok(())
+}
+
+/// Forget every job, including dismissed ones, so the same files can be processed from scratch.
+#[tauri::command]
+pub fn clear_generation_jobs(app: tauri::AppHandle, queue: tauri::State<'_, Arc<GenerationQueue>>) -> Result<(), AppError> {
+    if let Ok(mut running) = queue.running.lock() {
+        for (_, handle) in running.drain() {
+            handle.abort();
+        }
+    }
+    let conn = open_db(&app)?;
+    conn.execute("DELETE FROM generation_jobs", [])?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_generation_queue(app: tauri::AppHandle) -> Result<GenerationQueueSettings, AppError> {
+    let conn = open_db(&app)?;
+    read_settings(&conn)
+}
+
+#[tauri::command]
+pub fn update_generation_queue(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, Arc<GenerationQueue>>,
+    settings: GenerationQueueSettings,
+) -> Result<GenerationQueueSettings, AppError> {
+    if settings.concurrency == 0 {
+        return Err(AppError::validation("concurrency", "Concurrency must be at least 1"));
+    }
+    if settings.max_attempts == 0 {
+        return Err(AppError::validation("maxAttempts", "Max attempts must be at least 1"));
+    }
+    let conn = open_db(&app)?;
+    conn.execute(
+        "UPDATE generation_queue SET paused = ?1, concurrency = ?2, max_attempts = ?3, host = ?4, model = ?5, prompt_prefix = ?6
+         WHERE id = 1",
+        rusqlite::params![settings.paused, settings.concurrency, settings.max_attempts, settings.host.as_str(), settings.model, settings.prompt_prefix],
+    )?;
+    let _ = app.emit(QUEUE_UPDATED_EVENT, settings.clone());
+    queue.wake.notify_one();
+    Ok(settings)
+}
+
+fn set_paused(app: &tauri::AppHandle, queue: &GenerationQueue, paused: bool) -> Result<GenerationQueueSettings, AppError> {
+    let conn = open_db(app)?;
+    conn.execute("UPDATE generation_queue SET paused = ?1 WHERE id = 1", rusqlite::params![paused])?;
+    let settings = read_settings(&conn)?;
+    let _ = app.emit(QUEUE_UPDATED_EVENT, settings.clone());
+    queue.wake.notify_one();
+    Ok(settings)
+}
+
+/// Stop starting new jobs; jobs already running are allowed to finish.
+#[tauri::command]
+pub fn pause_generation_queue(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, Arc<GenerationQueue>>,
+) -> Result<GenerationQueueSettings, AppError> {
+    set_paused(&app, &queue, true)
+}
+
+#[tauri::command]
+pub fn resume_generation_queue(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, Arc<GenerationQueue>>,
+) -> Result<GenerationQueueSettings, AppError> {
+    set_paused(&app, &queue, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        assert_eq!(retry_delay_seconds(1), 5);
+        assert_eq!(retry_delay_seconds(2), 10);
+        assert_eq!(retry_delay_seconds(4), 40);
+        assert_eq!(retry_delay_seconds(7), RETRY_MAX_SECONDS);
+        assert_eq!(retry_delay_seconds(100), RETRY_MAX_SECONDS);
+    }
+
+    #[test]
+    fn parse_flashcard_falls_back_to_empty() {
+        assert_eq!(
+            parse_flashcard(r#"{"question": "Q?", "answer": "A."}"#),
+            ("Q?".to_string(), "A.".to_string())
+        );
+        assert_eq!(parse_flashcard("not json"), (String::new(), String::new()));
+    }
+
+    #[test]
+    fn jobs_table_migrates_from_one_job_per_path() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE generation_jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL UNIQUE,
+                status TEXT NOT NULL DEFAULT 'pending',
+                source_text TEXT,
+                response TEXT NOT NULL DEFAULT '',
+                question TEXT NOT NULL DEFAULT '',
+                answer TEXT NOT NULL DEFAULT '',
+                error TEXT,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            INSERT INTO generation_jobs (path, status, question) VALUES ('a.pdf', 'done', 'Q');",
+        )
+        .unwrap();
+        init_jobs_tables(&conn).unwrap();
+        init_jobs_tables(&conn).unwrap();
+        let job = get_job(&conn, 1).unwrap();
+        assert_eq!((job.path.as_str(), job.page, job.status.as_str()), ("a.pdf", None, "done"));
+        conn.execute("INSERT INTO generation_jobs (path, page) VALUES ('a.pdf', 2)", []).unwrap();
+    }
+}
@@ -0,0 +1,400 @@
+//! Undo/redo journal for destructive operations.
+//!
+//! An operation snapshots the rows it is about to change (`track`) and, when it commits, the same
+//! rows afterwards. Undo writes the before images back, redo the after images, so one journal entry
+//! covers a whole bulk operation. Rows are addressed by `rowid` so any table can be journaled.
+//! Undo and redo refuse to run when a row no longer holds the image they expect to replace.
+
+use crate::error::AppError;
+use base64::Engine;
+
+/// Undo entries kept; older ones are dropped as new operations are recorded.
+const UNDO_HISTORY_LIMIT: i64 = 50;
+
+pub(crate) fn init_journal_tables(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS undo_journal (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            label TEXT NOT NULL,
+            changes TEXT NOT NULL,
+            undone INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+type Row = serde_json::Map<String, serde_json::Value>;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RowChange {
+    table: String,
+    rowid: i64,
+    /// `None` when the operation created the row.
+    before: Option<Row>,
+    /// `None` when the operation deleted the row.
+    after: Option<Row>,
+}
+
+fn to_json(value: rusqlite::types::ValueRef) -> serde_json::Value {
+    use rusqlite::types::ValueRef;
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => f.into(),
+        ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned().into(),
+        ValueRef::Blob(b) => serde_json::json!({ "blob": base64::engine::general_purpose::STANDARD.encode(b) }),
+    }
+}
+
+fn to_sql(value: &serde_json::Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value;
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        serde_json::Value::Object(o) => match o.get("blob").and_then(|b| b.as_str()) {
+            Some(b) => Value::Blob(base64::engine::general_purpose::STANDARD.decode(b).unwrap_or_default()),
+            None => Value::Null,
+        },
+        serde_json::Value::Array(_) => Value::Null,
+    }
+}
+
+/// Rows of `table` matching `condition`, keyed by rowid.
+fn select_rows<P: rusqlite::Params>(
+    conn: &rusqlite::Connection,
+    table: &str,
+    condition: &str,
+    params: P,
+) -> Result<Vec<(i64, Row)>, AppError> {
+    let mut stmt = conn.prepare(&format!("SELECT rowid, * FROM {table} WHERE {condition}"))?;
+    let names: Vec<String> = stmt.column_names().into_iter().skip(1).map(str::to_string).collect();
+    let rows = stmt
+        .query_map(params, |row| {
+            let mut values = Row::new();
+            for (i, name) in names.iter().enumerate() {
+                values.insert(name.clone(), to_json(row.get_ref(i + 1)?));
+            }
+            Ok((row.get::<_, i64>(0)?, values))
+        })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// One undoable operation being recorded. Track rows before changing them, then `commit` in the same
+/// transaction as the change.
+pub(crate) struct Operation {
+    label: String,
+    changes: Vec<RowChange>,
+}
+
+impl Operation {
+    pub(crate) fn new(label: impl Into<String>) -> Self {
+        Operation { label: label.into(), changes: Vec::new() }
+    }
+
+    /// Snapshot rows of `table` matching `condition` that this operation is about to update or delete.
+    /// Track children before parents so undo restores parents first.
+    pub(crate) fn track<P: rusqlite::Params>(
+        &mut self,
+        conn: &rusqlite::Connection,
+        table: &str,
+        condition: &str,
+        params: P,
+    ) -> Result<(), AppError> {
+        for (rowid, row) in select_rows(conn, table, condition, params)? {
+            self.changes.push(RowChange { table: table.to_string(), rowid, before: Some(row), after: None });
+        }
+        Ok(())
+    }
+
+    /// Record a row this operation inserted, so undo deletes it again.
+    pub(crate) fn track_inserted(&mut self, table: &str, rowid: i64) {
+        self.changes.push(RowChange { table: table.to_string(), rowid, before: None, after: None });
+    }
+
+    /// Read the after image of every tracked row and store the entry. Recording a new operation
+    /// discards anything that was undone (the redo history) and entries beyond the history limit.
+    pub(crate) fn commit(mut self, conn: &rusqlite::Connection) -> Result<(), AppError> {
+        if self.changes.is_empty() {
+            return Ok(());
+        }
+        for change in &mut self.changes {
+            change.after = select_rows(conn, &change.table, "rowid = ?1", rusqlite::params![change.rowid])?
+                .pop()
+                .map(|(_, row)| row);
+        }
+        let changes = serde_json::to_string(&self.changes)?;
+        conn.execute("DELETE FROM undo_journal WHERE undone = 1", [])?;
+        conn.execute(
+            "INSERT INTO undo_journal (label, changes) VALUES (?1, ?2)",
+            rusqlite::params![self.label, changes],
+        )?;
+        conn.execute(
+            "DELETE FROM undo_journal WHERE id <= (SELECT MAX(id) FROM undo_journal) - ?1",
+            rusqlite::params![UNDO_HISTORY_LIMIT],
+        )?;
+        Ok(())
+    }
+}
+
+/// Forget the whole undo and redo history, for when rows it refers to are gone for good.
+pub(crate) fn clear(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    conn.execute("DELETE FROM undo_journal", [])?;
+    Ok(())
+}
+
+/// Whether a row is still in the given state: absent when `image` is `None`, otherwise holding the
+/// image's values. Columns added to the table since the image was taken are not compared.
+fn row_matches(conn: &rusqlite::Connection, table: &str, rowid: i64, image: Option<&Row>) -> Result<bool, AppError> {
+    let current = select_rows(conn, table, "rowid = ?1", rusqlite::params![rowid])?.pop().map(|(_, row)| row);
+    Ok(match (current, image) {
+        (None, None) => true,
+        (Some(current), Some(image)) => image.iter().all(|(name, value)| current.get(name).is_none_or(|c| c == value)),
+        _ => false,
+    })
+}
+
+/// Put a row into the given state: delete it when `image` is `None`, otherwise update or re-insert it.
+/// Columns dropped from the table since the image was taken are left out.
+fn apply_image(conn: &rusqlite::Connection, table: &str, rowid: i64, image: Option<&Row>) -> Result<(), AppError> {
+    let Some(row) = image else {
+        conn.execute(&format!("DELETE FROM {table} WHERE rowid = ?1"), rusqlite::params![rowid])?;
+        return Ok(());
+    };
+    let existing: std::collections::HashSet<String> = conn
+        .prepare("SELECT name FROM pragma_table_info(?1)")?
+        .query_map(rusqlite::params![table], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let row: Vec<(&String, &serde_json::Value)> = row.iter().filter(|(name, _)| existing.contains(*name)).collect();
+    let columns: Vec<&str> = row.iter().map(|(name, _)| name.as_str()).collect();
+    let mut values = vec![rusqlite::types::Value::Integer(rowid)];
+    values.extend(row.iter().map(|(_, value)| to_sql(value)));
+    let assignments: Vec<String> = columns.iter().enumerate().map(|(i, c)| format!("{c} = ?{}", i + 2)).collect();
+    let updated = conn
+        .execute(
+            &format!("UPDATE {table} SET {} WHERE rowid = ?1", assignments.join(", ")),
+            rusqlite::params_from_iter(values.iter()),
+        )?;
+    if updated == 0 {
+        let placeholders: Vec<String> = (1..=values.len()).map(|i| format!("?{i}")).collect();
+        conn.execute(
+            &format!("INSERT INTO {table} (rowid, {}) VALUES ({})", columns.join(", "), placeholders.join(", ")),
+            rusqlite::params_from_iter(values.iter()),
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct UndoEntry {
+    id: i64,
+    label: String,
+    created_at: String,
+}
+
+/// What undo and redo would do next, for labelling the buttons.
+#[derive(serde::Serialize)]
+pub struct UndoState {
+    undo: Option<UndoEntry>,
+    redo: Option<UndoEntry>,
+}
+
+fn next_entry(conn: &rusqlite::Connection, undone: bool) -> Result<Option<(UndoEntry, String)>, AppError> {
+    // Undo takes the newest entry still applied; redo the most recently undone, i.e. the oldest undone.
+    let sql = if undone {
+        "SELECT id, label, created_at, changes FROM undo_journal WHERE undone = 1 ORDER BY id LIMIT 1"
+    } else {
+        "SELECT id, label, created_at, changes FROM undo_journal WHERE undone = 0 ORDER BY id DESC LIMIT 1"
+    };
+    match conn.query_row(sql, [], |row| {
+        Ok((UndoEntry { id: row.get(0)?, label: row.get(1)?, created_at: row.get(2)? }, row.get(3)?))
+    }) {
+        Ok(entry) => Ok(Some(entry)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Undo (`undo = true`) or redo the next entry in one transaction.
+fn step(app: &tauri::AppHandle, undo: bool) -> Result<Option<UndoEntry>, AppError> {
+    let mut conn = crate::open_db(app)?;
+    let tx = conn.transaction()?;
+    let entry = apply_next(&tx, undo)?;
+    tx.commit()?;
+    Ok(entry)
+}
+
+/// Undo or redo the next entry on `tx`; the caller commits.
+pub(crate) fn apply_next(tx: &rusqlite::Connection, undo: bool) -> Result<Option<UndoEntry>, AppError> {
+    let Some((entry, changes)) = next_entry(tx, !undo)? else {
+        return Ok(None);
+    };
+    let changes: Vec<RowChange> = serde_json::from_str(&changes)?;
+    let verb = if undo { "undo" } else { "redo" };
+    // Refuse when a row was changed outside the journal since: writing the image back would silently
+    // discard that change (or recreate a row deleted since). A row tracked twice is checked once, against
+    // the state before the entry for redo; both copies share the same after image for undo.
+    let mut checked = std::collections::HashSet::new();
+    for change in &changes {
+        if !checked.insert((change.table.as_str(), change.rowid)) {
+            continue;
+        }
+        let expected = if undo { change.after.as_ref() } else { change.before.as_ref() };
+        if !row_matches(tx, &change.table, change.rowid, expected)? {
+            return Err(AppError::Conflict(format!(
+                "Cannot {verb} \"{}\": what it changed has been changed again since",
+                entry.label
+            )));
+        }
+    }
+    if undo {
+        for change in changes.iter().rev() {
+            apply_image(tx, &change.table, change.rowid, change.before.as_ref())
+                .map_err(|e| e.context(format!("Cannot {verb} \"{}\"", entry.label)))?;
+        }
+    } else {
+        for change in &changes {
+            apply_image(tx, &change.table, change.rowid, change.after.as_ref())
+                .map_err(|e| e.context(format!("Cannot {verb} \"{}\"", entry.label)))?;
+        }
+    }
+    tx.execute("UPDATE undo_journal SET undone = ?1 WHERE id = ?2", rusqlite::params![undo, entry.id])?;
+    Ok(Some(entry))
+}
+
+/// Undo the most recent operation; returns it, or `None` when there is nothing to undo.
+#[tauri::command]
+pub fn undo_last(app: tauri::AppHandle) -> Result<Option<UndoEntry>, AppError> {
+    step(&app, true)
+}
+
+/// Redo the most recently undone operation; returns it, or `None` when there is nothing to redo.
+#[tauri::command]
+pub fn redo(app: tauri::AppHandle) -> Result<Option<UndoEntry>, AppError> {
+    step(&app, false)
+}
+
+#[tauri::command]
+pub fn get_undo_state(app: tauri::AppHandle) -> Result<UndoState, AppError> {
+    let conn = crate::open_db(&app)?;
+    Ok(UndoState {
+        undo: next_entry(&conn, false)?.map(|(entry, _)| entry),
+        redo: next_entry(&conn, true)?.map(|(entry, _)| entry),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_apply_without_dropped_columns() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL, flag INTEGER NOT NULL DEFAULT 0);
+             INSERT INTO items (id, name, flag) VALUES (1, 'a', 1), (2, 'b', 0);",
+        )
+        .unwrap();
+        let mut images = select_rows(&conn, "items", "1", []).unwrap();
+        conn.execute_batch("DELETE FROM items; ALTER TABLE items DROP COLUMN flag;").unwrap();
+        let (rowid, row) = images.remove(0);
+        apply_image(&conn, "items", rowid, Some(&row)).unwrap();
+        let name: String = conn.query_row("SELECT name FROM items WHERE id = 1", [], |r| r.get(0)).unwrap();
+        assert_eq!(name, "a");
+        apply_image(&conn, "items", 1, None).unwrap();
+        let left: i64 = conn.query_row("SELECT COUNT(*) FROM items", [], |r| r.get(0)).unwrap();
+        assert_eq!(left, 0);
+    }
+
+    fn names(conn: &rusqlite::Connection) -> Vec<String> {
+        conn.prepare("SELECT name FROM items ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn rename(conn: &rusqlite::Connection, id: i64, name: &str) {
+        let mut op = Operation::new(format!("Rename {id}"));
+        op.track(conn, "items", "id = ?1", [id]).unwrap();
+        conn.execute("UPDATE items SET name = ?1 WHERE id = ?2", rusqlite::params![name, id]).unwrap();
+        op.commit(conn).unwrap();
+    }
+
+    #[test]
+    fn undo_and_redo_replay_whole_operations() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        init_journal_tables(&conn).unwrap();
+        conn.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL); INSERT INTO items VALUES (1, 'a'), (2, 'b');")
+            .unwrap();
+
+        let mut op = Operation::new("Delete both");
+        op.track(&conn, "items", "1", []).unwrap();
+        conn.execute("DELETE FROM items", []).unwrap();
+        conn.execute("INSERT INTO items (id, name) VALUES (3, 'c')", []).unwrap();
+        op.track_inserted("items", 3);
+        op.commit(&conn).unwrap();
+        assert_eq!(names(&conn), vec!["c"]);
+
+        assert_eq!(apply_next(&conn, true).unwrap().unwrap().label, "Delete both");
+        assert_eq!(names(&conn), vec!["a", "b"]);
+        assert!(apply_next(&conn, true).unwrap().is_none());
+        apply_next(&conn, false).unwrap().unwrap();
+        assert_eq!(names(&conn), vec!["c"]);
+
+        // A new operation after an undo drops what could have been redone.
+        apply_next(&conn, true).unwrap();
+        rename(&conn, 1, "z");
+        assert!(next_entry(&conn, true).unwrap().is_none());
+        assert_eq!(names(&conn), vec!["z", "b"]);
+    }
+
+    #[test]
+    fn undo_refuses_rows_changed_since() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        init_journal_tables(&conn).unwrap();
+        conn.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL); INSERT INTO items VALUES (1, 'a'), (2, 'b');")
+            .unwrap();
+        rename(&conn, 1, "x");
+        rename(&conn, 1, "y");
+        conn.execute("UPDATE items SET name = 'edited' WHERE id = 1", []).unwrap();
+        assert_eq!(apply_next(&conn, true).err().map(|e| e.code()), Some("conflict"));
+        assert_eq!(names(&conn), vec!["edited", "b"]);
+
+        // A row tracked twice in one entry is checked against the state before the entry.
+        conn.execute("UPDATE items SET name = 'y' WHERE id = 1", []).unwrap();
+        let mut op = Operation::new("Rename twice");
+        op.track(&conn, "items", "id = 2", []).unwrap();
+        conn.execute("UPDATE items SET name = 'c' WHERE id = 2", []).unwrap();
+        op.track(&conn, "items", "id = 2", []).unwrap();
+        conn.execute("UPDATE items SET name = 'd' WHERE id = 2", []).unwrap();
+        op.commit(&conn).unwrap();
+        apply_next(&conn, true).unwrap().unwrap();
+        assert_eq!(names(&conn), vec!["y", "b"]);
+        apply_next(&conn, false).unwrap().unwrap();
+        assert_eq!(names(&conn), vec!["y", "d"]);
+
+        conn.execute("DELETE FROM items WHERE id = 2", []).unwrap();
+        assert_eq!(apply_next(&conn, true).err().map(|e| e.code()), Some("conflict"));
+        assert_eq!(names(&conn), vec!["y"]);
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        init_journal_tables(&conn).unwrap();
+        conn.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL); INSERT INTO items VALUES (1, 'a');").unwrap();
+        for i in 0..UNDO_HISTORY_LIMIT + 5 {
+            rename(&conn, 1, &i.to_string());
+        }
+        let entries: i64 = conn.query_row("SELECT COUNT(*) FROM undo_journal", [], |r| r.get(0)).unwrap();
+        assert_eq!(entries, UNDO_HISTORY_LIMIT);
+    }
+}
@@ -0,0 +1,175 @@
+//! Leeches: cards that keep being forgotten.
+//!
+//! When a review makes one of a card's prompts lapse for the threshold'th time or later, the card is
+//! marked as a leech (`leech_at`), and suspended too if the leech action says so. The mark stays
+//! until cleared, typically after rewriting the card, and travels with the card through export.
+
+use crate::error::AppError;
+use crate::journal;
+
+/// Lapses of one prompt that make its card a leech.
+const DEFAULT_THRESHOLD: u32 = 8;
+const THRESHOLD_SETTING: &str = "leech_threshold";
+const SUSPEND_SETTING: &str = "leech_suspend";
+
+pub(crate) fn init_leech_column(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    crate::add_column_if_missing(conn, "cards", "leech_at", "TEXT")
+}
+
+fn threshold(conn: &rusqlite::Connection) -> Result<u32, AppError> {
+    Ok(crate::get_setting(conn, THRESHOLD_SETTING)?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_THRESHOLD))
+}
+
+fn suspends(conn: &rusqlite::Connection) -> Result<bool, AppError> {
+    Ok(crate::get_setting(conn, SUSPEND_SETTING)?.as_deref() == Some("1"))
+}
+
+/// After a review made the prompt lapse, mark its card as a leech if `lapses` reached the threshold,
+/// and suspend it if configured. A leech is suspended again on each further lapse. Returns whether
+/// the card is a leech.
+pub(crate) fn check_lapse(conn: &rusqlite::Connection, prompt_id: i64, lapses: i64) -> Result<bool, AppError> {
+    if lapses < i64::from(threshold(conn)?) {
+        return Ok(false);
+    }
+    let suspend = if suspends(conn)? { "COALESCE(suspended_at, datetime('now'))" } else { "suspended_at" };
+    conn.execute(
+        &format!(
+            "UPDATE cards SET leech_at = COALESCE(leech_at, datetime('now')), suspended_at = {suspend}
+             WHERE id = (SELECT card_id FROM card_prompts WHERE id = ?1)"
+        ),
+        rusqlite::params![prompt_id],
+    )?;
+    Ok(true)
+}
+
+#[derive(serde::Serialize)]
+pub struct LeechCard {
+    id: i64,
+    question: String,
+    answer: String,
+    hint: String,
+    sub_collection_id: i64,
+    /// Lapses over all of the card's prompts.
+    lapses: i64,
+    reps: i64,
+    leech_at: String,
+    suspended_at: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct Leeches {
+    cards: Vec<LeechCard>,
+    threshold: u32,
+    suspend: bool,
+}
+
+/// The collection's cards marked as leeches, most lapses first, with the leech settings.
+#[tauri::command]
+pub fn get_leeches(app: tauri::AppHandle, collection_id: i64) -> Result<Leeches, AppError> {
+    let conn = crate::open_db(&app)?;
+    let mut stmt = conn.prepare(
+        "SELECT c.id, c.question, c.answer, COALESCE(c.hint, ''), c.sub_collection_id,
+                COALESCE(SUM(p.lapses), 0), COALESCE(SUM(p.reps), 0), c.leech_at, c.suspended_at
+         FROM cards c LEFT JOIN card_prompts p ON p.card_id = c.id
+         WHERE c.collection_id = ?1 AND c.deleted_at IS NULL AND c.leech_at IS NOT NULL
+         GROUP BY c.id
+         ORDER BY 6 DESC, c.id",
+    )?;
+    let cards = stmt
+        .query_map(rusqlite::params![collection_id], |row| {
+            Ok(LeechCard {
+                id: row.get(0)?,
+                question: row.get(1)?,
+                answer: row.get(2)?,
+                hint: row.get(3)?,
+                sub_collection_id: row.get(4)?,
+                lapses: row.get(5)?,
+                reps: row.get(6)?,
+                leech_at: row.get(7)?,
+                suspended_at: row.get(8)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Leeches { cards, threshold: threshold(&conn)?, suspend: suspends(&conn)? })
+}
+
+/// Lapses of a prompt that make its card a leech, and whether leeches are also suspended.
+#[tauri::command]
+pub fn set_leech_options(app: tauri::AppHandle, threshold: u32, suspend: bool) -> Result<(), AppError> {
+    if threshold == 0 {
+        return Err(AppError::validation("threshold", "The leech threshold must be at least 1"));
+    }
+    let conn = crate::open_db(&app)?;
+    crate::set_setting(&conn, THRESHOLD_SETTING, &threshold.to_string())?;
+    crate::set_setting(&conn, SUSPEND_SETTING, if suspend { "1" } else { "0" })
+}
+
+/// Remove the leech mark from a card, e.g. once it has been rewritten. It stays suspended if it was.
+#[tauri::command]
+pub fn clear_leech(app: tauri::AppHandle, card_id: i64) -> Result<(), AppError> {
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    let question: String = tx
+        .query_row(
+            "SELECT question FROM cards WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![card_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::not_found("Card not found"))?;
+    let mut op = journal::Operation::new(format!("Clear leech \"{}\"", crate::truncate_label(&question)));
+    op.track(&tx, "cards", "id = ?1", rusqlite::params![card_id])?;
+    tx.execute("UPDATE cards SET leech_at = NULL WHERE id = ?1", rusqlite::params![card_id])?;
+    op.commit(&tx)?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> (rusqlite::Connection, i64) {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A');
+             INSERT INTO sub_collections (id, name, collection_id) VALUES (1, '- None -', 1);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES (1, 'Q', 'A', 1, 1);",
+        )
+        .unwrap();
+        crate::card_types::fill_missing_prompts(&conn).unwrap();
+        let prompt = conn.query_row("SELECT id FROM card_prompts WHERE card_id = 1", [], |r| r.get(0)).unwrap();
+        (conn, prompt)
+    }
+
+    fn marks(conn: &rusqlite::Connection) -> (bool, bool) {
+        conn.query_row("SELECT leech_at IS NOT NULL, suspended_at IS NOT NULL FROM cards WHERE id = 1", [], |r| {
+            Ok((r.get(0)?, r.get(1)?))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn cards_become_leeches_at_the_threshold() {
+        let (conn, prompt) = db();
+        assert!(!check_lapse(&conn, prompt, i64::from(DEFAULT_THRESHOLD) - 1).unwrap());
+        assert_eq!(marks(&conn), (false, false));
+        assert!(check_lapse(&conn, prompt, i64::from(DEFAULT_THRESHOLD)).unwrap());
+        assert_eq!(marks(&conn), (true, false));
+    }
+
+    #[test]
+    fn leeches_are_suspended_when_configured() {
+        let (conn, prompt) = db();
+        crate::set_setting(&conn, THRESHOLD_SETTING, "2").unwrap();
+        crate::set_setting(&conn, SUSPEND_SETTING, "1").unwrap();
+        assert!(check_lapse(&conn, prompt, 2).unwrap());
+        assert_eq!(marks(&conn), (true, true));
+        // Unsuspended by hand, a leech is suspended again on its next lapse.
+        conn.execute("UPDATE cards SET suspended_at = NULL", []).unwrap();
+        assert!(check_lapse(&conn, prompt, 3).unwrap());
+        assert_eq!(marks(&conn), (true, true));
+    }
+}
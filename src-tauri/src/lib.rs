@@ -1,18 +1,57 @@
 use tauri::Manager;
 use tauri_plugin_dialog::DialogExt;
 
+mod backup;
+mod bulk;
+mod card_types;
+mod credentials;
+mod duplicates;
+mod error;
+mod grading;
+mod hierarchy;
+mod integrity;
+mod jobs;
+mod journal;
+mod leech;
+mod llm;
+mod notes;
+mod options;
+mod pdf;
+mod scan;
+mod schedule;
+mod study;
+mod suspend;
+mod text;
+mod trash;
+mod transfer;
+mod watch;
+
+use error::AppError;
+
 const NULL_SUB_COLLECTION_NAME: &str = "- None -";
 
-fn db_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+/// Returns the app data dir, creating it if needed.
+fn app_data_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
     let path = app
         .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
-    std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
-    Ok(path.join("cards.db"))
+        .app_data_dir()?;
+    std::fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
+fn db_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
+    Ok(app_data_dir(app)?.join("cards.db"))
+}
+
+/// Open the cards database with the schema initialized.
+fn open_db(app: &tauri::AppHandle) -> Result<rusqlite::Connection, AppError> {
+    let path = db_path(app)?;
+    let conn = rusqlite::Connection::open(&path)?;
+    init_db(&conn)?;
+    Ok(conn)
 }
 
-fn init_db(conn: &rusqlite::Connection) -> Result<(), String> {
+fn init_db(conn: &rusqlite::Connection) -> Result<(), AppError> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS collections (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -20,20 +59,18 @@ fn init_db(conn: &rusqlite::Connection) -> Result<(), String> {
             created_at TEXT NOT NULL DEFAULT (datetime('now'))
         )",
         [],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     conn.execute(
         "CREATE TABLE IF NOT EXISTS sub_collections (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL,
-            collection_id INTEGER NOT NULL REFERENCES collections(id),
+            collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            UNIQUE(collection_id, name)
+            parent_id INTEGER REFERENCES sub_collections(id) ON DELETE CASCADE
         )",
         [],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
     conn.execute(
         "CREATE TABLE IF NOT EXISTS cards (
@@ -41,20 +78,225 @@ fn init_db(conn: &rusqlite::Connection) -> Result<(), String> {
             question TEXT NOT NULL,
             answer TEXT NOT NULL,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            collection_id INTEGER NOT NULL REFERENCES collections(id),
+            collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
             hint TEXT NOT NULL DEFAULT '',
-            skipped INTEGER NOT NULL DEFAULT 0,
             sub_collection_id INTEGER NOT NULL REFERENCES sub_collections(id)
         )",
         [],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
+
+    // Adds `deleted_at` and the unique (collection, sub collection, question, answer) index on live cards.
+    trash::init_trash_columns(conn)?;
+    // Adds `parent_id` and makes sub collection names unique among siblings.
+    hierarchy::init_sub_collection_tree(conn)?;
+
+    // Provenance for generated cards: where the content came from and what produced it.
+    add_column_if_missing(conn, "cards", "source_path", "TEXT")?;
+    add_column_if_missing(conn, "cards", "source_hash", "TEXT")?;
+    add_column_if_missing(conn, "cards", "generator", "TEXT")?;
+    add_column_if_missing(conn, "cards", "source_page", "INTEGER")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS cards_source_hash ON cards(source_hash)", [])?;
 
     conn.execute(
-        "CREATE UNIQUE INDEX IF NOT EXISTS cards_uniq_collection_sub_question_answer ON cards(collection_id, sub_collection_id, question, answer)",
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
         [],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
+
+    jobs::init_jobs_tables(conn)?;
+    notes::init_notes_tables(conn)?;
+    journal::init_journal_tables(conn)?;
+    // Adds `card_type` and `choices`, and the per-prompt scheduling state.
+    card_types::init_card_type_tables(conn)?;
+    card_types::fill_missing_prompts(conn)?;
+    // Adds the learning `step` of prompts and the `review_log`.
+    schedule::init_review_log(conn)?;
+    // Presets of daily limits and scheduling options, and `options_id` on collections and sub collections.
+    options::init_options_tables(conn)?;
+    study::init_study_tables(conn)?;
+    // Adds `suspended_at` and `buried_at`, replacing the old `skipped` flag.
+    suspend::init_suspend_columns(conn)?;
+    leech::init_leech_column(conn)?;
+    // Nests sub collections whose flat names are paths; needs every table that refers to them.
+    hierarchy::split_path_names(conn)?;
+
+    migrate_foreign_key_actions(conn)?;
+    // Deleting a sub-collection files its cards under the collection's "no sub-collection", the
+    // per-collection equivalent of ON DELETE SET DEFAULT. Skipped when the whole collection is going.
+    conn.execute(
+        &format!(
+            "CREATE TRIGGER IF NOT EXISTS sub_collections_reassign_cards
+             BEFORE DELETE ON sub_collections
+             WHEN OLD.name != '{NULL_SUB_COLLECTION_NAME}' AND EXISTS (SELECT 1 FROM collections WHERE id = OLD.collection_id)
+             BEGIN
+                 UPDATE cards SET sub_collection_id = (
+                     SELECT id FROM sub_collections WHERE collection_id = OLD.collection_id AND name = '{NULL_SUB_COLLECTION_NAME}'
+                 ) WHERE sub_collection_id = OLD.id;
+             END"
+        ),
+        [],
+    )?;
+    conn.execute("PRAGMA foreign_keys = ON", [])?;
+    Ok(())
+}
+
+/// Foreign keys that delete the child rows with their parent, as (child table, parent table).
+const CASCADING_FOREIGN_KEYS: &[(&str, &str)] = &[
+    ("sub_collections", "collections"),
+    ("cards", "collections"),
+    ("note_cards", "collections"),
+    ("note_cards", "cards"),
+];
+
+/// Add ON DELETE CASCADE to databases created before the schema had it. SQLite cannot alter a
+/// constraint, so each table is rebuilt from its stored CREATE statement with the action added.
+fn migrate_foreign_key_actions(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    for table in ["sub_collections", "cards", "note_cards"] {
+        let mut sql: String = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                rusqlite::params![table],
+                |row| row.get(0),
+            )?;
+        let mut changed = false;
+        for (_, parent) in CASCADING_FOREIGN_KEYS.iter().filter(|(child, _)| *child == table) {
+            let action: String = conn
+                .query_row(
+                    "SELECT on_delete FROM pragma_foreign_key_list(?1) WHERE \"table\" = ?2",
+                    rusqlite::params![table, parent],
+                    |row| row.get(0),
+                )?;
+            if action != "CASCADE" {
+                let clause = format!("REFERENCES {parent}(id)");
+                sql = sql.replacen(&clause, &format!("{clause} ON DELETE CASCADE"), 1);
+                changed = true;
+            }
+        }
+        if changed {
+            rebuild_table(conn, table, &sql)?;
+        }
+    }
+    Ok(())
+}
+
+/// Recreate a table from a changed CREATE statement with the same columns, keeping its rows, indexes
+/// and triggers. This is how constraints are changed, which SQLite cannot alter in place.
+fn rebuild_table(conn: &rusqlite::Connection, table: &str, sql: &str) -> Result<(), AppError> {
+    let mut stmt = conn
+        .prepare("SELECT sql FROM sqlite_master WHERE tbl_name = ?1 AND type IN ('index', 'trigger') AND sql IS NOT NULL")?;
+    let dependents = stmt
+        .query_map(rusqlite::params![table], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    // Build the new table beside the old one rather than renaming the old one away, which would
+    // rewrite the other tables' references to point at it.
+    static NAME: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    // A table SQLite has renamed before is stored as `CREATE TABLE "name"`.
+    let name = NAME.get_or_init(|| regex::Regex::new(r#"^CREATE TABLE\s+(?:"[^"]*"|\w+)"#).expect("valid table name pattern"));
+    let create = name.replace(sql, format!("CREATE TABLE {table}_new"));
+    // Foreign keys cannot be switched inside a transaction; they are off so dropping the old table
+    // does not cascade, and are restored whether or not the rebuild went through.
+    let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let rebuilt = replace_table(conn, table, &create, &dependents);
+    conn.pragma_update(None, "foreign_keys", foreign_keys)?;
+    rebuilt
+}
+
+/// Fill `{table}_new` from `create`, swap it in for `table` and recreate `dependents`, all or nothing.
+fn replace_table(conn: &rusqlite::Connection, table: &str, create: &str, dependents: &[String]) -> Result<(), AppError> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(create, [])?;
+    tx.execute(&format!("INSERT INTO {table}_new SELECT * FROM {table}"), [])?;
+    tx.execute(&format!("DROP TABLE {table}"), [])?;
+    tx.execute(&format!("ALTER TABLE {table}_new RENAME TO {table}"), [])?;
+    for sql in dependents {
+        tx.execute(sql, [])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Add a column to an existing table unless it is already there (schema migration for older databases).
+fn add_column_if_missing(conn: &rusqlite::Connection, table: &str, column: &str, definition: &str) -> Result<(), AppError> {
+    let exists: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+            rusqlite::params![table, column],
+            |row| row.get(0),
+        )?;
+    if exists == 0 {
+        conn.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"), [])?;
+    }
+    Ok(())
+}
+
+/// Read an app-wide setting stored in the `settings` table.
+fn get_setting(conn: &rusqlite::Connection, key: &str) -> Result<Option<String>, AppError> {
+    match conn.query_row("SELECT value FROM settings WHERE key = ?1", rusqlite::params![key], |row| row.get(0)) {
+        Ok(value) => Ok(Some(value)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn set_setting(conn: &rusqlite::Connection, key: &str, value: &str) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        rusqlite::params![key, value],
+    )?;
+    Ok(())
+}
+
+/// Hex-encoded SHA-256 of a file's contents; used to recognize source images across renames.
+fn file_sha256(path: &str) -> Result<String, AppError> {
+    use sha2::Digest;
+    let bytes = std::fs::read(path)?;
+    let digest = sha2::Sha256::digest(&bytes);
+    Ok(digest.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Where a generated card came from. All fields are optional; manual cards have no source.
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct CardSource {
+    path: Option<String>,
+    hash: Option<String>,
+    /// 1-based page, for cards generated from a PDF.
+    page: Option<u32>,
+    /// Model and prompt version, e.g. `ollama/local:glm-4.7-flash@prompt-1a2b3c4d`.
+    generator: Option<String>,
+}
+
+impl CardSource {
+    /// The given hash, or the hash of the file at `path` when the caller only knows its path. A path
+    /// that cannot be read is an error rather than a card without a hash.
+    fn hash(&self) -> Result<Option<String>, AppError> {
+        match (&self.hash, &self.path) {
+            (Some(hash), _) => Ok(Some(hash.clone())),
+            (None, Some(path)) => file_sha256(path)
+                .map(Some)
+                .map_err(|e| e.context(format!("Cannot hash the source file {path}"))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// Check that a card's sub collection is one of its collection's sub collections.
+fn check_sub_collection(conn: &rusqlite::Connection, collection_id: i64, sub_collection_id: i64) -> Result<(), AppError> {
+    let belongs: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sub_collections WHERE id = ?1 AND collection_id = ?2)",
+            rusqlite::params![sub_collection_id, collection_id],
+            |row| row.get(0),
+        )?;
+    if !belongs {
+        return Err(AppError::validation(
+            "subCollectionId",
+            format!("Sub collection {sub_collection_id} does not belong to collection {collection_id}."),
+        ));
+    }
     Ok(())
 }
 
@@ -66,20 +308,27 @@ fn add_card(
     collection_id: i64,
     hint: Option<String>,
     sub_collection_id: Option<i64>,
-) -> Result<(), String> {
+    source: Option<CardSource>,
+) -> Result<(), AppError> {
     let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&path)?;
     init_db(&conn)?;
     let hint = hint.unwrap_or_default();
     let sub_id = match sub_collection_id {
-        Some(id) => id,
+        Some(id) => {
+            check_sub_collection(&conn, collection_id, id)?;
+            id
+        }
         None => get_null_sub_collection_id(&conn, collection_id)?,
     };
+    let source = source.unwrap_or_default();
+    let source_hash = source.hash()?;
     conn.execute(
-        "INSERT INTO cards (question, answer, collection_id, hint, sub_collection_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![question, answer, collection_id, hint, sub_id],
-    )
-    .map_err(|e| map_unique_constraint(e))?;
+        "INSERT INTO cards (question, answer, collection_id, hint, sub_collection_id, source_path, source_hash, generator, source_page)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![question, answer, collection_id, hint, sub_id, source.path, source_hash, source.generator, source.page],
+    )?;
+    card_types::sync_card_prompts(&conn, None, conn.last_insert_rowid())?;
     Ok(())
 }
 
@@ -90,45 +339,41 @@ struct StoredCollection {
 }
 
 #[tauri::command]
-fn get_collections(app: tauri::AppHandle) -> Result<Vec<StoredCollection>, String> {
+fn get_collections(app: tauri::AppHandle) -> Result<Vec<StoredCollection>, AppError> {
     let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&path)?;
     init_db(&conn)?;
-    let mut stmt = conn
-        .prepare("SELECT id, name FROM collections ORDER BY name")
-        .map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare("SELECT id, name FROM collections WHERE deleted_at IS NULL ORDER BY name")?;
     let rows = stmt
         .query_map([], |row| {
             Ok(StoredCollection {
                 id: row.get(0)?,
                 name: row.get(1)?,
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
     let mut collections = Vec::new();
     for row in rows {
-        collections.push(row.map_err(|e| e.to_string())?);
+        collections.push(row?);
     }
     Ok(collections)
 }
 
 #[tauri::command]
-fn create_collection(app: tauri::AppHandle, name: String) -> Result<StoredCollection, String> {
+fn create_collection(app: tauri::AppHandle, name: String) -> Result<StoredCollection, AppError> {
     let name = name.trim();
     if name.is_empty() {
-        return Err("Collection name cannot be empty".to_string());
+        return Err(AppError::validation("name", "Collection name cannot be empty"));
     }
     let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&path)?;
     init_db(&conn)?;
-    conn.execute("INSERT INTO collections (name) VALUES (?1)", rusqlite::params![name])
-        .map_err(|e| e.to_string())?;
+    trash::check_name_not_trashed(&conn, name)?;
+    conn.execute("INSERT INTO collections (name) VALUES (?1)", rusqlite::params![name])?;
     let id = conn.last_insert_rowid();
     conn.execute(
         "INSERT INTO sub_collections (name, collection_id) VALUES (?1, ?2)",
         rusqlite::params![NULL_SUB_COLLECTION_NAME, id],
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
     Ok(StoredCollection {
         id,
         name: name.to_string(),
@@ -136,40 +381,45 @@ fn create_collection(app: tauri::AppHandle, name: String) -> Result<StoredCollec
 }
 
 #[tauri::command]
-fn update_collection(app: tauri::AppHandle, id: i64, name: String) -> Result<StoredCollection, String> {
+fn update_collection(app: tauri::AppHandle, id: i64, name: String) -> Result<StoredCollection, AppError> {
     let name = name.trim();
     if name.is_empty() {
-        return Err("Collection name cannot be empty".to_string());
+        return Err(AppError::validation("name", "Collection name cannot be empty"));
     }
     let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&path)?;
     init_db(&conn)?;
-    conn.execute("UPDATE collections SET name = ?1 WHERE id = ?2", rusqlite::params![name, id])
-        .map_err(|e| e.to_string())?;
+    trash::check_name_not_trashed(&conn, name)?;
+    conn.execute("UPDATE collections SET name = ?1 WHERE id = ?2", rusqlite::params![name, id])?;
     if conn.changes() == 0 {
-        return Err("Collection not found".to_string());
+        return Err(AppError::not_found("Collection not found"));
     }
     Ok(StoredCollection { id, name: name.to_string() })
 }
 
 #[tauri::command]
-fn delete_collection(app: tauri::AppHandle, id: i64) -> Result<(), String> {
-    let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
-    init_db(&conn)?;
-    // Delete cards that reference any sub_collection of this collection first (required for FK if enabled).
-    conn.execute(
-        "DELETE FROM cards WHERE sub_collection_id IN (SELECT id FROM sub_collections WHERE collection_id = ?1)",
-        rusqlite::params![id],
-    )
-    .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM sub_collections WHERE collection_id = ?1", rusqlite::params![id])
-        .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM collections WHERE id = ?1", rusqlite::params![id])
-        .map_err(|e| e.to_string())?;
-    if conn.changes() == 0 {
-        return Err("Collection not found".to_string());
-    }
+fn delete_collection(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction()?;
+    let name: String = tx
+        .query_row(
+            "SELECT name FROM collections WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![id],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::not_found("Collection not found"))?;
+    let mut op = journal::Operation::new(format!("Delete collection \"{name}\""));
+    op.track(&tx, "cards", "collection_id = ?1 AND deleted_at IS NULL", rusqlite::params![id])?;
+    op.track(&tx, "collections", "id = ?1", rusqlite::params![id])?;
+    // Move the collection and its cards to the trash with one timestamp, so they are restored together.
+    let now: String = tx.query_row("SELECT datetime('now')", [], |row| row.get(0))?;
+    tx.execute(
+        "UPDATE cards SET deleted_at = ?1 WHERE collection_id = ?2 AND deleted_at IS NULL",
+        rusqlite::params![now, id],
+    )?;
+    tx.execute("UPDATE collections SET deleted_at = ?1 WHERE id = ?2", rusqlite::params![now, id])?;
+    op.commit(&tx)?;
+    tx.commit()?;
     Ok(())
 }
 
@@ -178,114 +428,140 @@ struct StoredSubCollection {
     id: i64,
     name: String,
     collection_id: i64,
+    parent_id: Option<i64>,
+    /// Names from the top level down, e.g. "Chapter 1/Section 2".
+    path: String,
 }
 
 /// Returns the id of the reserved null-sub-collection for the given collection (used when a card has no real sub-collection).
-fn get_null_sub_collection_id(conn: &rusqlite::Connection, collection_id: i64) -> Result<i64, String> {
+fn get_null_sub_collection_id(conn: &rusqlite::Connection, collection_id: i64) -> Result<i64, AppError> {
     conn.query_row(
         "SELECT id FROM sub_collections WHERE collection_id = ?1 AND name = ?2",
         rusqlite::params![collection_id, NULL_SUB_COLLECTION_NAME],
         |row| row.get(0),
     )
-    .map_err(|e| e.to_string())
+    .map_err(|_| AppError::not_found(format!("Collection {collection_id} has no default sub collection")))
 }
 
 #[tauri::command]
-fn get_sub_collections(app: tauri::AppHandle, collection_id: i64) -> Result<Vec<StoredSubCollection>, String> {
+fn get_sub_collections(app: tauri::AppHandle, collection_id: i64) -> Result<Vec<StoredSubCollection>, AppError> {
     let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&path)?;
     init_db(&conn)?;
-    let mut stmt = conn
-        .prepare("SELECT id, name, collection_id FROM sub_collections WHERE collection_id = ?1 ORDER BY name")
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(rusqlite::params![collection_id], |row| {
-            Ok(StoredSubCollection {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                collection_id: row.get(2)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
     let mut sub_collections = Vec::new();
-    for row in rows {
-        sub_collections.push(row.map_err(|e| e.to_string())?);
+    for sub in hierarchy::sub_collection_paths(&conn, collection_id)? {
+        sub_collections.push(StoredSubCollection {
+            id: sub.id,
+            name: sub.name,
+            collection_id,
+            parent_id: sub.parent_id,
+            path: sub.path,
+        });
     }
     Ok(sub_collections)
 }
 
-#[tauri::command]
-fn create_sub_collection(app: tauri::AppHandle, collection_id: i64, name: String) -> Result<StoredSubCollection, String> {
-    let name = name.trim();
+/// Reject names that are empty, reserved, or would be read back as a path.
+fn check_sub_collection_name(name: &str) -> Result<(), AppError> {
     if name.is_empty() {
-        return Err("Sub collection name cannot be empty".to_string());
+        return Err(AppError::validation("name", "Sub collection name cannot be empty"));
     }
     if name.eq_ignore_ascii_case(NULL_SUB_COLLECTION_NAME) {
-        return Err("That name is reserved for internal use.".to_string());
+        return Err(AppError::Reserved("That name is reserved for internal use.".to_string()));
+    }
+    if name.contains(hierarchy::PATH_SEPARATOR) {
+        return Err(AppError::validation(
+            "name",
+            format!("Sub collection names cannot contain \"{}\"", hierarchy::PATH_SEPARATOR),
+        ));
+    }
+    Ok(())
+}
+
+/// Create a sub collection at the top level, or under `parent_id`.
+#[tauri::command]
+fn create_sub_collection(
+    app: tauri::AppHandle,
+    collection_id: i64,
+    name: String,
+    parent_id: Option<i64>,
+) -> Result<StoredSubCollection, AppError> {
+    let name = name.trim();
+    check_sub_collection_name(name)?;
+    let conn = open_db(&app)?;
+    if let Some(parent_id) = parent_id {
+        hierarchy::check_parent(&conn, collection_id, None, parent_id)?;
     }
-    let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
-    init_db(&conn)?;
     conn.execute(
-        "INSERT INTO sub_collections (name, collection_id) VALUES (?1, ?2)",
-        rusqlite::params![name, collection_id],
-    )
-    .map_err(|e| e.to_string())?;
+        "INSERT INTO sub_collections (name, collection_id, parent_id) VALUES (?1, ?2, ?3)",
+        rusqlite::params![name, collection_id, parent_id],
+    )?;
     let id = conn.last_insert_rowid();
     Ok(StoredSubCollection {
         id,
         name: name.to_string(),
         collection_id,
+        parent_id,
+        path: hierarchy::sub_collection_path(&conn, id)?,
     })
 }
 
 #[tauri::command]
-fn update_sub_collection(app: tauri::AppHandle, id: i64, name: String) -> Result<StoredSubCollection, String> {
+fn update_sub_collection(app: tauri::AppHandle, id: i64, name: String) -> Result<StoredSubCollection, AppError> {
     let name = name.trim();
-    if name.is_empty() {
-        return Err("Sub collection name cannot be empty".to_string());
-    }
-    if name.eq_ignore_ascii_case(NULL_SUB_COLLECTION_NAME) {
-        return Err("That name is reserved for internal use.".to_string());
-    }
+    check_sub_collection_name(name)?;
     let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&path)?;
     init_db(&conn)?;
-    let collection_id: i64 = conn
-        .query_row("SELECT collection_id FROM sub_collections WHERE id = ?1", rusqlite::params![id], |row| row.get(0))
-        .map_err(|e| e.to_string())?;
+    let (collection_id, parent_id): (i64, Option<i64>) = conn
+        .query_row(
+            "SELECT collection_id, parent_id FROM sub_collections WHERE id = ?1",
+            rusqlite::params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
     conn.execute(
         "UPDATE sub_collections SET name = ?1 WHERE id = ?2",
         rusqlite::params![name, id],
-    )
-    .map_err(|e| map_unique_constraint(e))?;
+    )?;
     Ok(StoredSubCollection {
         id,
         name: name.to_string(),
         collection_id,
+        parent_id,
+        path: hierarchy::sub_collection_path(&conn, id)?,
     })
 }
 
 #[tauri::command]
-fn delete_sub_collection(app: tauri::AppHandle, id: i64) -> Result<(), String> {
-    let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
-    init_db(&conn)?;
-    let (collection_id, name): (i64, String) = conn
-        .query_row(
-            "SELECT collection_id, name FROM sub_collections WHERE id = ?1",
-            rusqlite::params![id],
-            |row| Ok((row.get(0)?, row.get(1)?)),
-        )
-        .map_err(|e| e.to_string())?;
+fn delete_sub_collection(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    backup::snapshot(&app, "delete-sub-collection")?;
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction()?;
+    let name: String = tx
+        .query_row("SELECT name FROM sub_collections WHERE id = ?1", rusqlite::params![id], |row| row.get(0))?;
     if name == NULL_SUB_COLLECTION_NAME {
-        return Err("The default sub collection cannot be deleted.".to_string());
+        return Err(AppError::Reserved("The default sub collection cannot be deleted.".to_string()));
+    }
+    // The sub collection goes with everything below it; the deepest rows are deleted first.
+    let subtree: Vec<i64> = tx
+        .prepare(
+            "WITH RECURSIVE below(id, depth) AS (
+                 SELECT ?1, 0 UNION ALL SELECT s.id, below.depth + 1 FROM sub_collections s JOIN below ON s.parent_id = below.id
+             ) SELECT id FROM below ORDER BY depth DESC",
+        )?
+        .query_map(rusqlite::params![id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let mut op = journal::Operation::new(format!("Delete sub collection \"{name}\""));
+    op.track(&tx, "cards", &format!("sub_collection_id IN {}", hierarchy::subtree("?1")), rusqlite::params![id])?;
+    for sub_id in &subtree {
+        op.track(&tx, "sub_collections", "id = ?1", rusqlite::params![sub_id])?;
     }
-    let null_id = get_null_sub_collection_id(&conn, collection_id)?;
-    conn.execute("UPDATE cards SET sub_collection_id = ?1 WHERE sub_collection_id = ?2", rusqlite::params![null_id, id])
-        .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM sub_collections WHERE id = ?1", rusqlite::params![id])
-        .map_err(|e| e.to_string())?;
+    // The sub_collections_reassign_cards trigger moves the cards to the default sub collection.
+    for sub_id in &subtree {
+        tx.execute("DELETE FROM sub_collections WHERE id = ?1", rusqlite::params![sub_id])?;
+    }
+    op.commit(&tx)?;
+    tx.commit()?;
     Ok(())
 }
 
@@ -295,24 +571,59 @@ struct StoredCard {
     question: String,
     answer: String,
     hint: String,
-    skipped: bool,
+    /// When the card was suspended; it stays out of study until unsuspended.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suspended_at: Option<String>,
+    /// When the card was buried; it stays out of study until the next study day.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    buried_at: Option<String>,
+    /// When the card became a leech (kept being forgotten).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    leech_at: Option<String>,
+    card_type: card_types::CardType,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    choices: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     sub_collection_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_page: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generator: Option<String>,
+}
+
+/// A card found by its source, with the collection it lives in.
+#[derive(serde::Serialize)]
+struct SourcedCard {
+    collection_id: i64,
+    #[serde(flatten)]
+    card: StoredCard,
 }
 
-/// Card data for export/import (no id, no skipped).
+/// Card data for export/import (no id, no study state).
 #[derive(serde::Serialize, serde::Deserialize)]
 struct ExportCard {
     question: String,
     answer: String,
     #[serde(alias = "title")]
     hint: String,
-    /// Sub-collection name for this card; used on import to match/create sub-collections.
+    /// Sub-collection path for this card, e.g. "Chapter 1/Section 2"; used on import to match/create sub-collections.
     #[serde(default)]
     sub_collection_name: Option<String>,
+    #[serde(default)]
+    card_type: card_types::CardType,
+    /// Wrong answers of a multiple choice card.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    choices: Vec<String>,
+    /// When the card became a leech.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    leech_at: Option<String>,
 }
 
-/// Sub-collection export (name only; id is recreated on import).
+/// Sub-collection export (path only, e.g. "Chapter 1/Section 2"; ids are recreated on import).
 #[derive(serde::Serialize, serde::Deserialize)]
 struct ExportSubCollection {
     name: String,
@@ -344,29 +655,84 @@ struct FileCollectionSummary {
     sub_collection_count: u32,
 }
 
+const STORED_CARD_COLUMNS: &str =
+    "id, question, answer, COALESCE(hint, ''), suspended_at, sub_collection_id, source_path, source_hash, generator, source_page, card_type, choices, buried_at, leech_at";
+
+fn row_to_stored_card(row: &rusqlite::Row) -> rusqlite::Result<StoredCard> {
+    Ok(StoredCard {
+        id: row.get(0)?,
+        question: row.get(1)?,
+        answer: row.get(2)?,
+        hint: row.get(3)?,
+        suspended_at: row.get(4)?,
+        sub_collection_id: row.get::<_, Option<i64>>(5)?,
+        source_path: row.get(6)?,
+        source_hash: row.get(7)?,
+        generator: row.get(8)?,
+        source_page: row.get(9)?,
+        card_type: card_types::CardType::parse(&row.get::<_, String>(10)?),
+        choices: card_types::parse_choices(row.get::<_, Option<String>>(11)?.as_deref()),
+        buried_at: row.get(12)?,
+        leech_at: row.get(13)?,
+    })
+}
+
+/// Live cards of a collection; with `sub_collection_id`, only those in that sub collection or below it.
+#[tauri::command]
+fn get_cards(app: tauri::AppHandle, collection_id: i64, sub_collection_id: Option<i64>) -> Result<Vec<StoredCard>, AppError> {
+    let path = db_path(&app)?;
+    let conn = rusqlite::Connection::open(&path)?;
+    init_db(&conn)?;
+    suspend::unbury_expired(&conn)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {STORED_CARD_COLUMNS} FROM cards WHERE collection_id = ?1 AND deleted_at IS NULL
+               AND (?2 IS NULL OR sub_collection_id IN {})
+             ORDER BY id",
+            hierarchy::subtree("?2")
+        ))?;
+    let rows = stmt.query_map(rusqlite::params![collection_id, sub_collection_id], row_to_stored_card)?;
+    let mut cards = Vec::new();
+    for row in rows {
+        cards.push(row?);
+    }
+    Ok(cards)
+}
+
+/// Find cards by where they came from: source file path, content hash and/or generator.
+/// `generator` matches as a prefix, so "ollama/cloud:deepseek" finds every prompt version of that model.
 #[tauri::command]
-fn get_cards(app: tauri::AppHandle, collection_id: i64) -> Result<Vec<StoredCard>, String> {
+fn find_cards_by_source(
+    app: tauri::AppHandle,
+    source_path: Option<String>,
+    source_hash: Option<String>,
+    generator: Option<String>,
+) -> Result<Vec<SourcedCard>, AppError> {
+    if source_path.is_none() && source_hash.is_none() && generator.is_none() {
+        return Err(AppError::validation("sourcePath", "Specify a source path, source hash or generator"));
+    }
     let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&path)?;
     init_db(&conn)?;
     let mut stmt = conn
-        .prepare("SELECT id, question, answer, COALESCE(hint, ''), COALESCE(skipped, 0), sub_collection_id FROM cards WHERE collection_id = ?1 ORDER BY id")
-        .map_err(|e| e.to_string())?;
+        .prepare(&format!(
+            "SELECT {STORED_CARD_COLUMNS}, collection_id FROM cards
+             WHERE deleted_at IS NULL
+               AND (?1 IS NULL OR source_path = ?1)
+               AND (?2 IS NULL OR source_hash = ?2)
+               AND (?3 IS NULL OR substr(generator, 1, length(?3)) = ?3)
+             ORDER BY collection_id, id"
+        ))?;
     let rows = stmt
-        .query_map(rusqlite::params![collection_id], |row| {
-            Ok(StoredCard {
-                id: row.get(0)?,
-                question: row.get(1)?,
-                answer: row.get(2)?,
-                hint: row.get(3)?,
-                skipped: row.get::<_, i64>(4)? != 0,
-                sub_collection_id: row.get::<_, Option<i64>>(5)?,
+        .query_map(rusqlite::params![source_path, source_hash, generator], |row| {
+            Ok(SourcedCard {
+                collection_id: row.get(14)?,
+                card: row_to_stored_card(row)?,
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
     let mut cards = Vec::new();
     for row in rows {
-        cards.push(row.map_err(|e| e.to_string())?);
+        cards.push(row?);
     }
     Ok(cards)
 }
@@ -380,79 +746,79 @@ fn update_card(
     collection_id: i64,
     hint: Option<String>,
     sub_collection_id: Option<i64>,
-) -> Result<(), String> {
-    let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
-    init_db(&conn)?;
+) -> Result<(), AppError> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction()?;
     let hint = hint.unwrap_or_default();
     let sub_id = match sub_collection_id {
-        Some(sid) => sid,
-        None => get_null_sub_collection_id(&conn, collection_id)?,
+        Some(sid) => {
+            check_sub_collection(&tx, collection_id, sid)?;
+            sid
+        }
+        None => get_null_sub_collection_id(&tx, collection_id)?,
     };
-    conn.execute(
+    tx.execute(
         "UPDATE cards SET question = ?1, answer = ?2, collection_id = ?3, hint = ?4, sub_collection_id = ?5 WHERE id = ?6",
         rusqlite::params![question, answer, collection_id, hint, sub_id, id],
-    )
-    .map_err(|e| map_unique_constraint(e))?;
+    )?;
+    card_types::sync_card_prompts(&tx, None, id)?;
+    tx.commit()?;
     Ok(())
 }
 
-#[tauri::command]
-fn delete_card(app: tauri::AppHandle, id: i64) -> Result<(), String> {
-    let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
-    init_db(&conn)?;
-    conn.execute("DELETE FROM cards WHERE id = ?1", rusqlite::params![id])
-        .map_err(|e| e.to_string())?;
-    Ok(())
+/// Shorten card text for undo labels.
+fn truncate_label(text: &str) -> String {
+    const MAX_CHARS: usize = 40;
+    let text = text.trim();
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
 }
 
 #[tauri::command]
-fn set_card_skipped(app: tauri::AppHandle, card_id: i64, skipped: bool) -> Result<(), String> {
-    let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
-    init_db(&conn)?;
-    conn.execute(
-        "UPDATE cards SET skipped = ?1 WHERE id = ?2",
-        rusqlite::params![if skipped { 1i64 } else { 0i64 }, card_id],
-    )
-    .map_err(|e| e.to_string())?;
+fn delete_card(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction()?;
+    let question: String = tx
+        .query_row(
+            "SELECT question FROM cards WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![id],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::not_found("Card not found"))?;
+    let mut op = journal::Operation::new(format!("Delete card \"{}\"", truncate_label(&question)));
+    op.track(&tx, "cards", "id = ?1", rusqlite::params![id])?;
+    tx.execute("UPDATE cards SET deleted_at = datetime('now') WHERE id = ?1", rusqlite::params![id])?;
+    op.commit(&tx)?;
+    tx.commit()?;
     Ok(())
 }
 
-#[tauri::command]
-fn clear_skipped_for_collection(app: tauri::AppHandle, collection_id: i64) -> Result<(), String> {
-    let path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
-    init_db(&conn)?;
-    conn.execute(
-        "UPDATE cards SET skipped = 0 WHERE collection_id = ?1",
-        rusqlite::params![collection_id],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(())
+/// Sub collections to export, as (id, path), without the default one.
+fn export_sub_collection_paths(conn: &rusqlite::Connection, collection_id: i64) -> Result<Vec<(i64, String)>, AppError> {
+    Ok(hierarchy::sub_collection_paths(conn, collection_id)?
+        .into_iter()
+        .filter(|sub| sub.path != NULL_SUB_COLLECTION_NAME)
+        .map(|sub| (sub.id, sub.path))
+        .collect())
 }
 
 #[tauri::command]
-fn export_collection_to_path(app: tauri::AppHandle, collection_id: i64, path: String) -> Result<(), String> {
+fn export_collection_to_path(app: tauri::AppHandle, collection_id: i64, path: String) -> Result<(), AppError> {
     let db_path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&db_path)?;
     init_db(&conn)?;
 
     let name: String = conn
-        .query_row("SELECT name FROM collections WHERE id = ?1", rusqlite::params![collection_id], |row| row.get(0))
-        .map_err(|_| "Collection not found".to_string())?;
-
-    let mut sub_list: Vec<(i64, String)> = Vec::new();
-    let mut sub_stmt = conn
-        .prepare("SELECT id, name FROM sub_collections WHERE collection_id = ?1 AND name != ?2 ORDER BY name")
-        .map_err(|e| e.to_string())?;
-    let sub_rows = sub_stmt
-        .query_map(rusqlite::params![collection_id, NULL_SUB_COLLECTION_NAME], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
-        .map_err(|e| e.to_string())?;
-    for row in sub_rows {
-        sub_list.push(row.map_err(|e| e.to_string())?);
-    }
+        .query_row(
+            "SELECT name FROM collections WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![collection_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::not_found("Collection not found"))?;
+
+    let sub_list = export_sub_collection_paths(&conn, collection_id)?;
     let sub_collection_id_to_name: std::collections::HashMap<i64, String> = sub_list.iter().cloned().collect();
     let sub_collections: Vec<ExportSubCollection> = sub_list
         .into_iter()
@@ -461,8 +827,7 @@ fn export_collection_to_path(app: tauri::AppHandle, collection_id: i64, path: St
 
     let mut cards: Vec<ExportCard> = Vec::new();
     let mut card_stmt = conn
-        .prepare("SELECT question, answer, COALESCE(hint, ''), sub_collection_id FROM cards WHERE collection_id = ?1 ORDER BY id")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT question, answer, COALESCE(hint, ''), sub_collection_id, card_type, choices, leech_at FROM cards WHERE collection_id = ?1 AND deleted_at IS NULL ORDER BY id")?;
     let card_rows = card_stmt
         .query_map(rusqlite::params![collection_id], |row| {
             let sub_id: Option<i64> = row.get(3)?;
@@ -472,11 +837,13 @@ fn export_collection_to_path(app: tauri::AppHandle, collection_id: i64, path: St
                 answer: row.get(1)?,
                 hint: row.get(2)?,
                 sub_collection_name,
+                card_type: card_types::CardType::parse(&row.get::<_, String>(4)?),
+                choices: card_types::parse_choices(row.get::<_, Option<String>>(5)?.as_deref()),
+                leech_at: row.get(6)?,
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
     for card in card_rows {
-        cards.push(card.map_err(|e| e.to_string())?);
+        cards.push(card?);
     }
 
     let collections = vec![ExportCollection {
@@ -485,37 +852,25 @@ fn export_collection_to_path(app: tauri::AppHandle, collection_id: i64, path: St
         cards,
     }];
     let data = ExportData { collections };
-    let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&data)?;
+    std::fs::write(&path, json)?;
     Ok(())
 }
 
 #[tauri::command]
-fn export_collections_to_path(app: tauri::AppHandle, path: String) -> Result<(), String> {
+fn export_collections_to_path(app: tauri::AppHandle, path: String) -> Result<(), AppError> {
     let db_path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&db_path)?;
     init_db(&conn)?;
 
     let mut collections: Vec<ExportCollection> = Vec::new();
     let mut coll_stmt = conn
-        .prepare("SELECT id, name FROM collections ORDER BY name")
-        .map_err(|e| e.to_string())?;
-    let coll_rows = coll_stmt
-        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT id, name FROM collections WHERE deleted_at IS NULL ORDER BY name")?;
+    let coll_rows = coll_stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
 
     for row in coll_rows {
-        let (coll_id, name) = row.map_err(|e| e.to_string())?;
-        let mut sub_list: Vec<(i64, String)> = Vec::new();
-        let mut sub_stmt = conn
-            .prepare("SELECT id, name FROM sub_collections WHERE collection_id = ?1 AND name != ?2 ORDER BY name")
-            .map_err(|e| e.to_string())?;
-        for sub_row in sub_stmt
-            .query_map(rusqlite::params![coll_id, NULL_SUB_COLLECTION_NAME], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
-            .map_err(|e| e.to_string())?
-        {
-            sub_list.push(sub_row.map_err(|e| e.to_string())?);
-        }
+        let (coll_id, name) = row?;
+        let sub_list = export_sub_collection_paths(&conn, coll_id)?;
         let sub_collection_id_to_name: std::collections::HashMap<i64, String> = sub_list.iter().cloned().collect();
         let sub_collections: Vec<ExportSubCollection> = sub_list
             .into_iter()
@@ -524,8 +879,7 @@ fn export_collections_to_path(app: tauri::AppHandle, path: String) -> Result<(),
 
         let mut cards: Vec<ExportCard> = Vec::new();
         let mut card_stmt = conn
-            .prepare("SELECT question, answer, COALESCE(hint, ''), sub_collection_id FROM cards WHERE collection_id = ?1 ORDER BY id")
-            .map_err(|e| e.to_string())?;
+            .prepare("SELECT question, answer, COALESCE(hint, ''), sub_collection_id, card_type, choices, leech_at FROM cards WHERE collection_id = ?1 AND deleted_at IS NULL ORDER BY id")?;
         let card_rows = card_stmt
             .query_map(rusqlite::params![coll_id], |row| {
                 let sub_id: Option<i64> = row.get(3)?;
@@ -535,11 +889,13 @@ fn export_collections_to_path(app: tauri::AppHandle, path: String) -> Result<(),
                     answer: row.get(1)?,
                     hint: row.get(2)?,
                     sub_collection_name,
+                    card_type: card_types::CardType::parse(&row.get::<_, String>(4)?),
+                    choices: card_types::parse_choices(row.get::<_, Option<String>>(5)?.as_deref()),
+                    leech_at: row.get(6)?,
                 })
-            })
-            .map_err(|e| e.to_string())?;
+            })?;
         for card in card_rows {
-            cards.push(card.map_err(|e| e.to_string())?);
+            cards.push(card?);
         }
         collections.push(ExportCollection {
             name,
@@ -549,16 +905,16 @@ fn export_collections_to_path(app: tauri::AppHandle, path: String) -> Result<(),
     }
 
     let data = ExportData { collections };
-    let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&data)?;
+    std::fs::write(&path, json)?;
     Ok(())
 }
 
 /// Read an export file and return list of collections (name + card count) for the import modal.
 #[tauri::command]
-fn read_export_file(path: String) -> Result<Vec<FileCollectionSummary>, String> {
-    let json = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let data: ExportData = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+fn read_export_file(path: String) -> Result<Vec<FileCollectionSummary>, AppError> {
+    let json = std::fs::read_to_string(&path)?;
+    let data: ExportData = serde_json::from_str(&json)?;
     let list: Vec<FileCollectionSummary> = data
         .collections
         .into_iter()
@@ -571,39 +927,13 @@ fn read_export_file(path: String) -> Result<Vec<FileCollectionSummary>, String>
     Ok(list)
 }
 
-/// Map UNIQUE constraint violations to a user-friendly message; pass through other errors.
-fn map_unique_constraint(e: rusqlite::Error) -> String {
-    match &e {
-        rusqlite::Error::SqliteFailure(_, msg) if msg.as_deref().map(|s| s.contains("UNIQUE") || s.contains("unique")).unwrap_or(false) => {
-            "A card with this question and answer already exists in this sub-collection.".to_string()
-        }
-        _ => e.to_string(),
-    }
-}
-
-/// Get or create a sub-collection by name; returns its id.
+/// Get or create a sub-collection by path, e.g. "Chapter 1/Section 2", creating missing levels; returns its id.
 fn get_or_create_sub_collection(
     conn: &rusqlite::Connection,
     collection_id: i64,
-    name: &str,
-) -> Result<i64, String> {
-    let name = name.trim();
-    if name.is_empty() {
-        return Err("Sub collection name cannot be empty".to_string());
-    }
-    if let Ok(id) = conn.query_row(
-        "SELECT id FROM sub_collections WHERE collection_id = ?1 AND name = ?2",
-        rusqlite::params![collection_id, name],
-        |row| row.get(0),
-    ) {
-        return Ok(id);
-    }
-    conn.execute(
-        "INSERT INTO sub_collections (name, collection_id) VALUES (?1, ?2)",
-        rusqlite::params![name, collection_id],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(conn.last_insert_rowid())
+    path: &str,
+) -> Result<i64, AppError> {
+    hierarchy::get_or_create_path(conn, collection_id, path, |_| {})
 }
 
 /// Import one collection from an export file into an existing collection or a new one.
@@ -614,32 +944,32 @@ fn import_collection_from_file(
     file_collection_index: u32,
     destination_collection_id: Option<i64>,
     destination_new_name: Option<String>,
-) -> Result<ImportResult, String> {
-    let json = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let data: ExportData = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+) -> Result<ImportResult, AppError> {
+    let json = std::fs::read_to_string(&path)?;
+    let data: ExportData = serde_json::from_str(&json)?;
     let exp_coll = data
         .collections
         .get(file_collection_index as usize)
-        .ok_or_else(|| "Invalid collection index".to_string())?;
+        .ok_or_else(|| AppError::validation("fileCollectionIndex", "Invalid collection index"))?;
+    backup::snapshot(&app, "import")?;
 
     let db_path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&db_path)?;
     init_db(&conn)?;
 
         let collection_id: i64 = match (destination_collection_id, destination_new_name.as_deref().map(str::trim)) {
         (Some(id), _) => id,
         (None, Some(name)) if !name.is_empty() => {
-            conn.execute("INSERT INTO collections (name) VALUES (?1)", rusqlite::params![name])
-                .map_err(|e| e.to_string())?;
+            trash::check_name_not_trashed(&conn, name)?;
+            conn.execute("INSERT INTO collections (name) VALUES (?1)", rusqlite::params![name])?;
             let id = conn.last_insert_rowid();
             conn.execute(
                 "INSERT INTO sub_collections (name, collection_id) VALUES (?1, ?2)",
                 rusqlite::params![NULL_SUB_COLLECTION_NAME, id],
-            )
-            .map_err(|e| e.to_string())?;
+            )?;
             id
         }
-        _ => return Err("Specify an existing collection or a new collection name".to_string()),
+        _ => return Err(AppError::validation("destinationCollectionId", "Specify an existing collection or a new collection name")),
     };
 
     let null_sub_id = get_null_sub_collection_id(&conn, collection_id)?;
@@ -669,10 +999,16 @@ fn import_collection_from_file(
             .unwrap_or(null_sub_id);
         let n = conn
             .execute(
-                "INSERT OR IGNORE INTO cards (question, answer, collection_id, hint, sub_collection_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-                rusqlite::params![question, answer, collection_id, card.hint.trim(), sub_collection_id],
-            )
-            .map_err(|e| e.to_string())?;
+                "INSERT OR IGNORE INTO cards (question, answer, collection_id, hint, sub_collection_id, card_type, choices, leech_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                rusqlite::params![
+                    question, answer, collection_id, card.hint.trim(), sub_collection_id,
+                    card.card_type.as_str(), card_types::choices_json(&card.choices)?, card.leech_at
+                ],
+            )?;
+        if n > 0 {
+            card_types::sync_card_prompts(&conn, None, conn.last_insert_rowid())?;
+        }
         cards_added += n as u32;
     }
 
@@ -683,12 +1019,13 @@ fn import_collection_from_file(
 }
 
 #[tauri::command]
-fn import_collections_from_path(app: tauri::AppHandle, path: String) -> Result<ImportResult, String> {
-    let json = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let data: ExportData = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+fn import_collections_from_path(app: tauri::AppHandle, path: String) -> Result<ImportResult, AppError> {
+    let json = std::fs::read_to_string(&path)?;
+    let data: ExportData = serde_json::from_str(&json)?;
+    backup::snapshot(&app, "import")?;
 
     let db_path = db_path(&app)?;
-    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let conn = rusqlite::Connection::open(&db_path)?;
     init_db(&conn)?;
 
     let mut collections_count: u32 = 0;
@@ -700,20 +1037,19 @@ fn import_collections_from_path(app: tauri::AppHandle, path: String) -> Result<I
             continue;
         }
         let collection_id: i64 = match conn.query_row(
-            "SELECT id FROM collections WHERE name = ?1",
+            "SELECT id FROM collections WHERE name = ?1 AND deleted_at IS NULL",
             rusqlite::params![name],
             |row| row.get(0),
         ) {
             Ok(id) => id,
             Err(_) => {
-                conn.execute("INSERT INTO collections (name) VALUES (?1)", rusqlite::params![name])
-                    .map_err(|e| e.to_string())?;
+                trash::check_name_not_trashed(&conn, name)?;
+                conn.execute("INSERT INTO collections (name) VALUES (?1)", rusqlite::params![name])?;
                 let id = conn.last_insert_rowid();
                 conn.execute(
                     "INSERT INTO sub_collections (name, collection_id) VALUES (?1, ?2)",
                     rusqlite::params![NULL_SUB_COLLECTION_NAME, id],
-                )
-                .map_err(|e| e.to_string())?;
+                )?;
                 id
             }
         };
@@ -745,10 +1081,16 @@ fn import_collections_from_path(app: tauri::AppHandle, path: String) -> Result<I
                 .unwrap_or(null_sub_id);
             let n = conn
                 .execute(
-                    "INSERT OR IGNORE INTO cards (question, answer, collection_id, hint, sub_collection_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-                    rusqlite::params![question, answer, collection_id, card.hint.trim(), sub_collection_id],
-                )
-                .map_err(|e| e.to_string())?;
+                    "INSERT OR IGNORE INTO cards (question, answer, collection_id, hint, sub_collection_id, card_type, choices, leech_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    rusqlite::params![
+                        question, answer, collection_id, card.hint.trim(), sub_collection_id,
+                        card.card_type.as_str(), card_types::choices_json(&card.choices)?, card.leech_at
+                    ],
+                )?;
+            if n > 0 {
+                card_types::sync_card_prompts(&conn, None, conn.last_insert_rowid())?;
+            }
             cards_added += n as u32;
         }
     }
@@ -768,71 +1110,34 @@ fn extensions_for_format(format: &str) -> Vec<String> {
 }
 
 #[tauri::command]
-async fn pick_directory(app: tauri::AppHandle) -> Result<Option<String>, String> {
+async fn pick_directory(app: tauri::AppHandle) -> Result<Option<String>, AppError> {
     let (tx, rx) = std::sync::mpsc::channel();
     app.dialog().file().pick_folder(move |folder| {
         let _ = tx.send(folder.map(|p| p.to_string()));
     });
     rx.recv()
-        .map_err(|e| format!("Dialog channel error: {e}"))
+        .map_err(|e| AppError::io(format!("Dialog channel error: {e}")))
 }
 
-/// Count files in a directory whose extension matches the given format (e.g. "png", "jpeg").
+/// Count files in a directory matching the scan options (formats, recursion, include/exclude globs).
 #[tauri::command]
-fn count_files_in_directory(directory: String, format: String) -> Result<u32, String> {
-    let extensions = extensions_for_format(&format);
-    let dir = std::path::Path::new(&directory);
-    if !dir.is_dir() {
-        return Err("Path is not a directory".to_string());
-    }
-    let entries = std::fs::read_dir(dir).map_err(|e| e.to_string())?;
-    let mut count = 0u32;
-    for entry in entries {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let path = entry.path();
-        if path.is_file() {
-            let ext = path
-                .extension()
-                .and_then(|e| e.to_str())
-                .map(|s| s.to_lowercase());
-            if ext.map(|e| extensions.contains(&e)).unwrap_or(false) {
-                count += 1;
-            }
-        }
-    }
-    Ok(count)
+fn count_files_in_directory(directory: String, options: Option<scan::ScanOptions>) -> Result<u32, AppError> {
+    let files = scan::scan_directory(&directory, &options.unwrap_or_default())?;
+    Ok(files.len() as u32)
 }
 
-/// List full paths of files in a directory whose extension matches the given format. Sorted for stable order.
+/// List files in a directory matching the scan options, with size and modification time.
+/// Sorted by name unless the options ask for modification time.
 #[tauri::command]
-fn list_files_in_directory(directory: String, format: String) -> Result<Vec<String>, String> {
-    let extensions = extensions_for_format(&format);
-    let dir = std::path::Path::new(&directory);
-    if !dir.is_dir() {
-        return Err("Path is not a directory".to_string());
-    }
-    let entries = std::fs::read_dir(dir).map_err(|e| e.to_string())?;
-    let mut paths: Vec<String> = entries
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|path| path.is_file())
-        .filter(|path| {
-            path.extension()
-                .and_then(|e| e.to_str())
-                .map(|s| extensions.contains(&s.to_lowercase()))
-                .unwrap_or(false)
-        })
-        .filter_map(|p| p.into_os_string().into_string().ok())
-        .collect();
-    paths.sort();
-    Ok(paths)
+fn list_files_in_directory(directory: String, options: Option<scan::ScanOptions>) -> Result<Vec<scan::ScannedFile>, AppError> {
+    scan::scan_directory(&directory, &options.unwrap_or_default())
 }
 
 /// Read a file and return its contents as base64. Used so the frontend can pass image data to Tesseract.js.
 #[tauri::command]
-fn read_file_base64(path: String) -> Result<String, String> {
+fn read_file_base64(path: String) -> Result<String, AppError> {
     use base64::Engine;
-    let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+    let bytes = std::fs::read(&path)?;
     Ok(base64::engine::general_purpose::STANDARD.encode(&bytes))
 }
 
@@ -842,11 +1147,6 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[tauri::command]
-fn get_ollama_api_key() -> String {
-    std::env::var("OLLAMA_API_KEY").unwrap_or_default()
-}
-
 #[tauri::command]
 fn get_app_name(app: tauri::AppHandle) -> String {
     app.config()
@@ -861,7 +1161,15 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_opener::init())
+        .manage(watch::DirectoryWatch::default())
         .setup(|app| {
+            // A failed snapshot should not keep the app from opening; later ones may still succeed.
+            if let Err(e) = backup::snapshot(app.handle(), "startup") {
+                eprintln!("Startup backup failed: {e}");
+            }
+            trash::purge_expired(&open_db(app.handle())?)?;
+            suspend::unbury_expired(&open_db(app.handle())?)?;
+            jobs::start(app.handle())?;
             let handle = app.handle().clone();
             std::thread::spawn(move || {
                 std::thread::sleep(std::time::Duration::from_millis(1000));
@@ -871,7 +1179,214 @@ pub fn run() {
             });
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![greet, get_ollama_api_key, get_app_name, add_card, get_cards, get_collections, create_collection, update_collection, delete_collection, get_sub_collections, create_sub_collection, update_sub_collection, delete_sub_collection, update_card, delete_card, set_card_skipped, clear_skipped_for_collection, export_collection_to_path, export_collections_to_path, read_export_file, import_collection_from_file, import_collections_from_path, pick_directory, count_files_in_directory, list_files_in_directory, read_file_base64])
+        .invoke_handler(tauri::generate_handler![greet, get_app_name, credentials::set_api_key, credentials::delete_api_key, credentials::get_credential_status, credentials::test_api_key, llm::generate_flashcard, jobs::enqueue_generation_jobs, jobs::list_generation_jobs, jobs::set_generation_job_text, jobs::run_generation_job, jobs::cancel_generation_jobs, jobs::dismiss_generation_job, jobs::clear_generation_jobs, jobs::get_generation_queue, jobs::update_generation_queue, jobs::pause_generation_queue, jobs::resume_generation_queue, watch::start_directory_watch, watch::stop_directory_watch, pdf::extract_pdf_pages, pdf::read_pdf_page_image, add_card, get_cards, get_collections, create_collection, update_collection, delete_collection, get_sub_collections, create_sub_collection, update_sub_collection, delete_sub_collection, hierarchy::move_sub_collection, hierarchy::get_sub_collection_tree, update_card, find_cards_by_source, delete_card, journal::undo_last, journal::redo, journal::get_undo_state, trash::list_trash, trash::restore_trash, trash::purge_trash, trash::set_trash_retention_days, backup::list_backups, backup::restore_backup, integrity::check_database, integrity::repair_database, transfer::move_cards, transfer::copy_cards, bulk::bulk_update_cards, transfer::merge_collections, transfer::split_collection, duplicates::find_duplicates, duplicates::merge_duplicate_cards, card_types::set_card_type, study::get_study_queue, schedule::record_review, grading::check_answer, study::start_study_session, study::get_study_session, study::list_study_sessions, study::advance_study_session, study::end_study_session, suspend::set_card_suspended, suspend::set_card_buried, suspend::clear_card_state, leech::get_leeches, leech::set_leech_options, leech::clear_leech, options::list_collection_options, options::save_collection_options, options::delete_collection_options, options::set_collection_options, options::get_study_day, export_collection_to_path, export_collections_to_path, read_export_file, import_collection_from_file, import_collections_from_path, notes::import_notes_directory, pick_directory, count_files_in_directory, list_files_in_directory, read_file_base64])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database as the first release created it.
+    fn baseline_db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE collections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            CREATE TABLE sub_collections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                collection_id INTEGER NOT NULL REFERENCES collections(id),
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                UNIQUE(collection_id, name)
+            );
+            CREATE TABLE cards (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                question TEXT NOT NULL,
+                answer TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                collection_id INTEGER NOT NULL REFERENCES collections(id),
+                hint TEXT NOT NULL DEFAULT '',
+                skipped INTEGER NOT NULL DEFAULT 0,
+                sub_collection_id INTEGER NOT NULL REFERENCES sub_collections(id)
+            );
+            CREATE UNIQUE INDEX cards_uniq_collection_sub_question_answer ON cards(collection_id, sub_collection_id, question, answer);
+            INSERT INTO collections (id, name) VALUES (1, 'Bio');
+            INSERT INTO sub_collections (id, name, collection_id) VALUES (1, '- None -', 1);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn paths(conn: &rusqlite::Connection) -> Vec<String> {
+        hierarchy::sub_collection_paths(conn, 1).unwrap().into_iter().map(|s| s.path).collect()
+    }
+
+    #[test]
+    fn baseline_database_migrates_and_stays_stable() {
+        let conn = baseline_db();
+        conn.execute("INSERT INTO cards (question, answer, collection_id, sub_collection_id) VALUES ('Q', 'A', 1, 1)", [])
+            .unwrap();
+        init_db(&conn).unwrap();
+        let schema = |conn: &rusqlite::Connection| -> Vec<String> {
+            conn.prepare("SELECT sql FROM sqlite_master WHERE sql IS NOT NULL ORDER BY name")
+                .unwrap()
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        let migrated = schema(&conn);
+        init_db(&conn).unwrap();
+        assert_eq!(schema(&conn), migrated);
+        let problems: i64 = conn.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |r| r.get(0)).unwrap();
+        assert_eq!(problems, 0);
+    }
+
+    fn count(conn: &rusqlite::Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn deletes_cascade_after_migrating() {
+        let conn = baseline_db();
+        conn.execute_batch(
+            "INSERT INTO sub_collections (id, name, collection_id) VALUES (2, 'Cells', 1);
+             INSERT INTO cards (question, answer, collection_id, sub_collection_id) VALUES ('Q', 'A', 1, 1), ('R', 'B', 1, 2);",
+        )
+        .unwrap();
+        init_db(&conn).unwrap();
+        for (child, parent) in CASCADING_FOREIGN_KEYS {
+            let action: String = conn
+                .query_row(
+                    "SELECT on_delete FROM pragma_foreign_key_list(?1) WHERE \"table\" = ?2",
+                    rusqlite::params![child, parent],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(action, "CASCADE", "{child} -> {parent}");
+        }
+        // The rebuilt table keeps its indexes.
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM pragma_index_list('cards') WHERE name = 'cards_source_hash'"), 1);
+
+        // Deleting a sub-collection files its cards under "- None -"; deleting the collection takes everything.
+        conn.execute("DELETE FROM sub_collections WHERE id = 2", []).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM cards WHERE sub_collection_id = 1"), 2);
+        conn.execute("DELETE FROM collections WHERE id = 1", []).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM cards") + count(&conn, "SELECT COUNT(*) FROM sub_collections"), 0);
+    }
+
+    #[test]
+    fn cards_must_use_a_sub_collection_of_their_collection() {
+        let conn = baseline_db();
+        init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (2, 'Chem');
+             INSERT INTO sub_collections (id, name, collection_id) VALUES (2, '- None -', 2);",
+        )
+        .unwrap();
+        assert!(check_sub_collection(&conn, 1, 1).is_ok());
+        assert!(check_sub_collection(&conn, 1, 2).is_err());
+        assert!(conn
+            .execute("INSERT INTO cards (question, answer, collection_id, sub_collection_id) VALUES ('Q', 'A', 1, 99)", [])
+            .is_err());
+    }
+
+    #[test]
+    fn skipped_cards_become_suspended_and_keep_their_undo_history() {
+        let conn = baseline_db();
+        conn.execute_batch(
+            "INSERT INTO cards (id, question, answer, collection_id, sub_collection_id, skipped) VALUES
+                 (1, 'Q', 'A', 1, 1, 1), (2, 'R', 'B', 1, 1, 0);",
+        )
+        .unwrap();
+        journal::init_journal_tables(&conn).unwrap();
+        let mut op = journal::Operation::new("Edit");
+        op.track(&conn, "cards", "id = 2", []).unwrap();
+        conn.execute("UPDATE cards SET hint = 'h' WHERE id = 2", []).unwrap();
+        op.commit(&conn).unwrap();
+        init_db(&conn).unwrap();
+        let suspended: Vec<bool> = conn
+            .prepare("SELECT suspended_at IS NOT NULL FROM cards ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(suspended, vec![true, false]);
+        let entries: i64 = conn.query_row("SELECT COUNT(*) FROM undo_journal", [], |r| r.get(0)).unwrap();
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn source_files_hash_as_hex_sha256() {
+        let path = std::env::temp_dir().join(format!("source-hash-{}", std::process::id()));
+        std::fs::write(&path, b"abc").unwrap();
+        let hash = file_sha256(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(hash, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert!(file_sha256("/no/such/file").is_err());
+
+        let source = |path: &str, hash: Option<&str>| CardSource {
+            path: Some(path.to_string()),
+            hash: hash.map(str::to_string),
+            ..CardSource::default()
+        };
+        assert_eq!(source("/no/such/file", Some("given")).hash().unwrap().as_deref(), Some("given"));
+        assert_eq!(source("/no/such/file", None).hash().unwrap_err().code(), "not_found");
+        assert_eq!(CardSource::default().hash().unwrap(), None);
+    }
+
+    #[test]
+    fn path_names_from_notes_become_nested() {
+        let conn = baseline_db();
+        conn.execute_batch(
+            "INSERT INTO sub_collections (id, name, collection_id) VALUES
+                 (2, 'Biology/Cells', 1), (3, 'Biology', 1), (4, 'Biology / Cells', 1), (5, 'Biology/Cells/Mitosis', 1);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES
+                 (1, 'Q', 'A', 1, 2), (2, 'Q', 'A', 1, 4), (3, 'R', 'B', 1, 4), (4, 'S', 'C', 1, 5);",
+        )
+        .unwrap();
+        init_db(&conn).unwrap();
+        assert_eq!(paths(&conn), vec!["- None -", "Biology", "Biology/Cells", "Biology/Cells/Mitosis"]);
+        let cells = get_or_create_sub_collection(&conn, 1, "Biology/Cells").unwrap();
+        assert_eq!(cells, 2);
+        let live: Vec<i64> = conn
+            .prepare("SELECT id FROM cards WHERE sub_collection_id = 2 AND deleted_at IS NULL ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(live, vec![1, 3]);
+        let trashed: bool = conn.query_row("SELECT deleted_at IS NOT NULL FROM cards WHERE id = 2", [], |r| r.get(0)).unwrap();
+        assert!(trashed);
+    }
+
+    #[test]
+    fn failed_rebuilds_leave_the_table_as_it_was() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "PRAGMA foreign_keys = ON;
+             CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE INDEX items_name ON items(name);
+             INSERT INTO items (id, name) VALUES (1, 'a'), (2, NULL);",
+        )
+        .unwrap();
+        let stricter = "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)";
+        assert!(rebuild_table(&conn, "items", stricter).is_err());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM items"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('items_new', 'items_name')"), 1);
+        assert_eq!(count(&conn, "PRAGMA foreign_keys"), 1);
+
+        conn.execute("UPDATE items SET name = 'b' WHERE id = 2", []).unwrap();
+        rebuild_table(&conn, "items", stricter).unwrap();
+        assert!(conn.execute("INSERT INTO items (name) VALUES (NULL)", []).is_err());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sqlite_master WHERE name = 'items_name'"), 1);
+        assert_eq!(count(&conn, "PRAGMA foreign_keys"), 1);
+    }
+}
@@ -0,0 +1,165 @@
+//! Ollama chat requests made from the backend, so API keys stay out of the webview.
+
+use crate::error::AppError;
+use tauri_plugin_http::reqwest;
+
+const OLLAMA_LOCAL_HOST: &str = "http://localhost:11434";
+const OLLAMA_CLOUD_HOST: &str = "https://ollama.com";
+const DEFAULT_MODEL: &str = "glm-4.7-flash";
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OllamaHost {
+    Local,
+    Cloud,
+}
+
+impl OllamaHost {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OllamaHost::Local => "local",
+            OllamaHost::Cloud => "cloud",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "local" => Some(OllamaHost::Local),
+            "cloud" => Some(OllamaHost::Cloud),
+            _ => None,
+        }
+    }
+
+    fn base_url(self) -> &'static str {
+        match self {
+            OllamaHost::Local => OLLAMA_LOCAL_HOST,
+            OllamaHost::Cloud => OLLAMA_CLOUD_HOST,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ChatMessage {
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ChatResponse {
+    message: ChatMessage,
+}
+
+/// Turn a non-success response into an error message that includes the body Ollama sent back.
+async fn error_for_status(response: reqwest::Response) -> Result<reqwest::Response, AppError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(AppError::io("Ollama rejected the request: unauthorized (check the API key)"));
+    }
+    Err(AppError::io(format!("Ollama returned {status}: {}", body.trim())))
+}
+
+/// Send a single-message chat request to Ollama and return the reply text.
+/// For the cloud host the stored API key is attached here rather than by the caller.
+pub async fn chat(app: &tauri::AppHandle, host: OllamaHost, model: &str, content: &str) -> Result<String, AppError> {
+    let model = match model.trim() {
+        "" => DEFAULT_MODEL,
+        m => m,
+    };
+    let body = serde_json::json!({
+        "model": model,
+        "messages": [{ "role": "user", "content": content }],
+        "stream": false,
+    });
+    let mut request = reqwest::Client::new()
+        .post(format!("{}/api/chat", host.base_url()))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    if let OllamaHost::Cloud = host {
+        let key = crate::credentials::api_key(app, "ollama")?
+            .ok_or_else(|| AppError::not_found("No Ollama API key configured"))?;
+        request = request.bearer_auth(key);
+    }
+    let response = request.send().await.map_err(AppError::io)?;
+    let text = error_for_status(response).await?.text().await.map_err(AppError::io)?;
+    let parsed: ChatResponse = serde_json::from_str(&text)?;
+    Ok(parsed.message.content)
+}
+
+/// Verify an Ollama cloud API key by listing models with it.
+pub async fn check_ollama_cloud_key(key: &str) -> Result<(), AppError> {
+    let response = reqwest::Client::new()
+        .get(format!("{OLLAMA_CLOUD_HOST}/api/tags"))
+        .bearer_auth(key)
+        .send()
+        .await.map_err(AppError::io)?;
+    error_for_status(response).await.map(|_| ())
+}
+
+/// Drop markdown fence and bracket lines models like to wrap JSON in.
+fn strip_code_fences(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !line.starts_with("```") && !line.starts_with('[') && !line.starts_with(']')
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Provenance label for generated cards: host, model and a short hash identifying the prompt version,
+/// e.g. `ollama/local:glm-4.7-flash@prompt-1a2b3c4d`.
+pub fn generator_label(host: OllamaHost, model: &str, prompt_prefix: &str) -> String {
+    use sha2::Digest;
+    let model = match model.trim() {
+        "" => DEFAULT_MODEL,
+        m => m,
+    };
+    let digest = sha2::Sha256::digest(prompt_prefix.as_bytes());
+    let prompt_version: String = digest.iter().take(4).map(|b| format!("{b:02x}")).collect();
+    format!("ollama/{}:{model}@prompt-{prompt_version}", host.as_str())
+}
+
+/// Build the flashcard prompt, run it through Ollama and return the cleaned-up reply.
+pub async fn generate_flashcard_content(
+    app: &tauri::AppHandle,
+    host: OllamaHost,
+    model: &str,
+    prompt_prefix: &str,
+    data: &str,
+) -> Result<String, AppError> {
+    let content = format!("{prompt_prefix}\n  {data}");
+    let reply = chat(app, host, model, &content).await?;
+    Ok(strip_code_fences(&reply))
+}
+
+/// Generate one flashcard (as the model's JSON reply) from OCR or note text.
+#[tauri::command]
+pub async fn generate_flashcard(
+    app: tauri::AppHandle,
+    host: OllamaHost,
+    model: String,
+    prompt_prefix: String,
+    data: String,
+) -> Result<String, AppError> {
+    generate_flashcard_content(&app, host, &model, &prompt_prefix, &data).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generator_label_names_host_model_and_prompt_version() {
+        let label = generator_label(OllamaHost::Cloud, " deepseek ", "Make a card");
+        assert!(label.starts_with("ollama/cloud:deepseek@prompt-"));
+        assert_eq!(label.len(), "ollama/cloud:deepseek@prompt-".len() + 8);
+        assert_eq!(label, generator_label(OllamaHost::Cloud, "deepseek", "Make a card"));
+        assert_ne!(label, generator_label(OllamaHost::Cloud, "deepseek", "Make two cards"));
+        assert!(generator_label(OllamaHost::Local, "", "p").starts_with(&format!("ollama/local:{DEFAULT_MODEL}@")));
+    }
+}
@@ -0,0 +1,521 @@
+//! Markdown / Obsidian notes importer. Cards are written inline in notes:
+//!
+//! - `Question :: Answer` on one line, optionally as a list item; `::` without spaces around it or
+//!   inside inline code is not a card;
+//! - several question lines, a line with only `?`, then answer lines up to the next blank line.
+//!
+//! The first import appends an Obsidian block id (`^fc-…`) to each card's last line. Later runs find
+//! the card by that id and update it in place, so editing a note never duplicates its cards.
+//! Folders under the notes directory become sub-collections (`Biology/Cells`); notes at the top
+//! level go to the collection's "no sub-collection".
+
+use crate::error::AppError;
+use std::collections::{HashMap, HashSet};
+
+const BLOCK_ID_PREFIX: &str = "^fc-";
+
+pub(crate) fn init_notes_tables(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS note_cards (
+            collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+            note_id TEXT NOT NULL,
+            card_id INTEGER NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+            note_path TEXT NOT NULL,
+            PRIMARY KEY (collection_id, note_id)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// A card found in a note.
+struct NoteCard {
+    /// Block id without the `^`, e.g. `fc-1a2b3c4d5e6f`; `None` until the note has been synced once.
+    id: Option<String>,
+    /// 0-based line the existing block id is on.
+    id_line: Option<usize>,
+    question: String,
+    answer: String,
+    /// 0-based line the block id goes on.
+    last_line: usize,
+}
+
+#[derive(Default, serde::Serialize)]
+pub struct NotesImportResult {
+    notes: u32,
+    cards_added: u32,
+    cards_updated: u32,
+    cards_unchanged: u32,
+    /// Cards imported earlier whose block id no longer appears in the notes. They are kept.
+    cards_missing_from_notes: u32,
+    /// Cards that could not be saved, as "note.md:line: reason", and notes block ids could not be
+    /// written to.
+    errors: Vec<String>,
+}
+
+/// Split a trailing ` ^fc-…` block id off a line.
+fn split_block_id(line: &str) -> (&str, Option<&str>) {
+    let trimmed = line.trim_end();
+    if let Some(pos) = trimmed.rfind(BLOCK_ID_PREFIX) {
+        let id = &trimmed[pos + 1..];
+        let at_boundary = pos == 0 || trimmed[..pos].ends_with(char::is_whitespace);
+        if at_boundary && id.len() > 3 && id[3..].chars().all(|c| c.is_ascii_alphanumeric()) {
+            return (trimmed[..pos].trim_end(), Some(id));
+        }
+    }
+    (line, None)
+}
+
+/// Split an inline card at the first ` :: ` outside inline code, so prose like `` `HashMap::new()` ``
+/// or `std::fs` is not a card.
+fn split_inline_card(content: &str) -> Option<(&str, &str)> {
+    let bytes = content.as_bytes();
+    // Length of the backtick run that opened the code span we are in.
+    let mut code_ticks = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'`' {
+            let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+            if code_ticks == 0 {
+                code_ticks = run;
+            } else if run == code_ticks {
+                code_ticks = 0;
+            }
+            i += run;
+            continue;
+        }
+        if code_ticks == 0 && bytes[i..].starts_with(b" :: ") {
+            return Some((&content[..i], &content[i + 4..]));
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Drop a list marker (`- `, `* `, `+ `, `1. `) from the start of a line.
+fn strip_list_marker(line: &str) -> &str {
+    let line = line.trim_start();
+    if let Some(rest) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")).or_else(|| line.strip_prefix("+ ")) {
+        return rest;
+    }
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    match line[digits..].strip_prefix(". ") {
+        Some(rest) if digits > 0 => rest,
+        _ => line,
+    }
+}
+
+fn parse_note(text: &str) -> Vec<NoteCard> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut cards = Vec::new();
+    let mut i = 0;
+    // Skip YAML front matter.
+    if lines.first().map(|l| l.trim()) == Some("---") {
+        if let Some(end) = lines.iter().skip(1).position(|l| l.trim() == "---") {
+            i = end + 2;
+        }
+    }
+    let mut in_code = false;
+    let mut paragraph_start = i;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            paragraph_start = i + 1;
+            i += 1;
+            continue;
+        }
+        if in_code || trimmed.is_empty() || trimmed.starts_with('#') {
+            paragraph_start = i + 1;
+            i += 1;
+            continue;
+        }
+
+        let (content, id) = split_block_id(line);
+        if let Some((question, answer)) = split_inline_card(content) {
+            let (question, answer) = (strip_list_marker(question).trim(), answer.trim());
+            if !question.is_empty() && !answer.is_empty() {
+                cards.push(NoteCard {
+                    id: id.map(str::to_string),
+                    id_line: id.map(|_| i),
+                    question: question.to_string(),
+                    answer: answer.to_string(),
+                    last_line: i,
+                });
+                paragraph_start = i + 1;
+                i += 1;
+                continue;
+            }
+        }
+
+        if content.trim() == "?" && paragraph_start < i {
+            let mut end = i + 1;
+            while end < lines.len() && !lines[end].trim().is_empty() {
+                end += 1;
+            }
+            let mut id = id.map(|id| (id.to_string(), i));
+            let mut block_lines = |range: std::ops::Range<usize>| {
+                range
+                    .map(|index| {
+                        let (content, line_id) = split_block_id(lines[index]);
+                        if id.is_none() {
+                            id = line_id.map(|line_id| (line_id.to_string(), index));
+                        }
+                        content.trim_end()
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            let question = block_lines(paragraph_start..i);
+            let answer = block_lines(i + 1..end);
+            if !answer.trim().is_empty() {
+                cards.push(NoteCard {
+                    id_line: id.as_ref().map(|(_, line)| *line),
+                    id: id.map(|(id, _)| id),
+                    question: question.trim().to_string(),
+                    answer: answer.trim().to_string(),
+                    last_line: end - 1,
+                });
+            }
+            paragraph_start = end;
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+    cards
+}
+
+/// A new block id, unique enough for one vault: derived from the note, line and current time.
+fn new_block_id(note_path: &str, line: usize, taken: &HashSet<String>) -> String {
+    use sha2::Digest;
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let mut salt = 0u32;
+    loop {
+        let digest = sha2::Sha256::digest(format!("{note_path}:{line}:{nanos}:{salt}"));
+        let hex: String = digest.iter().take(6).map(|b| format!("{b:02x}")).collect();
+        let id = format!("fc-{hex}");
+        if !taken.contains(&id) {
+            return id;
+        }
+        salt += 1;
+    }
+}
+
+/// Append block ids to the given lines, keeping the note's line endings.
+fn write_block_ids(path: &std::path::Path, text: &str, ids: &HashMap<usize, String>) -> Result<(), AppError> {
+    let mut out = String::with_capacity(text.len() + ids.len() * 18);
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        match ids.get(&index) {
+            Some(id) => {
+                let body = line.trim_end_matches(['\n', '\r']);
+                out.push_str(body);
+                out.push(' ');
+                out.push('^');
+                out.push_str(id);
+                out.push_str(&line[body.len()..]);
+            }
+            None => out.push_str(line),
+        }
+    }
+    std::fs::write(path, out).map_err(|e| AppError::from(e).context(path.display()))
+}
+
+enum SyncOutcome {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+fn sync_card(
+    conn: &rusqlite::Connection,
+    collection_id: i64,
+    sub_collection_id: i64,
+    note_path: &str,
+    note_id: &str,
+    card: &NoteCard,
+) -> Result<SyncOutcome, AppError> {
+    let existing: Option<(i64, String, String, i64, bool)> = conn
+        .query_row(
+            "SELECT c.id, c.question, c.answer, c.sub_collection_id, c.deleted_at IS NOT NULL FROM note_cards n
+             JOIN cards c ON c.id = n.card_id
+             WHERE n.collection_id = ?1 AND n.note_id = ?2",
+            rusqlite::params![collection_id, note_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .ok();
+    if let Some((card_id, question, answer, sub_id, trashed)) = existing {
+        // A card deleted in the app stays in the trash until it is restored there.
+        if trashed {
+            return Ok(SyncOutcome::Unchanged);
+        }
+        if question == card.question && answer == card.answer && sub_id == sub_collection_id {
+            return Ok(SyncOutcome::Unchanged);
+        }
+        // Same as `update_card`, except the hint is kept since notes have no hint syntax.
+        conn.execute(
+            "UPDATE cards SET question = ?1, answer = ?2, sub_collection_id = ?3 WHERE id = ?4",
+            rusqlite::params![card.question, card.answer, sub_collection_id, card_id],
+        )?;
+        crate::card_types::sync_card_prompts(conn, None, card_id)?;
+        conn.execute(
+            "UPDATE note_cards SET note_path = ?1 WHERE collection_id = ?2 AND note_id = ?3",
+            rusqlite::params![note_path, collection_id, note_id],
+        )?;
+        return Ok(SyncOutcome::Updated);
+    }
+
+    let inserted = conn
+        .execute(
+            "INSERT OR IGNORE INTO cards (question, answer, collection_id, hint, sub_collection_id, source_path)
+             VALUES (?1, ?2, ?3, '', ?4, ?5)",
+            rusqlite::params![card.question, card.answer, collection_id, sub_collection_id, note_path],
+        )?;
+    if inserted > 0 {
+        crate::card_types::sync_card_prompts(conn, None, conn.last_insert_rowid())?;
+    }
+    // An identical card already exists (e.g. made by hand): link the note to it instead.
+    let card_id: i64 = conn
+        .query_row(
+            "SELECT id FROM cards
+             WHERE collection_id = ?1 AND sub_collection_id = ?2 AND question = ?3 AND answer = ?4 AND deleted_at IS NULL",
+            rusqlite::params![collection_id, sub_collection_id, card.question, card.answer],
+            |row| row.get(0),
+        )?;
+    conn.execute(
+        "INSERT OR REPLACE INTO note_cards (collection_id, note_id, card_id, note_path) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![collection_id, note_id, card_id, note_path],
+    )?;
+    Ok(if inserted > 0 { SyncOutcome::Added } else { SyncOutcome::Unchanged })
+}
+
+/// Import (or re-sync) every `.md` note under a directory into a collection. Hidden folders such as
+/// `.obsidian` and `.trash` are skipped.
+#[tauri::command]
+pub fn import_notes_directory(
+    app: tauri::AppHandle,
+    directory: String,
+    destination_collection_id: Option<i64>,
+    destination_new_name: Option<String>,
+) -> Result<NotesImportResult, AppError> {
+    let root = std::path::Path::new(&directory);
+    if !root.is_dir() {
+        return Err(AppError::validation("directory", "Path is not a directory"));
+    }
+    crate::backup::snapshot(&app, "import-notes")?;
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+
+    let collection_id: i64 = match (destination_collection_id, destination_new_name.as_deref().map(str::trim)) {
+        (Some(id), _) => id,
+        (None, Some(name)) if !name.is_empty() => {
+            crate::trash::check_name_not_trashed(&tx, name)?;
+            tx.execute("INSERT INTO collections (name) VALUES (?1)", rusqlite::params![name])?;
+            let id = tx.last_insert_rowid();
+            tx.execute(
+                "INSERT INTO sub_collections (name, collection_id) VALUES (?1, ?2)",
+                rusqlite::params![crate::NULL_SUB_COLLECTION_NAME, id],
+            )?;
+            id
+        }
+        _ => return Err(AppError::validation("destinationCollectionId", "Specify an existing collection or a new collection name")),
+    };
+    let null_sub_id = crate::get_null_sub_collection_id(&tx, collection_id)?;
+
+    let mut taken: HashSet<String> = tx
+        .prepare("SELECT note_id FROM note_cards WHERE collection_id = ?1")?
+        .query_map(rusqlite::params![collection_id], |row| row.get(0))?
+        .collect::<Result<HashSet<String>, _>>()?;
+    let mut seen: HashSet<String> = HashSet::new();
+    // Notes that get new block ids, written only once the cards they point at are committed.
+    let mut rewrites: Vec<(std::path::PathBuf, String, HashMap<usize, String>)> = Vec::new();
+    let mut sub_ids: HashMap<String, i64> = HashMap::new();
+    let mut result = NotesImportResult::default();
+
+    let notes = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_type().is_file()
+                && e.path().extension().and_then(|x| x.to_str()).map(|x| x.eq_ignore_ascii_case("md")) == Some(true)
+        });
+    for entry in notes {
+        let path = entry.path();
+        let text = std::fs::read_to_string(path).map_err(|e| AppError::from(e).context(path.display()))?;
+        let cards = parse_note(&text);
+        if cards.is_empty() {
+            continue;
+        }
+        result.notes += 1;
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let folder = relative
+            .parent()
+            .map(|p| p.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+            .unwrap_or_default();
+        let sub_collection_id = if folder.is_empty() {
+            null_sub_id
+        } else if let Some(id) = sub_ids.get(&folder) {
+            *id
+        } else {
+            let id = crate::get_or_create_sub_collection(&tx, collection_id, &folder)?;
+            sub_ids.insert(folder, id);
+            id
+        };
+        let note_path = path.to_string_lossy().to_string();
+
+        let mut new_ids: HashMap<usize, String> = HashMap::new();
+        let mut stale_id_lines: HashSet<usize> = HashSet::new();
+        for card in &cards {
+            let note_id = match &card.id {
+                // The same id twice (a copied line) would make both lines edit one card.
+                Some(id) if !seen.contains(id) => id.clone(),
+                _ => {
+                    stale_id_lines.extend(card.id_line);
+                    let id = new_block_id(&note_path, card.last_line, &taken);
+                    new_ids.insert(card.last_line, id.clone());
+                    id
+                }
+            };
+            taken.insert(note_id.clone());
+            seen.insert(note_id.clone());
+            match sync_card(&tx, collection_id, sub_collection_id, &note_path, &note_id, card) {
+                Ok(SyncOutcome::Added) => result.cards_added += 1,
+                Ok(SyncOutcome::Updated) => result.cards_updated += 1,
+                Ok(SyncOutcome::Unchanged) => result.cards_unchanged += 1,
+                Err(e) => result.errors.push(format!("{}:{}: {e}", relative.display(), card.last_line + 1)),
+            }
+        }
+        if !new_ids.is_empty() {
+            // A card carrying a duplicated id gets a fresh one; drop the old id from that line first.
+            let text = text
+                .split_inclusive('\n')
+                .enumerate()
+                .map(|(index, line)| match (stale_id_lines.contains(&index), split_block_id(line)) {
+                    (true, (content, Some(_))) => {
+                        let ending = &line[line.trim_end_matches(['\n', '\r']).len()..];
+                        format!("{content}{ending}")
+                    }
+                    _ => line.to_string(),
+                })
+                .collect::<String>();
+            rewrites.push((path.to_path_buf(), text, new_ids));
+        }
+    }
+
+    // Cards from notes in this directory whose ids are gone: the card was deleted from the note.
+    let root_prefix = format!("{}{}", root.to_string_lossy().trim_end_matches(std::path::MAIN_SEPARATOR), std::path::MAIN_SEPARATOR);
+    {
+        let mut stmt = tx.prepare("SELECT note_id, note_path FROM note_cards WHERE collection_id = ?1")?;
+        let rows = stmt
+            .query_map(rusqlite::params![collection_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (note_id, note_path) = row?;
+            if note_path.starts_with(&root_prefix) && !seen.contains(&note_id) {
+                result.cards_missing_from_notes += 1;
+            }
+        }
+    }
+    tx.commit()?;
+    for (path, text, ids) in rewrites {
+        // The cards are saved either way; without its ids the note is matched by content on the next sync.
+        if let Err(e) = write_block_ids(&path, &text, &ids) {
+            result.errors.push(e.to_string());
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_ids_split_off_only_at_a_word_boundary() {
+        assert_eq!(split_block_id("Q :: A ^fc-1a2b"), ("Q :: A", Some("fc-1a2b")));
+        assert_eq!(split_block_id("Q :: A^fc-1a2b"), ("Q :: A^fc-1a2b", None));
+        assert_eq!(split_block_id("Q :: A ^fc-"), ("Q :: A ^fc-", None));
+        assert_eq!(split_block_id("Q :: A"), ("Q :: A", None));
+    }
+
+    #[test]
+    fn notes_yield_inline_and_multiline_cards() {
+        let text = "---\ntitle: x :: y\n---\n# Cells :: heading\nWhat is ATP? :: Energy ^fc-aa11\n\n```\nnot :: a card\n```\n\
+                    Name the\norganelle\n?\nMitochondrion\nthe powerhouse ^fc-bb22\n";
+        let cards = parse_note(text);
+        assert_eq!(cards.len(), 2);
+        assert_eq!((cards[0].question.as_str(), cards[0].answer.as_str()), ("What is ATP?", "Energy"));
+        assert_eq!((cards[0].id.as_deref(), cards[0].id_line, cards[0].last_line), (Some("fc-aa11"), Some(4), 4));
+        assert_eq!(cards[1].question, "Name the\norganelle");
+        assert_eq!(cards[1].answer, "Mitochondrion\nthe powerhouse");
+        assert_eq!((cards[1].id.as_deref(), cards[1].last_line), (Some("fc-bb22"), 13));
+    }
+
+    #[test]
+    fn double_colons_in_prose_and_code_are_not_cards() {
+        let text = "Call `HashMap::new()` first\nUse std::fs to read files\n`a :: b` is a cons\n\n\
+                    - Which `Vec` method appends? :: `Vec::push`\n1. Capital of France :: Paris\nGröße :: size\n* `x :: y` in Haskell? :: A type signature\n";
+        let cards = parse_note(text);
+        let pairs: Vec<(&str, &str)> = cards.iter().map(|c| (c.question.as_str(), c.answer.as_str())).collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("Which `Vec` method appends?", "`Vec::push`"),
+                ("Capital of France", "Paris"),
+                ("Größe", "size"),
+                ("`x :: y` in Haskell?", "A type signature"),
+            ]
+        );
+        assert_eq!(strip_list_marker("12. Q"), "Q");
+        assert_eq!(strip_list_marker("1.5 is a number"), "1.5 is a number");
+    }
+
+    #[test]
+    fn new_block_ids_avoid_taken_ones() {
+        let first = new_block_id("a.md", 1, &HashSet::new());
+        assert!(first.starts_with("fc-") && first.len() == 15);
+        assert!(split_block_id(&format!("x ^{first}")).1.is_some());
+    }
+
+    #[test]
+    fn block_ids_keep_line_endings() {
+        let path = std::env::temp_dir().join(format!("notes-test-{}.md", std::process::id()));
+        let ids = HashMap::from([(0, "fc-aa11".to_string()), (2, "fc-bb22".to_string())]);
+        write_block_ids(&path, "Q :: A\r\n\nR :: B", &ids).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written, "Q :: A ^fc-aa11\r\n\nR :: B ^fc-bb22");
+    }
+
+    #[test]
+    fn resync_updates_the_linked_card() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute("INSERT INTO collections (name) VALUES ('Bio')", []).unwrap();
+        conn.execute("INSERT INTO sub_collections (name, collection_id) VALUES ('- None -', 1)", []).unwrap();
+        let card = |answer: &str| NoteCard {
+            id: None,
+            id_line: None,
+            question: "Q".to_string(),
+            answer: answer.to_string(),
+            last_line: 0,
+        };
+        assert!(matches!(sync_card(&conn, 1, 1, "a.md", "fc-aa11", &card("A")).unwrap(), SyncOutcome::Added));
+        assert!(matches!(sync_card(&conn, 1, 1, "a.md", "fc-aa11", &card("A")).unwrap(), SyncOutcome::Unchanged));
+        assert!(matches!(sync_card(&conn, 1, 1, "a.md", "fc-aa11", &card("B")).unwrap(), SyncOutcome::Updated));
+        let answers: Vec<String> = conn
+            .prepare("SELECT answer FROM cards")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(answers, vec!["B"]);
+    }
+}
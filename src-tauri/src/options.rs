@@ -0,0 +1,408 @@
+//! Study options: daily limits, learning steps, desired retention and the day rollover.
+//!
+//! Options live in `collection_options` as named presets that any number of collections can share.
+//! A collection uses the preset in its `options_id`, or the default one; a sub-collection can use its
+//! own preset instead, which then also applies below it. Study queues are cut to the cards due today
+//! and to what is left of the day's new card and review limits, counted from `review_log` so the
+//! limits hold across sessions. The day starts at the rollover hour of the options in effect.
+
+use crate::error::AppError;
+use crate::hierarchy;
+
+/// The preset created with the database; it cannot be deleted.
+const DEFAULT_OPTIONS_ID: i64 = 1;
+
+pub(crate) fn init_options_tables(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS collection_options (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            new_per_day INTEGER NOT NULL DEFAULT 20,
+            reviews_per_day INTEGER NOT NULL DEFAULT 200,
+            learning_steps TEXT NOT NULL DEFAULT '[1,10]',
+            desired_retention REAL NOT NULL DEFAULT 0.9,
+            rollover_hour INTEGER NOT NULL DEFAULT 4
+        )",
+        [],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO collection_options (id, name) VALUES (?1, 'Default')",
+        rusqlite::params![DEFAULT_OPTIONS_ID],
+    )?;
+    // NULL: the default preset for a collection, the parent's options for a sub-collection.
+    crate::add_column_if_missing(conn, "collections", "options_id", "INTEGER REFERENCES collection_options(id) ON DELETE SET NULL")?;
+    crate::add_column_if_missing(conn, "sub_collections", "options_id", "INTEGER REFERENCES collection_options(id) ON DELETE SET NULL")?;
+    Ok(())
+}
+
+/// The settings of a preset.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct OptionValues {
+    name: String,
+    /// New cards to introduce per day.
+    new_per_day: u32,
+    /// Reviews of cards already learned per day.
+    reviews_per_day: u32,
+    /// Minutes between answers while a card is being learned or relearned.
+    pub(crate) learning_steps: Vec<u32>,
+    /// Share of reviews meant to be remembered, from 0.7 to 0.99; higher means shorter intervals.
+    pub(crate) desired_retention: f64,
+    /// Local hour at which a new study day starts.
+    pub(crate) rollover_hour: u32,
+}
+
+#[derive(serde::Serialize)]
+pub struct CollectionOptions {
+    id: i64,
+    #[serde(flatten)]
+    pub(crate) values: OptionValues,
+    /// Collections and sub-collections using the preset.
+    used_by: u32,
+}
+
+const OPTIONS_COLUMNS: &str = "o.id, o.name, o.new_per_day, o.reviews_per_day, o.learning_steps, o.desired_retention, o.rollover_hour,
+    (SELECT COUNT(*) FROM collections c WHERE c.options_id = o.id AND c.deleted_at IS NULL)
+      + (SELECT COUNT(*) FROM sub_collections s WHERE s.options_id = o.id)";
+
+fn row_to_options(row: &rusqlite::Row) -> rusqlite::Result<CollectionOptions> {
+    let steps: String = row.get(4)?;
+    let learning_steps = serde_json::from_str(&steps)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, Box::new(e)))?;
+    Ok(CollectionOptions {
+        id: row.get(0)?,
+        values: OptionValues {
+            name: row.get(1)?,
+            new_per_day: row.get(2)?,
+            reviews_per_day: row.get(3)?,
+            learning_steps,
+            desired_retention: row.get(5)?,
+            rollover_hour: row.get(6)?,
+        },
+        used_by: row.get(7)?,
+    })
+}
+
+fn load_options(conn: &rusqlite::Connection, id: i64) -> Result<CollectionOptions, AppError> {
+    match conn.query_row(&format!("SELECT {OPTIONS_COLUMNS} FROM collection_options o WHERE o.id = ?1"), rusqlite::params![id], row_to_options) {
+        Ok(options) => Ok(options),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(AppError::not_found("Options preset not found")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The preset set on the sub-collection or its nearest ancestor with one, else the collection's.
+fn effective_options_id(conn: &rusqlite::Connection, collection_id: i64, sub_collection_id: Option<i64>) -> Result<i64, AppError> {
+    let id: Option<i64> = conn.query_row(
+        "WITH RECURSIVE up(id, parent_id, options_id, depth) AS (
+             SELECT id, parent_id, options_id, 0 FROM sub_collections WHERE id = ?2
+             UNION ALL SELECT s.id, s.parent_id, s.options_id, up.depth + 1 FROM sub_collections s JOIN up ON s.id = up.parent_id
+         )
+         SELECT COALESCE(
+             (SELECT options_id FROM up WHERE options_id IS NOT NULL ORDER BY depth LIMIT 1),
+             (SELECT options_id FROM collections WHERE id = ?1)
+         )",
+        rusqlite::params![collection_id, sub_collection_id],
+        |row| row.get(0),
+    )?;
+    Ok(id.unwrap_or(DEFAULT_OPTIONS_ID))
+}
+
+/// The options that apply to studying the collection, or the sub-collection.
+pub(crate) fn effective(conn: &rusqlite::Connection, collection_id: i64, sub_collection_id: Option<i64>) -> Result<CollectionOptions, AppError> {
+    load_options(conn, effective_options_id(conn, collection_id, sub_collection_id)?)
+}
+
+/// The options that apply to a card, from its sub-collection.
+pub(crate) fn for_card(conn: &rusqlite::Connection, card_id: i64) -> Result<CollectionOptions, AppError> {
+    let (collection_id, sub_collection_id): (i64, i64) = conn
+        .query_row(
+            "SELECT collection_id, sub_collection_id FROM cards WHERE id = ?1",
+            rusqlite::params![card_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| AppError::not_found("Card not found"))?;
+    effective(conn, collection_id, Some(sub_collection_id))
+}
+
+/// SQL for the start of the current study day with the given rollover hour, as UTC text comparable
+/// with the stored timestamps.
+pub(crate) fn day_start_sql(rollover_hour: u32) -> String {
+    format!("datetime('now', 'localtime', '-{rollover_hour} hours', 'start of day', '+{rollover_hour} hours', 'utc')")
+}
+
+/// New cards and reviews answered since the study day starting at `rollover_hour` began, in the
+/// collection, or the sub-collection and below it.
+fn done_today(conn: &rusqlite::Connection, collection_id: i64, sub_collection_id: Option<i64>, rollover_hour: u32) -> Result<(u32, u32), AppError> {
+    Ok(conn.query_row(
+        &format!(
+            "SELECT COUNT(*) FILTER (WHERE l.state_before = 'new'), COUNT(*) FILTER (WHERE l.state_before = 'review')
+             FROM review_log l JOIN cards c ON c.id = l.card_id
+             WHERE c.collection_id = ?1 AND (?2 IS NULL OR c.sub_collection_id IN {})
+               AND l.reviewed_at >= {}",
+            hierarchy::subtree("?2"),
+            day_start_sql(rollover_hour)
+        ),
+        rusqlite::params![collection_id, sub_collection_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?)
+}
+
+/// The prompts to study today, from `prompts` in creation order: those being learned that are due
+/// today, reviews due today up to what is left of the review limit, and new ones up to what is left
+/// of the new card limit.
+pub(crate) fn todays_prompts(
+    conn: &rusqlite::Connection,
+    collection_id: i64,
+    sub_collection_id: Option<i64>,
+    prompts: Vec<crate::card_types::StudyPrompt>,
+) -> Result<Vec<crate::card_types::StudyPrompt>, AppError> {
+    let options = effective(conn, collection_id, sub_collection_id)?.values;
+    let (new_done, reviews_done) = done_today(conn, collection_id, sub_collection_id, options.rollover_hour)?;
+    let mut new_left = options.new_per_day.saturating_sub(new_done);
+    let mut reviews_left = options.reviews_per_day.saturating_sub(reviews_done);
+    let tomorrow: String =
+        conn.query_row(&format!("SELECT datetime({}, '+1 day')", day_start_sql(options.rollover_hour)), [], |row| row.get(0))?;
+    Ok(prompts
+        .into_iter()
+        .filter(|prompt| {
+            let due_today = prompt.due.as_deref().is_some_and(|due| due < tomorrow.as_str());
+            let left = match prompt.state.as_str() {
+                "new" => &mut new_left,
+                "review" if due_today => &mut reviews_left,
+                _ => return due_today,
+            };
+            let take = *left > 0;
+            *left = left.saturating_sub(1);
+            take
+        })
+        .collect())
+}
+
+fn check_values(values: &OptionValues) -> Result<(), AppError> {
+    if values.name.trim().is_empty() {
+        return Err(AppError::validation("name", "Preset name cannot be empty"));
+    }
+    if values.learning_steps.is_empty() || values.learning_steps.contains(&0) {
+        return Err(AppError::validation("learningSteps", "Enter at least one learning step of a minute or more"));
+    }
+    if !(0.7..=0.99).contains(&values.desired_retention) {
+        return Err(AppError::validation("desiredRetention", "Desired retention must be between 70% and 99%"));
+    }
+    if values.rollover_hour > 23 {
+        return Err(AppError::validation("rolloverHour", "The rollover hour must be from 0 to 23"));
+    }
+    Ok(())
+}
+
+/// All presets, the default one first.
+#[tauri::command]
+pub fn list_collection_options(app: tauri::AppHandle) -> Result<Vec<CollectionOptions>, AppError> {
+    let conn = crate::open_db(&app)?;
+    let mut stmt = conn.prepare(&format!("SELECT {OPTIONS_COLUMNS} FROM collection_options o ORDER BY o.id != ?1, o.name"))?;
+    let presets = stmt.query_map(rusqlite::params![DEFAULT_OPTIONS_ID], row_to_options)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(presets)
+}
+
+/// Create a preset, or with `id` change one; changes apply to everything using it.
+#[tauri::command]
+pub fn save_collection_options(app: tauri::AppHandle, id: Option<i64>, options: OptionValues) -> Result<CollectionOptions, AppError> {
+    check_values(&options)?;
+    let conn = crate::open_db(&app)?;
+    let name = options.name.trim();
+    let taken: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM collection_options WHERE name = ?1 AND id IS NOT ?2)",
+        rusqlite::params![name, id],
+        |row| row.get(0),
+    )?;
+    if taken {
+        return Err(AppError::Duplicate(format!("A preset named \"{name}\" already exists")));
+    }
+    let steps = serde_json::to_string(&options.learning_steps)?;
+    let params = rusqlite::params![
+        name, options.new_per_day, options.reviews_per_day, steps, options.desired_retention, options.rollover_hour, id
+    ];
+    let id = match id {
+        Some(id) => {
+            let changed = conn.execute(
+                "UPDATE collection_options SET name = ?1, new_per_day = ?2, reviews_per_day = ?3, learning_steps = ?4,
+                     desired_retention = ?5, rollover_hour = ?6
+                 WHERE id = ?7",
+                params,
+            )?;
+            if changed == 0 {
+                return Err(AppError::not_found("Options preset not found"));
+            }
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO collection_options (name, new_per_day, reviews_per_day, learning_steps, desired_retention, rollover_hour)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                &params[..6],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+    load_options(&conn, id)
+}
+
+/// Delete a preset other than the default; what used it goes back to its parent's or the default options.
+#[tauri::command]
+pub fn delete_collection_options(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    if id == DEFAULT_OPTIONS_ID {
+        return Err(AppError::validation("id", "The default preset cannot be deleted"));
+    }
+    let conn = crate::open_db(&app)?;
+    if conn.execute("DELETE FROM collection_options WHERE id = ?1", rusqlite::params![id])? == 0 {
+        return Err(AppError::not_found("Options preset not found"));
+    }
+    Ok(())
+}
+
+/// Use a preset for the collection, or for the sub-collection and below it. Without `options_id` the
+/// collection goes back to the default preset and the sub-collection to its parent's options.
+#[tauri::command]
+pub fn set_collection_options(
+    app: tauri::AppHandle,
+    collection_id: i64,
+    sub_collection_id: Option<i64>,
+    options_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = crate::open_db(&app)?;
+    if let Some(id) = options_id {
+        load_options(&conn, id)?;
+    }
+    match sub_collection_id {
+        Some(sub_id) => {
+            crate::check_sub_collection(&conn, collection_id, sub_id)?;
+            conn.execute("UPDATE sub_collections SET options_id = ?1 WHERE id = ?2", rusqlite::params![options_id, sub_id])?;
+        }
+        None => {
+            let changed = conn.execute(
+                "UPDATE collections SET options_id = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                rusqlite::params![options_id, collection_id],
+            )?;
+            if changed == 0 {
+                return Err(AppError::not_found("Collection not found"));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct StudyDay {
+    /// The options in effect.
+    options: CollectionOptions,
+    /// The preset set on this collection or sub-collection itself; none when it inherits.
+    own_options_id: Option<i64>,
+    new_done: u32,
+    reviews_done: u32,
+}
+
+/// The options in effect for studying the collection or sub-collection, and what has been studied today.
+#[tauri::command]
+pub fn get_study_day(app: tauri::AppHandle, collection_id: i64, sub_collection_id: Option<i64>) -> Result<StudyDay, AppError> {
+    let conn = crate::open_db(&app)?;
+    let own_options_id: Option<i64> = match sub_collection_id {
+        Some(sub_id) => {
+            crate::check_sub_collection(&conn, collection_id, sub_id)?;
+            conn.query_row("SELECT options_id FROM sub_collections WHERE id = ?1", rusqlite::params![sub_id], |row| row.get(0))?
+        }
+        None => conn
+            .query_row("SELECT options_id FROM collections WHERE id = ?1", rusqlite::params![collection_id], |row| row.get(0))
+            .map_err(|_| AppError::not_found("Collection not found"))?,
+    };
+    let options = effective(&conn, collection_id, sub_collection_id)?;
+    let (new_done, reviews_done) = done_today(&conn, collection_id, sub_collection_id, options.values.rollover_hour)?;
+    Ok(StudyDay {
+        options,
+        own_options_id,
+        new_done,
+        reviews_done,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collection 1 with four new cards: two at the top, two in sub-collection 2 under 3.
+    fn db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A');
+             INSERT INTO sub_collections (id, name, collection_id, parent_id) VALUES
+                 (1, '- None -', 1, NULL), (3, 'Ch1', 1, NULL), (2, 'Sec', 1, 3);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES
+                 (1, 'Q1', 'A1', 1, 1), (2, 'Q2', 'A2', 1, 1), (3, 'Q3', 'A3', 1, 2), (4, 'Q4', 'A4', 1, 2);
+             INSERT INTO collection_options (id, name, new_per_day, rollover_hour) VALUES (2, 'Slow', 1, 0), (3, 'Night', 20, 23);",
+        )
+        .unwrap();
+        crate::card_types::fill_missing_prompts(&conn).unwrap();
+        conn
+    }
+
+    fn todays(conn: &rusqlite::Connection, sub_collection_id: Option<i64>) -> usize {
+        let prompts = crate::card_types::study_prompts(conn, 1, sub_collection_id).unwrap();
+        todays_prompts(conn, 1, sub_collection_id, prompts).unwrap().len()
+    }
+
+    #[test]
+    fn sub_collection_presets_apply_below_them() {
+        let conn = db();
+        assert_eq!(effective(&conn, 1, Some(2)).unwrap().id, DEFAULT_OPTIONS_ID);
+        conn.execute("UPDATE collections SET options_id = 3", []).unwrap();
+        conn.execute("UPDATE sub_collections SET options_id = 2 WHERE id = 3", []).unwrap();
+        assert_eq!(effective(&conn, 1, Some(2)).unwrap().id, 2);
+        assert_eq!(effective(&conn, 1, Some(1)).unwrap().id, 3);
+        assert_eq!(effective(&conn, 1, None).unwrap().id, 3);
+        assert_eq!(for_card(&conn, 3).unwrap().id, 2);
+    }
+
+    #[test]
+    fn new_cards_are_cut_to_what_is_left_of_the_limit() {
+        let conn = db();
+        assert_eq!(todays(&conn, None), 4);
+        conn.execute("UPDATE collection_options SET new_per_day = 3 WHERE id = 1", []).unwrap();
+        assert_eq!(todays(&conn, None), 3);
+        conn.execute(
+            "INSERT INTO review_log (prompt_id, card_id, grade, state_before)
+             SELECT id, card_id, 'good', 'new' FROM card_prompts WHERE card_id = 1",
+            [],
+        )
+        .unwrap();
+        conn.execute("UPDATE card_prompts SET state = 'learning', due = datetime('now', '+1 minutes') WHERE card_id = 1", []).unwrap();
+        // The card being learned is still due today, and only two new ones are left.
+        assert_eq!(todays(&conn, None), 3);
+        assert_eq!(todays(&conn, Some(2)), 2);
+        conn.execute("UPDATE sub_collections SET options_id = 2 WHERE id = 3", []).unwrap();
+        assert_eq!(todays(&conn, Some(2)), 1);
+    }
+
+    #[test]
+    fn the_day_starts_at_the_rollover_hour_in_effect() {
+        let conn = db();
+        conn.execute("UPDATE sub_collections SET options_id = 3 WHERE id = 3", []).unwrap();
+        let night_start: String = conn.query_row(&format!("SELECT {}", day_start_sql(23)), [], |r| r.get(0)).unwrap();
+        conn.execute(
+            "INSERT INTO review_log (prompt_id, card_id, grade, state_before, reviewed_at)
+             SELECT id, card_id, 'good', 'new', CASE card_id WHEN 3 THEN ?1 ELSE datetime(?1, '-1 seconds') END
+             FROM card_prompts WHERE card_id IN (3, 4)",
+            [&night_start],
+        )
+        .unwrap();
+        let hour = effective(&conn, 1, Some(3)).unwrap().values.rollover_hour;
+        assert_eq!(hour, 23);
+        assert_eq!(done_today(&conn, 1, Some(3), hour).unwrap(), (1, 0));
+    }
+
+    #[test]
+    fn corrupt_learning_steps_are_an_error() {
+        let conn = db();
+        conn.execute("UPDATE collection_options SET learning_steps = 'not json' WHERE id = 2", []).unwrap();
+        assert_eq!(load_options(&conn, 2).err().map(|e| e.code()), Some("db"));
+        assert_eq!(load_options(&conn, 99).err().map(|e| e.code()), Some("not_found"));
+    }
+}
@@ -0,0 +1,273 @@
+//! PDF ingestion for bulk create: text is extracted per page (pure Rust, no Poppler) and split into
+//! chunks small enough for one card each. Pages without a text layer are left for OCR in the webview,
+//! which gets the whole page rendered by Pdfium through `read_pdf_page_image`.
+
+use crate::error::AppError;
+use base64::Engine;
+use pdfium_render::prelude::{PdfRenderConfig, Pdfium};
+use tauri::Manager;
+
+/// Upper bound on chunk length, so one generation request stays about one topic.
+const MAX_CHUNK_CHARS: usize = 1500;
+/// Longest line still treated as a heading.
+const MAX_HEADING_CHARS: usize = 80;
+/// Pages with fewer letters and digits than this (page numbers, running headers) count as image-only.
+const MIN_PAGE_TEXT_CHARS: usize = 40;
+/// Resolution pages are rendered at for OCR; Tesseract works best around 300 DPI.
+const OCR_DPI: f32 = 300.0;
+/// Cap on either side of a rendered page, so oversized pages don't take gigabytes.
+const MAX_RENDER_PIXELS: i32 = 5000;
+
+#[derive(serde::Serialize)]
+pub struct PdfPage {
+    /// 1-based page number.
+    pub number: u32,
+    /// Text chunks in reading order; empty when the page needs OCR.
+    pub chunks: Vec<String>,
+}
+
+impl PdfPage {
+    pub fn needs_ocr(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+pub fn is_pdf(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("pdf"))
+        .unwrap_or(false)
+}
+
+/// Extract and chunk the text of every page.
+pub fn extract_pages(path: &str) -> Result<Vec<PdfPage>, AppError> {
+    let pages = pdf_extract::extract_text_by_pages(path).map_err(|e| AppError::io(format!("Could not read PDF: {e}")))?;
+    Ok(pages
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            let letters = text.chars().filter(|c| c.is_alphanumeric()).count();
+            PdfPage {
+                number: i as u32 + 1,
+                chunks: if letters < MIN_PAGE_TEXT_CHARS { Vec::new() } else { chunk_text(text) },
+            }
+        })
+        .collect())
+}
+
+fn looks_like_heading(line: &str) -> bool {
+    let line = line.trim();
+    let Some(first) = line.chars().next() else {
+        return false;
+    };
+    line.chars().count() <= MAX_HEADING_CHARS
+        && (first.is_uppercase() || first.is_ascii_digit())
+        && line.chars().any(char::is_alphabetic)
+        && !line.ends_with(['.', ',', ';', ':', '?', '!'])
+}
+
+/// Split off a leading heading line: either a block that is a single heading-like line, or a
+/// heading-like first line clearly shorter than the text under it.
+fn split_heading<'a, 'b>(block: &'b [&'a str]) -> (Option<&'a str>, &'b [&'a str]) {
+    match block {
+        [first] if looks_like_heading(first) => (Some(first.trim()), &[]),
+        [first, second, ..] if looks_like_heading(first) && first.trim().len() * 5 <= second.trim().len() * 3 => {
+            (Some(first.trim()), &block[1..])
+        }
+        _ => (None, block),
+    }
+}
+
+/// Join the lines of a paragraph, undoing end-of-line hyphenation.
+fn join_lines(lines: &[&str]) -> String {
+    let mut text = String::new();
+    for line in lines.iter().map(|l| l.trim()) {
+        if text.ends_with('-') && line.starts_with(char::is_lowercase) {
+            text.pop();
+        } else if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(line);
+    }
+    text
+}
+
+/// Break a paragraph longer than `max` chars at word boundaries.
+fn split_long(paragraph: &str, max: usize) -> Vec<String> {
+    if paragraph.len() <= max {
+        return vec![paragraph.to_string()];
+    }
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    for word in paragraph.split_whitespace() {
+        if !piece.is_empty() && piece.len() + 1 + word.len() > max {
+            pieces.push(std::mem::take(&mut piece));
+        }
+        if !piece.is_empty() {
+            piece.push(' ');
+        }
+        piece.push_str(word);
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Split page text into chunks: a heading starts a new chunk (and is repeated on each chunk of its
+/// section for context), paragraphs are packed together up to `MAX_CHUNK_CHARS`.
+pub fn chunk_text(text: &str) -> Vec<String> {
+    let mut blocks: Vec<Vec<&str>> = vec![Vec::new()];
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if blocks.last().is_some_and(|b| !b.is_empty()) {
+                blocks.push(Vec::new());
+            }
+        } else if let Some(block) = blocks.last_mut() {
+            block.push(line);
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let mut heading: Option<&str> = None;
+    let mut current = String::new();
+    let mut flush = |heading: Option<&str>, current: &mut String| {
+        if !current.is_empty() {
+            chunks.push(match heading {
+                Some(h) => format!("{h}\n\n{current}"),
+                None => current.clone(),
+            });
+            current.clear();
+        }
+    };
+    for block in blocks.iter().filter(|b| !b.is_empty()) {
+        let (block_heading, body) = split_heading(block);
+        if block_heading.is_some() {
+            flush(heading, &mut current);
+            heading = block_heading;
+        }
+        if body.is_empty() {
+            continue;
+        }
+        for piece in split_long(&join_lines(body), MAX_CHUNK_CHARS) {
+            if !current.is_empty() && current.len() + 2 + piece.len() > MAX_CHUNK_CHARS {
+                flush(heading, &mut current);
+            }
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(&piece);
+        }
+    }
+    flush(heading, &mut current);
+    chunks
+}
+
+/// Encode raw 8-bit gray samples as PNG.
+fn encode_png(width: u32, height: u32, samples: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(AppError::io)?;
+    writer.write_image_data(samples).map_err(AppError::io)?;
+    writer.finish().map_err(AppError::io)?;
+    Ok(out)
+}
+
+/// Bind Pdfium, which is loaded at runtime: the copy bundled in the app's resources if there is one,
+/// else the system library.
+fn pdfium(app: &tauri::AppHandle) -> Result<Pdfium, AppError> {
+    let bundled = app
+        .path()
+        .resource_dir()
+        .ok()
+        .and_then(|dir| Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(&dir)).ok());
+    let bindings = match bundled {
+        Some(bindings) => bindings,
+        None => Pdfium::bind_to_system_library()
+            .map_err(|e| AppError::io(format!("Could not load the PDF renderer (Pdfium): {e}")))?,
+    };
+    Ok(Pdfium::new(bindings))
+}
+
+/// Render a whole page as a grayscale PNG data URL, as Tesseract needs it. Rendering rather than
+/// pulling out embedded images covers every scan encoding (JBIG2, CCITT, JPEG, strips), the page
+/// transform, and pages drawn as vectors.
+fn page_image_data_url(app: &tauri::AppHandle, path: &str, page: u32) -> Result<String, AppError> {
+    let pdfium = pdfium(app)?;
+    let doc = pdfium
+        .load_pdf_from_file(path, None)
+        .map_err(|e| AppError::io(format!("Could not read PDF: {e}")))?;
+    let index = page
+        .checked_sub(1)
+        .and_then(|i| u16::try_from(i).ok())
+        .filter(|&i| i < doc.pages().len())
+        .ok_or_else(|| AppError::validation("page", format!("PDF has no page {page}")))?;
+    let config = PdfRenderConfig::new()
+        .scale_page_by_factor(OCR_DPI / 72.0)
+        .set_maximum_width(MAX_RENDER_PIXELS)
+        .set_maximum_height(MAX_RENDER_PIXELS);
+    let render_error = |e| AppError::io(format!("Could not render page {page}: {e}"));
+    let pdf_page = doc.pages().get(index).map_err(render_error)?;
+    let bitmap = pdf_page.render_with_config(&config).map_err(render_error)?;
+    let image = bitmap.as_image().into_luma8();
+    let png = encode_png(image.width(), image.height(), image.as_raw())?;
+    Ok(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png)))
+}
+
+/// Text chunks per page, for previewing what bulk create will generate from a PDF.
+#[tauri::command]
+pub fn extract_pdf_pages(path: String) -> Result<Vec<PdfPage>, AppError> {
+    extract_pages(&path)
+}
+
+/// Rendering of a page without a text layer, as a data URL for OCR.
+#[tauri::command]
+pub fn read_pdf_page_image(app: tauri::AppHandle, path: String, page: u32) -> Result<String, AppError> {
+    page_image_data_url(&app, &path, page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headings_start_chunks_and_repeat_on_each() {
+        let text = "Cells\n\nThe cell is the basic unit of life.\n\nMitosis\n\nMitosis splits a cell in two.";
+        assert_eq!(
+            chunk_text(text),
+            vec!["Cells\n\nThe cell is the basic unit of life.", "Mitosis\n\nMitosis splits a cell in two."]
+        );
+        let long = format!("Cells\n\n{}", ["word"; 400].join(" "));
+        let chunks = chunk_text(&long);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.starts_with("Cells\n\n") && c.len() <= MAX_CHUNK_CHARS + 7));
+    }
+
+    #[test]
+    fn lines_are_joined_undoing_hyphenation() {
+        assert_eq!(join_lines(&["photo-", "synthesis makes", "Sugar-", "Free food"]), "photosynthesis makes Sugar- Free food");
+    }
+
+    #[test]
+    fn sentences_are_not_headings() {
+        assert!(looks_like_heading("2.1 Cell Structure"));
+        assert!(!looks_like_heading("The cell is the basic unit."));
+        assert!(!looks_like_heading("lowercase start"));
+        assert!(!looks_like_heading("42"));
+    }
+
+    #[test]
+    fn long_paragraphs_split_at_words() {
+        let pieces = split_long("aaa bbb ccc ddd", 7);
+        assert_eq!(pieces, vec!["aaa bbb", "ccc ddd"]);
+    }
+
+    #[test]
+    fn rendered_pages_encode_as_png() {
+        let png = encode_png(2, 1, &[0, 255]).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+}
@@ -0,0 +1,204 @@
+//! Directory scanning options shared by file listing, counting and directory watch.
+
+use crate::error::AppError;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileSort {
+    #[default]
+    Name,
+    Modified,
+}
+
+/// How to find files under a directory. Omitted fields keep the old single-level behavior.
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// Formats to include, e.g. `["png", "jpeg"]`. Empty means every file.
+    formats: Vec<String>,
+    /// Descend into subdirectories.
+    recursive: bool,
+    /// Deepest subdirectory level to visit when recursive (1 = direct children only); unlimited if omitted.
+    max_depth: Option<usize>,
+    /// Glob patterns matched against the path relative to the directory; a file must match one if any are given.
+    include: Vec<String>,
+    /// Glob patterns matched against the relative path; matching files are skipped.
+    exclude: Vec<String>,
+    sort: FileSort,
+    descending: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct ScannedFile {
+    path: String,
+    size: u64,
+    /// Last modification time in milliseconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified_ms: Option<u64>,
+}
+
+/// `ScanOptions` with extensions resolved and globs compiled.
+pub struct FileMatcher {
+    root: std::path::PathBuf,
+    extensions: Vec<String>,
+    max_depth: usize,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>, AppError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| AppError::validation("options", format!("Invalid pattern {pattern:?}: {e}")))?);
+    }
+    Ok(Some(builder.build().map_err(|e| AppError::validation("options", e.to_string()))?))
+}
+
+impl FileMatcher {
+    pub fn new(directory: &std::path::Path, options: &ScanOptions) -> Result<Self, AppError> {
+        let max_depth = match (options.recursive, options.max_depth) {
+            (false, _) => 1,
+            (true, Some(0)) => return Err(AppError::validation("options", "Max depth must be at least 1")),
+            (true, Some(depth)) => depth,
+            (true, None) => usize::MAX,
+        };
+        Ok(FileMatcher {
+            root: directory.to_path_buf(),
+            extensions: options.formats.iter().flat_map(|f| crate::extensions_for_format(f)).collect(),
+            max_depth,
+            include: build_glob_set(&options.include)?,
+            exclude: build_glob_set(&options.exclude)?,
+        })
+    }
+
+    pub fn recursive(&self) -> bool {
+        self.max_depth > 1
+    }
+
+    /// Whether a file path (under the root) passes the depth, extension and glob filters.
+    pub fn matches(&self, path: &std::path::Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if relative.components().count() > self.max_depth {
+            return false;
+        }
+        if !self.extensions.is_empty() {
+            let ext = path.extension().and_then(|e| e.to_str()).map(|s| s.to_lowercase());
+            if !ext.map(|e| self.extensions.contains(&e)).unwrap_or(false) {
+                return false;
+            }
+        }
+        if let Some(include) = &self.include {
+            if !include.is_match(relative) {
+                return false;
+            }
+        }
+        if let Some(exclude) = &self.exclude {
+            if exclude.is_match(relative) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Walk a directory and return matching files with their metadata, sorted as requested.
+pub fn scan_directory(directory: &str, options: &ScanOptions) -> Result<Vec<ScannedFile>, AppError> {
+    let dir = std::path::Path::new(directory);
+    if !dir.is_dir() {
+        return Err(AppError::validation("directory", "Path is not a directory"));
+    }
+    let matcher = FileMatcher::new(dir, options)?;
+    let mut files: Vec<ScannedFile> = walkdir::WalkDir::new(dir)
+        .min_depth(1)
+        .max_depth(matcher.max_depth)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && matcher.matches(e.path()))
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            let modified_ms = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64);
+            Some(ScannedFile {
+                path: e.into_path().into_os_string().into_string().ok()?,
+                size: metadata.len(),
+                modified_ms,
+            })
+        })
+        .collect();
+    match options.sort {
+        FileSort::Name => files.sort_by(|a, b| a.path.cmp(&b.path)),
+        FileSort::Modified => files.sort_by(|a, b| a.modified_ms.cmp(&b.modified_ms).then_with(|| a.path.cmp(&b.path))),
+    }
+    if options.descending {
+        files.reverse();
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(json: serde_json::Value) -> ScanOptions {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn matcher_applies_depth_formats_and_globs() {
+        let root = std::path::Path::new("/shots");
+        let flat = FileMatcher::new(root, &options(serde_json::json!({ "formats": ["jpeg"] }))).unwrap();
+        assert!(!flat.recursive());
+        assert!(flat.matches(&root.join("a.JPG")));
+        assert!(!flat.matches(&root.join("a.png")));
+        assert!(!flat.matches(&root.join("sub/a.jpg")));
+        assert!(!flat.matches(std::path::Path::new("/elsewhere/a.jpg")));
+
+        let deep = FileMatcher::new(
+            root,
+            &options(serde_json::json!({ "recursive": true, "max_depth": 2, "include": ["**/*.png"], "exclude": ["drafts/**"] })),
+        )
+        .unwrap();
+        assert!(deep.matches(&root.join("sub/a.png")));
+        assert!(!deep.matches(&root.join("sub/deeper/a.png")));
+        assert!(!deep.matches(&root.join("drafts/a.png")));
+        assert!(!deep.matches(&root.join("sub/a.jpg")));
+    }
+
+    #[test]
+    fn bad_options_are_rejected() {
+        let root = std::path::Path::new("/shots");
+        assert!(FileMatcher::new(root, &options(serde_json::json!({ "recursive": true, "max_depth": 0 }))).is_err());
+        assert!(FileMatcher::new(root, &options(serde_json::json!({ "include": ["a[b"] }))).is_err());
+    }
+
+    #[test]
+    fn scan_walks_and_sorts() {
+        let dir = std::env::temp_dir().join(format!("scan-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        for name in ["b.png", "a.png", "notes.txt", "sub/c.png"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+        let names = |json| -> Vec<String> {
+            scan_directory(dir.to_str().unwrap(), &options(json))
+                .unwrap()
+                .into_iter()
+                .map(|f| std::path::Path::new(&f.path).strip_prefix(&dir).unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+        assert_eq!(names(serde_json::json!({ "formats": ["png"] })), vec!["a.png", "b.png"]);
+        assert_eq!(
+            names(serde_json::json!({ "formats": ["png"], "recursive": true, "descending": true })),
+            vec!["sub/c.png", "b.png", "a.png"]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
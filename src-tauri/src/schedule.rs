@@ -0,0 +1,303 @@
+//! Spaced repetition scheduling of card prompts.
+//!
+//! A simplified SM-2: each prompt has an interval and an ease factor. New and forgotten prompts go
+//! through the learning steps of their options (minutes apart) before they get an interval in days.
+//! Forgetting (`Again`) sends a prompt back to the first step and lowers the ease; remembering a
+//! learned prompt multiplies the interval by the ease, less for `Hard` and more for `Easy`, scaled
+//! for the desired retention. Every answer is logged in `review_log`, which the daily limits count.
+
+use crate::error::AppError;
+
+const MIN_EASE: f64 = 1.3;
+/// Retention the plain SM-2 intervals are taken to give.
+const BASE_RETENTION: f64 = 0.9;
+
+pub(crate) fn init_review_log(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    // Index into the learning steps of a prompt being learned or relearned.
+    crate::add_column_if_missing(conn, "card_prompts", "step", "INTEGER NOT NULL DEFAULT 0")?;
+    // `prompt_id` is NULL once the prompt is gone (e.g. its cloze was removed); the review still counts
+    // for the card.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS review_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            prompt_id INTEGER REFERENCES card_prompts(id) ON DELETE SET NULL,
+            card_id INTEGER NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+            grade TEXT NOT NULL,
+            state_before TEXT NOT NULL,
+            reviewed_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS review_log_reviewed_at ON review_log(reviewed_at)", [])?;
+    // In databases from before, deleting a prompt deleted its reviews too.
+    let sql: String = conn.query_row("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'review_log'", [], |row| {
+        row.get(0)
+    })?;
+    let cascading = "prompt_id INTEGER NOT NULL REFERENCES card_prompts(id) ON DELETE CASCADE";
+    if sql.contains(cascading) {
+        let sql = sql.replace(cascading, "prompt_id INTEGER REFERENCES card_prompts(id) ON DELETE SET NULL");
+        crate::rebuild_table(conn, "review_log", &sql)?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    fn as_str(self) -> &'static str {
+        match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct PromptSchedule {
+    id: i64,
+    state: String,
+    due: String,
+    interval_days: f64,
+    ease: f64,
+    reps: i64,
+    lapses: i64,
+    /// The answer was a lapse that made the card a leech or kept it one, see `leech::check_lapse`.
+    leech: bool,
+}
+
+/// A prompt's scheduling state.
+struct Progress {
+    state: &'static str,
+    step: usize,
+    interval_days: f64,
+    ease: f64,
+    lapses: i64,
+}
+
+enum Delay {
+    Minutes(u32),
+    Days(f64),
+}
+
+/// Progress after answering with `grade`, and how long until the prompt is due.
+fn next_state(current: &Progress, grade: Grade, steps: &[u32], retention: f64) -> (Progress, Delay) {
+    let learning = current.state != "review";
+    // Asking for better retention than SM-2 gives shortens intervals, and the other way round.
+    let modifier = retention.ln() / BASE_RETENTION.ln();
+    let step_minutes = |step: usize| steps.get(step).or(steps.last()).copied().unwrap_or(1);
+    let grown = |factor: f64| (current.interval_days * factor * modifier).max(current.interval_days + 1.0);
+    let graduated = |interval_days: f64, ease: f64| {
+        (Progress { state: "review", step: 0, interval_days, ease, lapses: current.lapses }, Delay::Days(interval_days))
+    };
+    match grade {
+        Grade::Again => {
+            let state = if current.state == "new" || current.state == "learning" { "learning" } else { "relearning" };
+            let lapses = if learning { current.lapses } else { current.lapses + 1 };
+            let ease = (current.ease - 0.2).max(MIN_EASE);
+            let interval_days = current.interval_days;
+            (Progress { state, step: 0, interval_days, ease, lapses }, Delay::Minutes(step_minutes(0)))
+        }
+        Grade::Hard if learning => {
+            let state = if current.state == "new" { "learning" } else { current.state };
+            let progress = Progress { state, ..*current };
+            (progress, Delay::Minutes(step_minutes(current.step)))
+        }
+        Grade::Good if learning && current.step + 1 < steps.len() => {
+            let state = if current.state == "new" { "learning" } else { current.state };
+            let step = current.step + 1;
+            (Progress { state, step, ..*current }, Delay::Minutes(step_minutes(step)))
+        }
+        Grade::Good if learning => graduated(1.0, current.ease),
+        Grade::Easy if learning => graduated(4.0, current.ease + 0.15),
+        Grade::Hard => graduated(grown(1.2), (current.ease - 0.15).max(MIN_EASE)),
+        Grade::Good => graduated(grown(current.ease), current.ease),
+        Grade::Easy => graduated(grown(current.ease * 1.3), current.ease + 0.15),
+    }
+}
+
+/// Update a prompt's scheduling state for an answer graded `grade`, under the options of its card.
+/// Prompts of trashed cards are not found; suspended and buried cards cannot be reviewed.
+pub(crate) fn review(conn: &rusqlite::Connection, prompt_id: i64, grade: Grade) -> Result<PromptSchedule, AppError> {
+    let (card_id, suspended, buried): (i64, bool, bool) = conn
+        .query_row(
+            "SELECT c.id, c.suspended_at IS NOT NULL, c.buried_at IS NOT NULL
+             FROM card_prompts p JOIN cards c ON c.id = p.card_id
+             WHERE p.id = ?1 AND c.deleted_at IS NULL",
+            rusqlite::params![prompt_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|_| AppError::not_found("Card not found"))?;
+    if suspended || buried {
+        let status = if suspended { "suspended" } else { "buried" };
+        return Err(AppError::validation("promptId", format!("The card is {status} and cannot be reviewed")));
+    }
+    let (state, step, interval_days, ease, reps, lapses): (String, usize, f64, f64, i64, i64) = conn
+        .query_row(
+            "SELECT state, step, interval_days, ease, reps, lapses FROM card_prompts WHERE id = ?1",
+            rusqlite::params![prompt_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        )?;
+    let options = crate::options::for_card(conn, card_id)?.values;
+    let current = Progress {
+        state: match state.as_str() {
+            "learning" => "learning",
+            "review" => "review",
+            "relearning" => "relearning",
+            _ => "new",
+        },
+        step,
+        interval_days,
+        ease,
+        lapses,
+    };
+    let (next, delay) = next_state(&current, grade, &options.learning_steps, options.desired_retention);
+    let delay = match delay {
+        Delay::Minutes(minutes) => format!("+{minutes} minutes"),
+        Delay::Days(days) => format!("+{} seconds", (days * 86400.0).round() as i64),
+    };
+    let due: String = conn.query_row("SELECT datetime('now', ?1)", rusqlite::params![delay], |row| row.get(0))?;
+    conn.execute(
+        "UPDATE card_prompts SET state = ?1, step = ?2, due = ?3, interval_days = ?4, ease = ?5, reps = reps + 1, lapses = ?6,
+             last_reviewed_at = datetime('now')
+         WHERE id = ?7",
+        rusqlite::params![next.state, next.step, due, next.interval_days, next.ease, next.lapses, prompt_id],
+    )?;
+    conn.execute(
+        "INSERT INTO review_log (prompt_id, card_id, grade, state_before) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![prompt_id, card_id, grade.as_str(), current.state],
+    )?;
+    let leech = next.lapses > lapses && crate::leech::check_lapse(conn, prompt_id, next.lapses)?;
+    Ok(PromptSchedule {
+        id: prompt_id,
+        state: next.state.to_string(),
+        due,
+        interval_days: next.interval_days,
+        ease: next.ease,
+        reps: reps + 1,
+        lapses: next.lapses,
+        leech,
+    })
+}
+
+/// Record an answer to a prompt and schedule its next review.
+#[tauri::command]
+pub fn record_review(app: tauri::AppHandle, prompt_id: i64, grade: Grade) -> Result<PromptSchedule, AppError> {
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    let schedule = review(&tx, prompt_id, grade)?;
+    tx.commit()?;
+    Ok(schedule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEPS: &[u32] = &[1, 10];
+
+    fn progress(state: &'static str, step: usize, interval_days: f64) -> Progress {
+        Progress { state, step, interval_days, ease: 2.5, lapses: 0 }
+    }
+
+    fn minutes(delay: Delay) -> u32 {
+        match delay {
+            Delay::Minutes(minutes) => minutes,
+            Delay::Days(days) => panic!("expected minutes, got {days} days"),
+        }
+    }
+
+    #[test]
+    fn new_cards_go_through_the_learning_steps() {
+        let (next, delay) = next_state(&progress("new", 0, 0.0), Grade::Good, STEPS, BASE_RETENTION);
+        assert_eq!((next.state, next.step, minutes(delay)), ("learning", 1, 10));
+        let (next, delay) = next_state(&next, Grade::Hard, STEPS, BASE_RETENTION);
+        assert_eq!((next.state, next.step, minutes(delay)), ("learning", 1, 10));
+        let (next, _) = next_state(&next, Grade::Good, STEPS, BASE_RETENTION);
+        assert_eq!((next.state, next.interval_days), ("review", 1.0));
+        let (next, _) = next_state(&progress("new", 0, 0.0), Grade::Easy, STEPS, BASE_RETENTION);
+        assert_eq!((next.state, next.interval_days, next.ease), ("review", 4.0, 2.65));
+    }
+
+    #[test]
+    fn reviews_grow_and_lapses_relearn() {
+        let review = progress("review", 0, 10.0);
+        let (good, _) = next_state(&review, Grade::Good, STEPS, BASE_RETENTION);
+        assert!((good.interval_days - 25.0).abs() < 1e-9);
+        let (stricter, _) = next_state(&review, Grade::Good, STEPS, 0.95);
+        assert!(stricter.interval_days < good.interval_days);
+        let (hard, _) = next_state(&review, Grade::Hard, STEPS, BASE_RETENTION);
+        assert!(hard.interval_days < good.interval_days && hard.interval_days >= 11.0);
+
+        let (lapsed, delay) = next_state(&review, Grade::Again, STEPS, BASE_RETENTION);
+        assert_eq!((lapsed.state, lapsed.lapses, minutes(delay)), ("relearning", 1, 1));
+        assert!((lapsed.ease - 2.3).abs() < 1e-9);
+        let (again, _) = next_state(&lapsed, Grade::Again, STEPS, BASE_RETENTION);
+        assert_eq!(again.lapses, 1);
+        let floor = Progress { ease: MIN_EASE, ..progress("review", 0, 1.0) };
+        assert_eq!(next_state(&floor, Grade::Again, STEPS, BASE_RETENTION).0.ease, MIN_EASE);
+    }
+
+    #[test]
+    fn old_logs_keep_reviews_of_deleted_prompts() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "DROP TABLE review_log;
+             CREATE TABLE review_log (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 prompt_id INTEGER NOT NULL REFERENCES card_prompts(id) ON DELETE CASCADE,
+                 card_id INTEGER NOT NULL REFERENCES cards(id) ON DELETE CASCADE,
+                 grade TEXT NOT NULL,
+                 state_before TEXT NOT NULL,
+                 reviewed_at TEXT NOT NULL DEFAULT (datetime('now'))
+             );
+             INSERT INTO collections (id, name) VALUES (1, 'A');
+             INSERT INTO sub_collections (id, name, collection_id) VALUES (1, '- None -', 1);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES (1, 'Q', 'A', 1, 1);
+             INSERT INTO card_prompts (id, card_id, ordinal) VALUES (1, 1, 0);
+             INSERT INTO review_log (prompt_id, card_id, grade, state_before) VALUES (1, 1, 'good', 'new');",
+        )
+        .unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute("DELETE FROM card_prompts WHERE id = 1", []).unwrap();
+        let logged: (Option<i64>, i64) =
+            conn.query_row("SELECT prompt_id, card_id FROM review_log", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+        assert_eq!(logged, (None, 1));
+    }
+
+    #[test]
+    fn only_studyable_cards_are_reviewed() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A');
+             INSERT INTO sub_collections (id, name, collection_id) VALUES (1, '- None -', 1);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES
+                 (1, 'Q1', 'A', 1, 1), (2, 'Q2', 'A', 1, 1), (3, 'Q3', 'A', 1, 1), (4, 'Q4', 'A', 1, 1);
+             UPDATE cards SET deleted_at = datetime('now') WHERE id = 2;
+             UPDATE cards SET suspended_at = datetime('now') WHERE id = 3;
+             UPDATE cards SET buried_at = datetime('now') WHERE id = 4;",
+        )
+        .unwrap();
+        crate::card_types::fill_missing_prompts(&conn).unwrap();
+        let prompt = |card: i64| -> i64 {
+            conn.query_row("SELECT id FROM card_prompts WHERE card_id = ?1", rusqlite::params![card], |r| r.get(0)).unwrap()
+        };
+        let code = |card: i64| review(&conn, prompt(card), Grade::Good).err().map(|e| e.code());
+        assert_eq!(code(1), None);
+        assert_eq!(code(2), Some("not_found"));
+        assert_eq!(code(3), Some("validation"));
+        assert_eq!(code(4), Some("validation"));
+        let reviews: i64 = conn.query_row("SELECT COUNT(*) FROM review_log", [], |r| r.get(0)).unwrap();
+        assert_eq!(reviews, 1);
+    }
+}
@@ -0,0 +1,612 @@
+//! Study sessions that survive restarts.
+//!
+//! Starting a session fixes the order of the prompts to study (from the mode and seed) in
+//! `study_session_cards`. Each answer records an outcome for its prompt and moves the session's
+//! position past it, so an interrupted pass can be resumed where it stopped, on any launch. Sessions
+//! stay open until ended; starting a new one for the same collection and sub-collection ends the old.
+
+use crate::card_types::{self, StudyPrompt};
+use crate::error::AppError;
+use crate::options;
+use crate::schedule::{self, Grade};
+
+pub(crate) fn init_study_tables(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS study_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+            sub_collection_id INTEGER REFERENCES sub_collections(id) ON DELETE CASCADE,
+            mode TEXT NOT NULL,
+            seed INTEGER NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0,
+            started_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            ended_at TEXT
+        )",
+        [],
+    )?;
+    // Cards whose prompt goes away (trashed, purged, cloze removed) drop out of the session.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS study_session_cards (
+            session_id INTEGER NOT NULL REFERENCES study_sessions(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            prompt_id INTEGER NOT NULL REFERENCES card_prompts(id) ON DELETE CASCADE,
+            outcome TEXT,
+            answered_at TEXT,
+            PRIMARY KEY (session_id, position)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Order of the prompts in a session.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StudyMode {
+    /// Card creation order, each card's prompts together.
+    #[default]
+    Created,
+    /// Shuffled from the session's seed, so the same seed gives the same order.
+    Shuffle,
+    /// Highest share of reviews answered `again` first; prompts never reviewed come last.
+    HardestFirst,
+    /// Longest since the last review first, after the prompts never reviewed.
+    OldestReviewed,
+    /// One prompt from each sub-collection in turn, each in creation order.
+    Interleaved,
+}
+
+impl StudyMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            StudyMode::Created => "created",
+            StudyMode::Shuffle => "shuffle",
+            StudyMode::HardestFirst => "hardest_first",
+            StudyMode::OldestReviewed => "oldest_reviewed",
+            StudyMode::Interleaved => "interleaved",
+        }
+    }
+
+    fn parse(value: &str) -> StudyMode {
+        match value {
+            "shuffle" => StudyMode::Shuffle,
+            "hardest_first" => StudyMode::HardestFirst,
+            "oldest_reviewed" => StudyMode::OldestReviewed,
+            "interleaved" => StudyMode::Interleaved,
+            _ => StudyMode::Created,
+        }
+    }
+}
+
+/// SplitMix64, a small generator that is plenty for shuffling cards reproducibly.
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Fisher–Yates shuffle driven by `seed`.
+fn seeded_shuffle<T>(items: &mut [T], seed: i64) {
+    let mut rng = SeededRng(seed as u64);
+    for i in (1..items.len()).rev() {
+        let j = (rng.next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+fn lapse_rate(prompt: &StudyPrompt) -> f64 {
+    if prompt.reps == 0 {
+        0.0
+    } else {
+        prompt.lapses as f64 / prompt.reps as f64
+    }
+}
+
+/// Round robin over the sub-collections, taken in the order their first prompt appears.
+fn interleave(prompts: Vec<StudyPrompt>) -> Vec<StudyPrompt> {
+    let mut groups: Vec<std::collections::VecDeque<StudyPrompt>> = Vec::new();
+    let mut group_of: std::collections::HashMap<i64, usize> = std::collections::HashMap::new();
+    for prompt in prompts {
+        let index = *group_of.entry(prompt.sub_collection_id).or_insert_with(|| {
+            groups.push(std::collections::VecDeque::new());
+            groups.len() - 1
+        });
+        groups[index].push_back(prompt);
+    }
+    let mut out = Vec::new();
+    while !groups.is_empty() {
+        groups.retain_mut(|group| match group.pop_front() {
+            Some(prompt) => {
+                out.push(prompt);
+                true
+            }
+            None => false,
+        });
+    }
+    out
+}
+
+/// Put prompts, given in creation order, in the order of `mode`. Ties keep creation order.
+pub(crate) fn order_prompts(mut prompts: Vec<StudyPrompt>, mode: StudyMode, seed: i64) -> Vec<StudyPrompt> {
+    match mode {
+        StudyMode::Created => {}
+        StudyMode::Shuffle => seeded_shuffle(&mut prompts, seed),
+        StudyMode::HardestFirst => prompts.sort_by(|a, b| {
+            lapse_rate(b).total_cmp(&lapse_rate(a)).then(b.lapses.cmp(&a.lapses))
+        }),
+        // None sorts before any timestamp, and the stored timestamps sort as text.
+        StudyMode::OldestReviewed => prompts.sort_by(|a, b| a.last_reviewed_at.cmp(&b.last_reviewed_at)),
+        StudyMode::Interleaved => prompts = interleave(prompts),
+    }
+    prompts
+}
+
+/// A seed for when the caller gives none; small enough to survive a round trip through JavaScript.
+fn new_seed() -> i64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as i64 ^ d.as_secs() as i64)
+        .unwrap_or_default();
+    nanos & 0x7fff_ffff
+}
+
+/// What happened to a card in a session.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Again,
+    Hard,
+    Good,
+    Easy,
+    /// Looked at and moved on without grading.
+    Seen,
+    /// Suspended: left out of study until unsuspended.
+    Suspended,
+    /// Buried: left out of study until the next study day.
+    Buried,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Again => "again",
+            Outcome::Hard => "hard",
+            Outcome::Good => "good",
+            Outcome::Easy => "easy",
+            Outcome::Seen => "seen",
+            Outcome::Suspended => "suspended",
+            Outcome::Buried => "buried",
+        }
+    }
+
+    fn parse(value: &str) -> Outcome {
+        match value {
+            "again" => Outcome::Again,
+            "hard" => Outcome::Hard,
+            "good" => Outcome::Good,
+            "easy" => Outcome::Easy,
+            "suspended" => Outcome::Suspended,
+            "buried" => Outcome::Buried,
+            _ => Outcome::Seen,
+        }
+    }
+
+    fn grade(self) -> Option<Grade> {
+        match self {
+            Outcome::Again => Some(Grade::Again),
+            Outcome::Hard => Some(Grade::Hard),
+            Outcome::Good => Some(Grade::Good),
+            Outcome::Easy => Some(Grade::Easy),
+            Outcome::Seen | Outcome::Suspended | Outcome::Buried => None,
+        }
+    }
+}
+
+/// How many cards of a session ended with each outcome.
+#[derive(Default, serde::Serialize)]
+pub struct OutcomeCounts {
+    again: u32,
+    hard: u32,
+    good: u32,
+    easy: u32,
+    seen: u32,
+    suspended: u32,
+    buried: u32,
+}
+
+#[derive(serde::Serialize)]
+pub struct StudySession {
+    id: i64,
+    collection_id: i64,
+    sub_collection_id: Option<i64>,
+    mode: StudyMode,
+    seed: i64,
+    /// Cards in the session, including answered ones.
+    total: u32,
+    /// Cards answered so far.
+    answered: u32,
+    outcomes: OutcomeCounts,
+    started_at: String,
+    updated_at: String,
+    ended_at: Option<String>,
+}
+
+fn load_session(conn: &rusqlite::Connection, id: i64) -> Result<StudySession, AppError> {
+    let mut session = conn
+        .query_row(
+            "SELECT id, collection_id, sub_collection_id, mode, seed, started_at, updated_at, ended_at
+             FROM study_sessions WHERE id = ?1",
+            rusqlite::params![id],
+            |row| {
+                Ok(StudySession {
+                    id: row.get(0)?,
+                    collection_id: row.get(1)?,
+                    sub_collection_id: row.get(2)?,
+                    mode: StudyMode::parse(&row.get::<_, String>(3)?),
+                    seed: row.get(4)?,
+                    total: 0,
+                    answered: 0,
+                    outcomes: OutcomeCounts::default(),
+                    started_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    ended_at: row.get(7)?,
+                })
+            },
+        )
+        .map_err(|_| AppError::not_found("Study session not found"))?;
+    let mut stmt = conn.prepare("SELECT outcome, COUNT(*) FROM study_session_cards WHERE session_id = ?1 GROUP BY outcome")?;
+    let rows = stmt.query_map(rusqlite::params![id], |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, u32>(1)?)))?;
+    for row in rows {
+        let (outcome, count) = row?;
+        session.total += count;
+        let Some(outcome) = outcome else { continue };
+        let slot = match Outcome::parse(&outcome) {
+            Outcome::Again => &mut session.outcomes.again,
+            Outcome::Hard => &mut session.outcomes.hard,
+            Outcome::Good => &mut session.outcomes.good,
+            Outcome::Easy => &mut session.outcomes.easy,
+            Outcome::Seen => &mut session.outcomes.seen,
+            Outcome::Suspended => &mut session.outcomes.suspended,
+            Outcome::Buried => &mut session.outcomes.buried,
+        };
+        *slot += count;
+        session.answered += count;
+    }
+    Ok(session)
+}
+
+#[derive(serde::Serialize)]
+pub struct SessionCard {
+    #[serde(flatten)]
+    prompt: StudyPrompt,
+    outcome: Option<Outcome>,
+}
+
+/// A session with its cards in order, for studying.
+#[derive(serde::Serialize)]
+pub struct StudySessionState {
+    session: StudySession,
+    cards: Vec<SessionCard>,
+    /// Index in `cards` to continue from; equal to the number of cards when the pass is complete.
+    current_index: usize,
+}
+
+fn session_state(conn: &rusqlite::Connection, id: i64) -> Result<StudySessionState, AppError> {
+    let session = load_session(conn, id)?;
+    let position: i64 = conn.query_row("SELECT position FROM study_sessions WHERE id = ?1", rusqlite::params![id], |row| row.get(0))?;
+    let mut prompts: std::collections::HashMap<i64, StudyPrompt> =
+        card_types::study_prompts(conn, session.collection_id, session.sub_collection_id)?
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+    let mut stmt = conn.prepare("SELECT position, prompt_id, outcome FROM study_session_cards WHERE session_id = ?1 ORDER BY position")?;
+    let rows: Vec<(i64, i64, Option<String>)> = stmt
+        .query_map(rusqlite::params![id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<_, _>>()?;
+    let mut cards = Vec::new();
+    let mut current_index = None;
+    for (card_position, prompt_id, outcome) in rows {
+        // Cards suspended, buried or moved out of the sub-collection since the session started are left out.
+        let Some(prompt) = prompts.remove(&prompt_id) else { continue };
+        if current_index.is_none() && card_position >= position {
+            current_index = Some(cards.len());
+        }
+        cards.push(SessionCard { prompt, outcome: outcome.as_deref().map(Outcome::parse) });
+    }
+    let current_index = current_index.unwrap_or(cards.len());
+    Ok(StudySessionState { session, cards, current_index })
+}
+
+/// Start studying the collection, or the sub-collection and everything below it, in the given order.
+/// Without a seed a random one is picked; it is returned so the order can be repeated. Any open
+/// session on the same cards is ended.
+#[tauri::command]
+pub fn start_study_session(
+    app: tauri::AppHandle,
+    collection_id: i64,
+    sub_collection_id: Option<i64>,
+    mode: Option<StudyMode>,
+    seed: Option<i64>,
+) -> Result<StudySessionState, AppError> {
+    let mode = mode.unwrap_or_default();
+    let seed = seed.unwrap_or_else(new_seed);
+    let mut conn = crate::open_db(&app)?;
+    if let Some(sub_id) = sub_collection_id {
+        crate::check_sub_collection(&conn, collection_id, sub_id)?;
+    }
+    let prompts = card_types::study_prompts(&conn, collection_id, sub_collection_id)?;
+    let prompts = options::todays_prompts(&conn, collection_id, sub_collection_id, prompts)?;
+    let prompts: Vec<i64> = order_prompts(prompts, mode, seed)
+        .into_iter()
+        .map(|p| p.id)
+        .collect();
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "UPDATE study_sessions SET ended_at = datetime('now')
+         WHERE collection_id = ?1 AND sub_collection_id IS ?2 AND ended_at IS NULL",
+        rusqlite::params![collection_id, sub_collection_id],
+    )?;
+    tx.execute(
+        "INSERT INTO study_sessions (collection_id, sub_collection_id, mode, seed) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![collection_id, sub_collection_id, mode.as_str(), seed],
+    )?;
+    let id = tx.last_insert_rowid();
+    {
+        let mut insert = tx.prepare("INSERT INTO study_session_cards (session_id, position, prompt_id) VALUES (?1, ?2, ?3)")?;
+        for (position, prompt_id) in prompts.iter().enumerate() {
+            insert.execute(rusqlite::params![id, position as i64, prompt_id])?;
+        }
+    }
+    tx.commit()?;
+    session_state(&conn, id)
+}
+
+/// The prompts to study in the collection, or the sub-collection and everything below it, in the given
+/// order (creation order by default). A shuffle with the same seed comes out the same while the cards
+/// don't change.
+#[tauri::command]
+pub fn get_study_queue(
+    app: tauri::AppHandle,
+    collection_id: i64,
+    sub_collection_id: Option<i64>,
+    mode: Option<StudyMode>,
+    seed: Option<i64>,
+) -> Result<Vec<StudyPrompt>, AppError> {
+    let conn = crate::open_db(&app)?;
+    if let Some(sub_id) = sub_collection_id {
+        crate::check_sub_collection(&conn, collection_id, sub_id)?;
+    }
+    let prompts = card_types::study_prompts(&conn, collection_id, sub_collection_id)?;
+    let prompts = options::todays_prompts(&conn, collection_id, sub_collection_id, prompts)?;
+    Ok(order_prompts(prompts, mode.unwrap_or_default(), seed.unwrap_or_else(new_seed)))
+}
+
+/// A session with its cards and where to continue, for resuming it.
+#[tauri::command]
+pub fn get_study_session(app: tauri::AppHandle, session_id: i64) -> Result<StudySessionState, AppError> {
+    let conn = crate::open_db(&app)?;
+    session_state(&conn, session_id)
+}
+
+/// Sessions of the collection not yet ended, most recently used first.
+#[tauri::command]
+pub fn list_study_sessions(app: tauri::AppHandle, collection_id: i64) -> Result<Vec<StudySession>, AppError> {
+    let conn = crate::open_db(&app)?;
+    let ids: Vec<i64> = conn
+        .prepare("SELECT id FROM study_sessions WHERE collection_id = ?1 AND ended_at IS NULL ORDER BY updated_at DESC, id DESC")?
+        .query_map(rusqlite::params![collection_id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    ids.into_iter().map(|id| load_session(&conn, id)).collect()
+}
+
+/// Record the outcome for a card of the session and continue after it; answering a card again after
+/// going back replaces its outcome without moving the session back. The first grade given to a card
+/// in the session is also recorded as a review of the prompt, so a regrade doesn't schedule it twice;
+/// `suspended` and `buried` suspend or bury the card.
+#[tauri::command]
+pub fn advance_study_session(app: tauri::AppHandle, session_id: i64, prompt_id: i64, outcome: Outcome) -> Result<StudySession, AppError> {
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    advance(&tx, session_id, prompt_id, outcome)?;
+    tx.commit()?;
+    load_session(&conn, session_id)
+}
+
+fn advance(tx: &rusqlite::Connection, session_id: i64, prompt_id: i64, outcome: Outcome) -> Result<(), AppError> {
+    let ended: Option<String> = tx
+        .query_row("SELECT ended_at FROM study_sessions WHERE id = ?1", rusqlite::params![session_id], |row| row.get(0))
+        .map_err(|_| AppError::not_found("Study session not found"))?;
+    if ended.is_some() {
+        return Err(AppError::validation("sessionId", "This study session has ended"));
+    }
+    let (position, previous): (i64, Option<String>) = tx
+        .query_row(
+            "SELECT position, outcome FROM study_session_cards WHERE session_id = ?1 AND prompt_id = ?2",
+            rusqlite::params![session_id, prompt_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| AppError::not_found("Card is not part of this study session"))?;
+    tx.execute(
+        "UPDATE study_session_cards SET outcome = ?1, answered_at = datetime('now') WHERE session_id = ?2 AND position = ?3",
+        rusqlite::params![outcome.as_str(), session_id, position],
+    )?;
+    tx.execute(
+        "UPDATE study_sessions SET position = MAX(position, ?1), updated_at = datetime('now') WHERE id = ?2",
+        rusqlite::params![position + 1, session_id],
+    )?;
+    let graded_before = previous.is_some_and(|previous| Outcome::parse(&previous).grade().is_some());
+    if let Some(grade) = outcome.grade().filter(|_| !graded_before) {
+        schedule::review(tx, prompt_id, grade)?;
+    }
+    let column = match outcome {
+        Outcome::Suspended => Some("suspended_at"),
+        Outcome::Buried => Some("buried_at"),
+        _ => None,
+    };
+    if let Some(column) = column {
+        tx.execute(
+            &format!("UPDATE cards SET {column} = datetime('now') WHERE id = (SELECT card_id FROM card_prompts WHERE id = ?1)"),
+            rusqlite::params![prompt_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// End a session; returns its final counts. Ending an ended session changes nothing.
+#[tauri::command]
+pub fn end_study_session(app: tauri::AppHandle, session_id: i64) -> Result<StudySession, AppError> {
+    let conn = crate::open_db(&app)?;
+    conn.execute(
+        "UPDATE study_sessions SET ended_at = datetime('now') WHERE id = ?1 AND ended_at IS NULL",
+        rusqlite::params![session_id],
+    )?;
+    load_session(&conn, session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cards 1 and 3 in sub-collection 1, cards 2 and 4 in sub-collection 2, one prompt each.
+    fn db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A');
+             INSERT INTO sub_collections (id, name, collection_id) VALUES (1, '- None -', 1), (2, 'Ch1', 1);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES
+                 (1, 'Q1', 'A1', 1, 1), (2, 'Q2', 'A2', 1, 2), (3, 'Q3', 'A3', 1, 1), (4, 'Q4', 'A4', 1, 2);",
+        )
+        .unwrap();
+        card_types::fill_missing_prompts(&conn).unwrap();
+        conn
+    }
+
+    fn prompt_ids(prompts: &[StudyPrompt]) -> Vec<i64> {
+        prompts.iter().map(|p| p.id).collect()
+    }
+
+    #[test]
+    fn shuffle_is_reproducible_from_the_seed() {
+        let shuffled = |seed| {
+            let mut items: Vec<u32> = (0..20).collect();
+            seeded_shuffle(&mut items, seed);
+            items
+        };
+        assert_eq!(shuffled(42), shuffled(42));
+        assert_ne!(shuffled(42), shuffled(43));
+        let mut sorted = shuffled(42);
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn interleaved_takes_sub_collections_in_turn() {
+        let conn = db();
+        let prompts = card_types::study_prompts(&conn, 1, None).unwrap();
+        let created = prompt_ids(&prompts);
+        let interleaved = prompt_ids(&order_prompts(prompts, StudyMode::Interleaved, 0));
+        assert_eq!(interleaved, vec![created[0], created[1], created[2], created[3]]);
+
+        conn.execute("UPDATE cards SET sub_collection_id = 1 WHERE id = 2", []).unwrap();
+        let prompts = card_types::study_prompts(&conn, 1, None).unwrap();
+        let interleaved = prompt_ids(&order_prompts(prompts, StudyMode::Interleaved, 0));
+        assert_eq!(interleaved, vec![created[0], created[3], created[1], created[2]]);
+    }
+
+    #[test]
+    fn hardest_first_puts_the_highest_lapse_rate_first() {
+        let conn = db();
+        conn.execute_batch(
+            "UPDATE card_prompts SET reps = 4, lapses = 1 WHERE card_id = 1;
+             UPDATE card_prompts SET reps = 2, lapses = 2 WHERE card_id = 3;",
+        )
+        .unwrap();
+        let prompts = card_types::study_prompts(&conn, 1, None).unwrap();
+        let created = prompt_ids(&prompts);
+        let ordered = prompt_ids(&order_prompts(prompts, StudyMode::HardestFirst, 0));
+        assert_eq!(ordered, vec![created[2], created[0], created[1], created[3]]);
+    }
+
+    #[test]
+    fn oldest_reviewed_starts_with_never_reviewed() {
+        let conn = db();
+        conn.execute_batch(
+            "UPDATE card_prompts SET last_reviewed_at = '2025-02-01 00:00:00' WHERE card_id = 1;
+             UPDATE card_prompts SET last_reviewed_at = '2025-01-01 00:00:00' WHERE card_id = 2;",
+        )
+        .unwrap();
+        let prompts = card_types::study_prompts(&conn, 1, None).unwrap();
+        let created = prompt_ids(&prompts);
+        let ordered = prompt_ids(&order_prompts(prompts, StudyMode::OldestReviewed, 0));
+        assert_eq!(ordered, vec![created[2], created[3], created[1], created[0]]);
+    }
+
+    #[test]
+    fn modes_keep_the_same_order_for_the_same_seed() {
+        let conn = db();
+        for mode in [
+            StudyMode::Created,
+            StudyMode::Shuffle,
+            StudyMode::HardestFirst,
+            StudyMode::OldestReviewed,
+            StudyMode::Interleaved,
+        ] {
+            assert!(StudyMode::parse(mode.as_str()) == mode);
+            let order = || prompt_ids(&order_prompts(card_types::study_prompts(&conn, 1, None).unwrap(), mode, 7));
+            assert_eq!(order(), order());
+        }
+        let created = prompt_ids(&card_types::study_prompts(&conn, 1, None).unwrap());
+        assert_eq!(prompt_ids(&order_prompts(card_types::study_prompts(&conn, 1, None).unwrap(), StudyMode::Created, 7)), created);
+    }
+
+    #[test]
+    fn outcomes_round_trip() {
+        for outcome in [
+            Outcome::Again,
+            Outcome::Hard,
+            Outcome::Good,
+            Outcome::Easy,
+            Outcome::Seen,
+            Outcome::Suspended,
+            Outcome::Buried,
+        ] {
+            assert!(Outcome::parse(outcome.as_str()) == outcome);
+        }
+        assert!(Outcome::parse("unknown") == Outcome::Seen);
+    }
+
+    #[test]
+    fn regrading_a_card_reviews_it_once() {
+        let conn = db();
+        let prompt: i64 = conn.query_row("SELECT id FROM card_prompts WHERE card_id = 1", [], |r| r.get(0)).unwrap();
+        conn.execute("INSERT INTO study_sessions (id, collection_id, mode, seed) VALUES (1, 1, 'created', 0)", []).unwrap();
+        conn.execute("INSERT INTO study_session_cards (session_id, position, prompt_id) VALUES (1, 0, ?1)", [prompt]).unwrap();
+        let reviews = || -> (i64, i64) {
+            conn.query_row(
+                "SELECT reps, (SELECT COUNT(*) FROM review_log WHERE prompt_id = ?1) FROM card_prompts WHERE id = ?1",
+                [prompt],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap()
+        };
+
+        advance(&conn, 1, prompt, Outcome::Seen).unwrap();
+        assert_eq!(reviews(), (0, 0));
+        advance(&conn, 1, prompt, Outcome::Again).unwrap();
+        assert_eq!(reviews(), (1, 1));
+        advance(&conn, 1, prompt, Outcome::Good).unwrap();
+        assert_eq!(reviews(), (1, 1));
+
+        let session = load_session(&conn, 1).unwrap();
+        assert_eq!((session.answered, session.outcomes.good, session.outcomes.again), (1, 1, 0));
+    }
+}
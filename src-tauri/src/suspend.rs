@@ -0,0 +1,166 @@
+//! Suspending and burying cards.
+//!
+//! A suspended card is left out of study until it is restored by hand. A buried card is left out
+//! for the rest of the study day only: at the day rollover it comes back by itself. Both are
+//! timestamps on the card (`suspended_at`, `buried_at`), so the lists can show since when. Buried
+//! cards from an earlier day are unburied at startup and whenever cards or study prompts are loaded.
+
+use crate::error::AppError;
+use crate::journal;
+use crate::options;
+
+pub(crate) fn init_suspend_columns(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    crate::add_column_if_missing(conn, "cards", "suspended_at", "TEXT")?;
+    crate::add_column_if_missing(conn, "cards", "buried_at", "TEXT")?;
+    // Databases from before suspending had a `skipped` flag with the same meaning.
+    let has_skipped: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('cards') WHERE name = 'skipped'",
+        [],
+        |row| row.get(0),
+    )?;
+    if has_skipped {
+        conn.execute_batch(
+            "BEGIN;
+             UPDATE cards SET suspended_at = datetime('now') WHERE skipped = 1 AND suspended_at IS NULL;
+             ALTER TABLE cards DROP COLUMN skipped;
+             COMMIT;",
+        )?;
+    }
+    Ok(())
+}
+
+/// Unbury cards buried before the current study day began, by the rollover hour of the options in
+/// effect for each card's sub-collection.
+pub(crate) fn unbury_expired(conn: &rusqlite::Connection) -> Result<u32, AppError> {
+    let places: Vec<(i64, i64)> = conn
+        .prepare("SELECT DISTINCT collection_id, sub_collection_id FROM cards WHERE buried_at IS NOT NULL")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    let mut unburied = 0;
+    for (collection_id, sub_collection_id) in places {
+        let day_start = options::day_start_sql(options::effective(conn, collection_id, Some(sub_collection_id))?.values.rollover_hour);
+        unburied += conn.execute(
+            &format!("UPDATE cards SET buried_at = NULL WHERE collection_id = ?1 AND sub_collection_id = ?2 AND buried_at < {day_start}"),
+            rusqlite::params![collection_id, sub_collection_id],
+        )?;
+    }
+    Ok(unburied as u32)
+}
+
+#[derive(Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardState {
+    Suspended,
+    Buried,
+}
+
+impl CardState {
+    fn column(self) -> &'static str {
+        match self {
+            CardState::Suspended => "suspended_at",
+            CardState::Buried => "buried_at",
+        }
+    }
+}
+
+/// Set or clear `column` on the cards selected by `card_ids`, as one undo entry named `label`.
+fn set_state(conn: &mut rusqlite::Connection, label: String, card_ids: &str, params: &[&dyn rusqlite::ToSql], column: &str, on: bool) -> Result<u32, AppError> {
+    let tx = conn.transaction()?;
+    let mut op = journal::Operation::new(label);
+    let condition = format!("id IN ({card_ids}) AND {column} IS {}", if on { "NULL" } else { "NOT NULL" });
+    op.track(&tx, "cards", &condition, params)?;
+    let value = if on { "datetime('now')" } else { "NULL" };
+    let changed = tx.execute(&format!("UPDATE cards SET {column} = {value} WHERE {condition}"), params)?;
+    if changed > 0 {
+        op.commit(&tx)?;
+    }
+    tx.commit()?;
+    Ok(changed as u32)
+}
+
+fn card_label(conn: &rusqlite::Connection, card_id: i64) -> Result<String, AppError> {
+    let question: String = conn
+        .query_row(
+            "SELECT question FROM cards WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![card_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::not_found("Card not found"))?;
+    Ok(crate::truncate_label(&question))
+}
+
+/// Suspend a card, leaving it out of study until it is unsuspended, or unsuspend it.
+#[tauri::command]
+pub fn set_card_suspended(app: tauri::AppHandle, card_id: i64, suspended: bool) -> Result<(), AppError> {
+    let mut conn = crate::open_db(&app)?;
+    let label = card_label(&conn, card_id)?;
+    let verb = if suspended { "Suspend" } else { "Unsuspend" };
+    set_state(&mut conn, format!("{verb} card \"{label}\""), "?1", &[&card_id], CardState::Suspended.column(), suspended)?;
+    Ok(())
+}
+
+/// Bury a card, leaving it out of study until the next study day, or unbury it now.
+#[tauri::command]
+pub fn set_card_buried(app: tauri::AppHandle, card_id: i64, buried: bool) -> Result<(), AppError> {
+    let mut conn = crate::open_db(&app)?;
+    let label = card_label(&conn, card_id)?;
+    let verb = if buried { "Bury" } else { "Unbury" };
+    set_state(&mut conn, format!("{verb} card \"{label}\""), "?1", &[&card_id], CardState::Buried.column(), buried)?;
+    Ok(())
+}
+
+/// Unsuspend or unbury every card of the collection, or of the sub-collection and everything below it.
+/// Returns how many cards came back.
+#[tauri::command]
+pub fn clear_card_state(
+    app: tauri::AppHandle,
+    collection_id: i64,
+    sub_collection_id: Option<i64>,
+    state: CardState,
+) -> Result<u32, AppError> {
+    let mut conn = crate::open_db(&app)?;
+    if let Some(sub_id) = sub_collection_id {
+        crate::check_sub_collection(&conn, collection_id, sub_id)?;
+    }
+    let label = match state {
+        CardState::Suspended => "Unsuspend cards",
+        CardState::Buried => "Unbury cards",
+    };
+    let card_ids = format!(
+        "SELECT id FROM cards WHERE collection_id = ?1 AND deleted_at IS NULL AND (?2 IS NULL OR sub_collection_id IN {})",
+        crate::hierarchy::subtree("?2")
+    );
+    set_state(&mut conn, label.to_string(), &card_ids, &[&collection_id, &sub_collection_id], state.column(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buried_cards_come_back_at_the_rollover_of_their_options() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A');
+             INSERT INTO collection_options (id, name, rollover_hour) VALUES (2, 'Night', 23);
+             INSERT INTO sub_collections (id, name, collection_id, options_id) VALUES (1, '- None -', 1, NULL), (2, 'Late', 1, 2);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES
+                 (1, 'Q1', 'A1', 1, 2), (2, 'Q2', 'A2', 1, 2), (3, 'Q3', 'A3', 1, 1);",
+        )
+        .unwrap();
+        let day_start = options::day_start_sql(23);
+        conn.execute(&format!("UPDATE cards SET buried_at = datetime({day_start}, '-1 seconds') WHERE id = 1"), []).unwrap();
+        conn.execute(&format!("UPDATE cards SET buried_at = {day_start} WHERE id = 2"), []).unwrap();
+        conn.execute("UPDATE cards SET buried_at = datetime('now') WHERE id = 3", []).unwrap();
+        assert_eq!(unbury_expired(&conn).unwrap(), 1);
+        let buried: Vec<i64> = conn
+            .prepare("SELECT id FROM cards WHERE buried_at IS NOT NULL ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(buried, vec![2, 3]);
+    }
+}
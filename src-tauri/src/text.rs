@@ -0,0 +1,49 @@
+//! Text normalization shared by duplicate detection and answer checking.
+
+use std::sync::OnceLock;
+use unicode_normalization::UnicodeNormalization;
+
+/// Remove diacritics: decompose (NFD) and drop the nonspacing marks, so precomposed "é" and
+/// "e\u{301}" as some keyboards type it both become "e".
+fn strip_marks(text: &str) -> String {
+    static MARKS: OnceLock<regex::Regex> = OnceLock::new();
+    let marks = MARKS.get_or_init(|| regex::Regex::new(r"\p{Mn}+").expect("valid mark pattern"));
+    marks.replace_all(&text.nfd().collect::<String>(), "").into_owned()
+}
+
+/// Lowercase `c` and spell it without diacritics, e.g. 'É' → "e".
+pub(crate) fn fold_char(c: char) -> String {
+    strip_marks(c.encode_utf8(&mut [0; 4])).to_lowercase()
+}
+
+/// Drop diacritics and apostrophes, lowercase, replace other punctuation with spaces and collapse runs
+/// of whitespace, so "Qu'est-ce  que C'EST?" and "quest ce que cest" compare equal.
+pub(crate) fn normalize(text: &str) -> String {
+    let cleaned: String = strip_marks(text)
+        .to_lowercase()
+        .chars()
+        .filter(|c| !matches!(c, '\'' | '’'))
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composed_and_decomposed_accents_normalize_alike() {
+        assert_eq!(normalize("Résumé"), "resume");
+        assert_eq!(normalize("re\u{301}sume\u{301}"), "resume");
+        assert_eq!(normalize("Qu'est-ce  que C'EST?"), "quest ce que cest");
+        assert_eq!(normalize("  Ærø, Łódź! "), "ærø łodz");
+    }
+
+    #[test]
+    fn fold_char_drops_the_accent() {
+        assert_eq!(fold_char('É'), "e");
+        assert_eq!(fold_char('\u{301}'), "");
+        assert_eq!(fold_char('x'), "x");
+    }
+}
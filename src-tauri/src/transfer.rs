@@ -0,0 +1,566 @@
+//! Moving and copying cards between collections and sub-collections.
+//!
+//! A card is unique by question and answer within its sub-collection, so a transfer can run into a
+//! card that is already in the target. The caller picks what happens then with a `ConflictPolicy`;
+//! every card in a request is handled in one transaction and one undo entry.
+
+use crate::card_types;
+use crate::error::AppError;
+use crate::hierarchy;
+use crate::journal;
+
+/// What to do with a card whose question and answer already exist in the target sub-collection.
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Leave the card where it is (or do not copy it).
+    #[default]
+    Skip,
+    /// Transfer it with " (2)", " (3)", … appended to the question.
+    Rename,
+    /// Keep the existing card, filling in its hint and source from this one if it has none. A moved
+    /// card is then put in the trash.
+    Merge,
+}
+
+#[derive(Default, serde::Serialize)]
+pub struct TransferReport {
+    /// The cards in the target, in request order: moved cards keep their id, copies get a new one and
+    /// merged cards are the existing card they were merged into.
+    card_ids: Vec<i64>,
+    renamed: u32,
+    merged: u32,
+    /// Cards left alone because of a conflict and the `skip` policy.
+    skipped: Vec<i64>,
+}
+
+struct SourceCard {
+    collection_id: i64,
+    sub_collection_id: i64,
+    /// Path of the sub-collection, e.g. "Chapter 1/Section 2".
+    sub_collection_path: String,
+    question: String,
+    answer: String,
+}
+
+fn source_card(conn: &rusqlite::Connection, id: i64) -> Result<SourceCard, AppError> {
+    let (collection_id, sub_collection_id, question, answer) = conn
+        .query_row(
+            "SELECT collection_id, sub_collection_id, question, answer FROM cards WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|_| AppError::not_found(format!("Card {id} not found")))?;
+    let sub_collection_path = hierarchy::sub_collection_path(conn, sub_collection_id)?;
+    Ok(SourceCard { collection_id, sub_collection_id, sub_collection_path, question, answer })
+}
+
+/// The live card with this question and answer in the sub-collection, other than `except`.
+fn find_live_card(
+    conn: &rusqlite::Connection,
+    sub_collection_id: i64,
+    question: &str,
+    answer: &str,
+    except: i64,
+) -> Result<Option<i64>, AppError> {
+    match conn.query_row(
+        "SELECT id FROM cards
+         WHERE sub_collection_id = ?1 AND question = ?2 AND answer = ?3 AND deleted_at IS NULL AND id != ?4",
+        rusqlite::params![sub_collection_id, question, answer, except],
+        |row| row.get(0),
+    ) {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The question with the first free " (n)" suffix in the sub-collection.
+fn unique_question(conn: &rusqlite::Connection, sub_collection_id: i64, question: &str, answer: &str) -> Result<String, AppError> {
+    let mut n = 2;
+    loop {
+        let candidate = format!("{question} ({n})");
+        if find_live_card(conn, sub_collection_id, &candidate, answer, 0)?.is_none() {
+            return Ok(candidate);
+        }
+        n += 1;
+    }
+}
+
+/// Fold `card_id` into `existing`: fill the existing card's empty hint, missing source and leech mark from it, and
+/// take its review state for prompts the existing card has not been reviewed on. Used wherever two
+/// cards become one, so anything else recorded per card is combined here too.
+pub(crate) fn merge_into(conn: &rusqlite::Connection, op: &mut journal::Operation, card_id: i64, existing: i64) -> Result<(), AppError> {
+    op.track(conn, "cards", "id = ?1", rusqlite::params![existing])?;
+    conn.execute(
+        "UPDATE cards SET
+             hint = CASE WHEN COALESCE(cards.hint, '') = '' THEN src.hint ELSE cards.hint END,
+             source_path = COALESCE(cards.source_path, src.source_path),
+             source_hash = COALESCE(cards.source_hash, src.source_hash),
+             source_page = COALESCE(cards.source_page, src.source_page),
+             generator = COALESCE(cards.generator, src.generator),
+             leech_at = COALESCE(cards.leech_at, src.leech_at)
+         FROM (SELECT hint, source_path, source_hash, source_page, generator, leech_at FROM cards WHERE id = ?1) AS src
+         WHERE cards.id = ?2",
+        rusqlite::params![card_id, existing],
+    )?;
+    card_types::fold_prompts(conn, op, card_id, existing)?;
+    Ok(())
+}
+
+/// Get or create the collection's sub-collection at `path`, journaling the levels created.
+fn sub_collection_at(conn: &rusqlite::Connection, op: &mut journal::Operation, collection_id: i64, path: &str) -> Result<i64, AppError> {
+    hierarchy::get_or_create_path(conn, collection_id, path, |id| op.track_inserted("sub_collections", id))
+}
+
+fn move_card(
+    conn: &rusqlite::Connection,
+    op: &mut journal::Operation,
+    id: i64,
+    collection_id: i64,
+    sub_collection_id: i64,
+    question: &str,
+) -> Result<(), AppError> {
+    // A note's link is per collection; a card leaving the collection is no longer that note's card.
+    op.track(conn, "note_cards", "card_id = ?1 AND collection_id != ?2", rusqlite::params![id, collection_id])?;
+    conn.execute(
+        "DELETE FROM note_cards WHERE card_id = ?1 AND collection_id != ?2",
+        rusqlite::params![id, collection_id],
+    )?;
+    op.track(conn, "cards", "id = ?1", rusqlite::params![id])?;
+    conn.execute(
+        "UPDATE cards SET collection_id = ?1, sub_collection_id = ?2, question = ?3 WHERE id = ?4",
+        rusqlite::params![collection_id, sub_collection_id, question, id],
+    )?;
+    card_types::sync_card_prompts(conn, Some(op), id)?;
+    Ok(())
+}
+
+fn transfer(
+    app: &tauri::AppHandle,
+    card_ids: &[i64],
+    target_collection_id: i64,
+    target_sub_collection_id: Option<i64>,
+    on_conflict: ConflictPolicy,
+    copy: bool,
+) -> Result<TransferReport, AppError> {
+    let mut conn = crate::open_db(app)?;
+    let tx = conn.transaction()?;
+    let report = transfer_cards(&tx, card_ids, target_collection_id, target_sub_collection_id, on_conflict, copy)?;
+    tx.commit()?;
+    Ok(report)
+}
+
+fn transfer_cards(
+    tx: &rusqlite::Connection,
+    card_ids: &[i64],
+    target_collection_id: i64,
+    target_sub_collection_id: Option<i64>,
+    on_conflict: ConflictPolicy,
+    copy: bool,
+) -> Result<TransferReport, AppError> {
+    let target_name: String = tx
+        .query_row(
+            "SELECT name FROM collections WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![target_collection_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::not_found("Collection not found"))?;
+    if let Some(sub_id) = target_sub_collection_id {
+        crate::check_sub_collection(tx, target_collection_id, sub_id)?;
+    }
+    let verb = if copy { "Copy" } else { "Move" };
+    let noun = if card_ids.len() == 1 { "card" } else { "cards" };
+    let mut op = journal::Operation::new(format!("{verb} {} {noun} to \"{target_name}\"", card_ids.len()));
+    let mut report = TransferReport::default();
+
+    for &id in card_ids {
+        let card = source_card(tx, id)?;
+        let sub_id = match target_sub_collection_id {
+            Some(sub_id) => sub_id,
+            None if card.collection_id == target_collection_id => card.sub_collection_id,
+            None => sub_collection_at(tx, &mut op, target_collection_id, &card.sub_collection_path)?,
+        };
+        if !copy && sub_id == card.sub_collection_id {
+            report.card_ids.push(id);
+            continue;
+        }
+
+        // A copy into the card's own sub-collection conflicts with the card itself.
+        let except = if copy { 0 } else { id };
+        let mut question = card.question.clone();
+        if let Some(existing) = find_live_card(tx, sub_id, &card.question, &card.answer, except)? {
+            match on_conflict {
+                ConflictPolicy::Skip => {
+                    report.skipped.push(id);
+                    continue;
+                }
+                ConflictPolicy::Rename => {
+                    question = unique_question(tx, sub_id, &card.question, &card.answer)?;
+                    report.renamed += 1;
+                }
+                ConflictPolicy::Merge => {
+                    merge_into(tx, &mut op, id, existing)?;
+                    if !copy {
+                        op.track(tx, "cards", "id = ?1", rusqlite::params![id])?;
+                        tx.execute("UPDATE cards SET deleted_at = datetime('now') WHERE id = ?1", rusqlite::params![id])?;
+                    }
+                    report.merged += 1;
+                    report.card_ids.push(existing);
+                    continue;
+                }
+            }
+        }
+
+        if copy {
+            tx.execute(
+                "INSERT INTO cards (question, answer, hint, suspended_at, buried_at, collection_id, sub_collection_id, source_path, source_hash, generator, source_page, card_type, choices, leech_at)
+                 SELECT ?1, answer, hint, suspended_at, buried_at, ?2, ?3, source_path, source_hash, generator, source_page, card_type, choices, leech_at FROM cards WHERE id = ?4",
+                rusqlite::params![question, target_collection_id, sub_id, id],
+            )?;
+            let new_id = tx.last_insert_rowid();
+            op.track_inserted("cards", new_id);
+            card_types::sync_card_prompts(tx, Some(&mut op), new_id)?;
+            report.card_ids.push(new_id);
+        } else {
+            move_card(tx, &mut op, id, target_collection_id, sub_id, &question)?;
+            report.card_ids.push(id);
+        }
+    }
+
+    op.commit(tx)?;
+    Ok(report)
+}
+
+/// Move cards to another collection or sub-collection. Without `target_sub_collection_id` a card keeps
+/// its sub-collection when staying in its collection, and otherwise goes to the sub-collection with the
+/// same path in the target, which is created if needed.
+#[tauri::command]
+pub fn move_cards(
+    app: tauri::AppHandle,
+    card_ids: Vec<i64>,
+    target_collection_id: i64,
+    target_sub_collection_id: Option<i64>,
+    on_conflict: Option<ConflictPolicy>,
+) -> Result<TransferReport, AppError> {
+    transfer(&app, &card_ids, target_collection_id, target_sub_collection_id, on_conflict.unwrap_or_default(), false)
+}
+
+/// Copy cards to a collection or sub-collection, choosing the target sub-collection like `move_cards`.
+/// Copies keep the original's card type, hint, suspended and buried state, leech mark and source.
+#[tauri::command]
+pub fn copy_cards(
+    app: tauri::AppHandle,
+    card_ids: Vec<i64>,
+    target_collection_id: i64,
+    target_sub_collection_id: Option<i64>,
+    on_conflict: Option<ConflictPolicy>,
+) -> Result<TransferReport, AppError> {
+    transfer(&app, &card_ids, target_collection_id, target_sub_collection_id, on_conflict.unwrap_or_default(), true)
+}
+
+#[derive(serde::Serialize)]
+pub struct MergeReport {
+    /// Cards moved into the target collection.
+    moved: u32,
+    /// Cards folded into an identical card already in the target; they go to the trash with the source.
+    merged: u32,
+}
+
+/// Move every card of `source_id` into `target_id`, then put the source collection in the trash. Every
+/// sub-collection of the source, empty ones included, gets one with the same path in the target, and
+/// cards go to theirs. A card identical to one already there is merged into it like
+/// `ConflictPolicy::Merge`. Moved cards keep their id, so everything recorded against them comes along.
+#[tauri::command]
+pub fn merge_collections(app: tauri::AppHandle, source_id: i64, target_id: i64) -> Result<MergeReport, AppError> {
+    if source_id == target_id {
+        return Err(AppError::validation("targetId", "Cannot merge a collection into itself"));
+    }
+    crate::backup::snapshot(&app, "merge-collections")?;
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    let report = merge(&tx, source_id, target_id)?;
+    tx.commit()?;
+    Ok(report)
+}
+
+fn merge(conn: &rusqlite::Connection, source_id: i64, target_id: i64) -> Result<MergeReport, AppError> {
+    let name = |id: i64| {
+        conn.query_row(
+            "SELECT name FROM collections WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![id],
+            |row| row.get::<_, String>(0),
+        )
+        .map_err(|_| AppError::not_found(format!("Collection {id} not found")))
+    };
+    let (source_name, target_name) = (name(source_id)?, name(target_id)?);
+    let mut op = journal::Operation::new(format!("Merge \"{source_name}\" into \"{target_name}\""));
+
+    // Parents sort before their children, so each level exists before the ones below it.
+    for sub in hierarchy::sub_collection_paths(conn, source_id)? {
+        sub_collection_at(conn, &mut op, target_id, &sub.path)?;
+    }
+    let cards: Vec<i64> = conn
+        .prepare("SELECT id FROM cards WHERE collection_id = ?1 AND deleted_at IS NULL ORDER BY id")?
+        .query_map(rusqlite::params![source_id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let mut report = MergeReport { moved: 0, merged: 0 };
+    for id in cards {
+        let card = source_card(conn, id)?;
+        let sub_id = sub_collection_at(conn, &mut op, target_id, &card.sub_collection_path)?;
+        match find_live_card(conn, sub_id, &card.question, &card.answer, id)? {
+            Some(existing) => {
+                merge_into(conn, &mut op, id, existing)?;
+                report.merged += 1;
+            }
+            None => {
+                move_card(conn, &mut op, id, target_id, sub_id, &card.question)?;
+                report.moved += 1;
+            }
+        }
+    }
+
+    // Trash the source the way delete_collection does, taking the merged duplicates with it.
+    op.track(conn, "cards", "collection_id = ?1 AND deleted_at IS NULL", rusqlite::params![source_id])?;
+    op.track(conn, "collections", "id = ?1", rusqlite::params![source_id])?;
+    let now: String = conn.query_row("SELECT datetime('now')", [], |row| row.get(0))?;
+    conn.execute(
+        "UPDATE cards SET deleted_at = ?1 WHERE collection_id = ?2 AND deleted_at IS NULL",
+        rusqlite::params![now, source_id],
+    )?;
+    conn.execute("UPDATE collections SET deleted_at = ?1 WHERE id = ?2", rusqlite::params![now, source_id])?;
+    op.commit(conn)?;
+    Ok(report)
+}
+
+/// Create a collection named `new_name` from some of a collection's sub-collections. The chosen
+/// sub-collections move over whole, with everything below them and their cards (trashed ones
+/// included), and become top-level unless their parent moves too; two that would then share a name are
+/// refused. Cards in the default sub-collection, if chosen, go to the new collection's default. Card ids
+/// are kept, so everything recorded against the cards comes along.
+#[tauri::command]
+pub fn split_collection(
+    app: tauri::AppHandle,
+    collection_id: i64,
+    sub_collection_ids: Vec<i64>,
+    new_name: String,
+) -> Result<crate::StoredCollection, AppError> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err(AppError::validation("newName", "Collection name cannot be empty"));
+    }
+    if sub_collection_ids.is_empty() {
+        return Err(AppError::validation("subCollectionIds", "Choose at least one sub collection"));
+    }
+    crate::backup::snapshot(&app, "split-collection")?;
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    let collection = split(&tx, collection_id, &sub_collection_ids, new_name)?;
+    tx.commit()?;
+    Ok(collection)
+}
+
+fn split(tx: &rusqlite::Connection, collection_id: i64, sub_collection_ids: &[i64], new_name: &str) -> Result<crate::StoredCollection, AppError> {
+    let name: String = tx
+        .query_row(
+            "SELECT name FROM collections WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![collection_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| AppError::not_found("Collection not found"))?;
+    let mut moved: Vec<i64> = Vec::new();
+    for &sub_id in sub_collection_ids {
+        crate::check_sub_collection(tx, collection_id, sub_id)?;
+        let below = tx
+            .prepare(&format!("SELECT id FROM sub_collections WHERE id IN {}", hierarchy::subtree("?1")))?
+            .query_map(rusqlite::params![sub_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        for id in below {
+            if !moved.contains(&id) {
+                moved.push(id);
+            }
+        }
+    }
+    // Chosen sub-collections whose parent stays behind end up at the top level, where sibling names
+    // must differ.
+    let mut top: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    for &id in &moved {
+        let (leaf, parent_id): (String, Option<i64>) =
+            tx.query_row("SELECT name, parent_id FROM sub_collections WHERE id = ?1", rusqlite::params![id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?;
+        if parent_id.is_some_and(|parent| moved.contains(&parent)) || leaf == crate::NULL_SUB_COLLECTION_NAME {
+            continue;
+        }
+        if let Some(other) = top.insert(leaf.clone(), id) {
+            return Err(AppError::validation(
+                "subCollectionIds",
+                format!(
+                    "\"{}\" and \"{}\" would both become \"{leaf}\" in the new collection; choose one or rename it first",
+                    hierarchy::sub_collection_path(tx, other)?,
+                    hierarchy::sub_collection_path(tx, id)?
+                ),
+            ));
+        }
+    }
+    crate::trash::check_name_not_trashed(tx, new_name)?;
+    let mut op = journal::Operation::new(format!("Split \"{new_name}\" from \"{name}\""));
+
+    tx.execute("INSERT INTO collections (name) VALUES (?1)", rusqlite::params![new_name])?;
+    let new_id = tx.last_insert_rowid();
+    op.track_inserted("collections", new_id);
+    let new_null_sub = crate::get_or_create_sub_collection(tx, new_id, crate::NULL_SUB_COLLECTION_NAME)?;
+    op.track_inserted("sub_collections", new_null_sub);
+    let null_sub = crate::get_null_sub_collection_id(tx, collection_id)?;
+
+    let ids = serde_json::to_string(&moved)?;
+    let chosen = "sub_collection_id IN (SELECT value FROM json_each(?1))";
+    op.track(tx, "sub_collections", "id IN (SELECT value FROM json_each(?1)) AND id != ?2", rusqlite::params![ids, null_sub])?;
+    op.track(
+        tx,
+        "note_cards",
+        &format!("card_id IN (SELECT id FROM cards WHERE {chosen})"),
+        rusqlite::params![ids],
+    )?;
+    op.track(tx, "cards", chosen, rusqlite::params![ids])?;
+    tx.execute(
+        &format!("DELETE FROM note_cards WHERE card_id IN (SELECT id FROM cards WHERE {chosen})"),
+        rusqlite::params![ids],
+    )?;
+    tx.execute(
+        &format!(
+            "UPDATE cards SET collection_id = ?2,
+                 sub_collection_id = CASE WHEN sub_collection_id = ?3 THEN ?4 ELSE sub_collection_id END
+             WHERE {chosen}"
+        ),
+        rusqlite::params![ids, new_id, null_sub, new_null_sub],
+    )?;
+    tx.execute(
+        "UPDATE sub_collections SET collection_id = ?2,
+             parent_id = CASE WHEN parent_id IN (SELECT value FROM json_each(?1)) THEN parent_id END
+         WHERE id IN (SELECT value FROM json_each(?1)) AND id != ?3",
+        rusqlite::params![ids, new_id, null_sub],
+    )?;
+    op.commit(tx)?;
+    Ok(crate::StoredCollection { id: new_id, name: new_name.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name) VALUES (1, 'A'), (2, 'B');
+             INSERT INTO sub_collections (id, name, collection_id, parent_id) VALUES
+                 (1, '- None -', 1, NULL), (2, '- None -', 2, NULL), (3, 'Ch1', 1, NULL), (4, 'Sec', 1, 3),
+                 (5, 'Empty', 1, NULL), (6, 'Ch1', 2, NULL);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id) VALUES
+                 (1, 'Q', 'A', 1, 3), (2, 'R', 'B', 1, 1), (3, 'Q', 'A', 2, 6);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn paths(conn: &rusqlite::Connection, collection_id: i64) -> Vec<String> {
+        hierarchy::sub_collection_paths(conn, collection_id).unwrap().into_iter().map(|s| s.path).collect()
+    }
+
+    #[test]
+    fn merge_keeps_the_source_structure() {
+        let conn = db();
+        let report = merge(&conn, 1, 2).unwrap();
+        assert_eq!((report.moved, report.merged), (1, 1));
+        assert_eq!(paths(&conn, 2), vec!["- None -", "Ch1", "Ch1/Sec", "Empty"]);
+        let trashed: bool = conn.query_row("SELECT deleted_at IS NOT NULL FROM collections WHERE id = 1", [], |r| r.get(0)).unwrap();
+        assert!(trashed);
+    }
+
+    fn card(conn: &rusqlite::Connection, id: i64) -> (i64, i64, String, bool) {
+        conn.query_row(
+            "SELECT collection_id, sub_collection_id, question, deleted_at IS NOT NULL FROM cards WHERE id = ?1",
+            [id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn conflicts_follow_the_policy() {
+        let conn = db();
+        let report = transfer_cards(&conn, &[1], 2, None, ConflictPolicy::Skip, false).unwrap();
+        assert_eq!((report.skipped, report.card_ids), (vec![1], vec![]));
+        assert_eq!(card(&conn, 1), (1, 3, "Q".to_string(), false));
+
+        let report = transfer_cards(&conn, &[1], 2, None, ConflictPolicy::Rename, true).unwrap();
+        assert_eq!(report.renamed, 1);
+        assert_eq!(card(&conn, report.card_ids[0]), (2, 6, "Q (2)".to_string(), false));
+        assert_eq!(unique_question(&conn, 6, "Q", "A").unwrap(), "Q (3)");
+
+        conn.execute("UPDATE cards SET hint = 'h' WHERE id = 1", []).unwrap();
+        let report = transfer_cards(&conn, &[1], 2, None, ConflictPolicy::Merge, false).unwrap();
+        assert_eq!((report.merged, report.card_ids), (1, vec![3]));
+        assert!(card(&conn, 1).3);
+        let hint: String = conn.query_row("SELECT hint FROM cards WHERE id = 3", [], |r| r.get(0)).unwrap();
+        assert_eq!(hint, "h");
+    }
+
+    #[test]
+    fn moved_cards_keep_their_sub_collection_path() {
+        let conn = db();
+        conn.execute("UPDATE cards SET sub_collection_id = 4 WHERE id = 1", []).unwrap();
+        let report = transfer_cards(&conn, &[1, 2], 2, None, ConflictPolicy::Skip, false).unwrap();
+        assert_eq!(report.card_ids, vec![1, 2]);
+        assert_eq!(hierarchy::sub_collection_path(&conn, card(&conn, 1).1).unwrap(), "Ch1/Sec");
+        assert_eq!(card(&conn, 2).1, 2);
+        assert!(transfer_cards(&conn, &[1], 1, Some(6), ConflictPolicy::Skip, false).is_err());
+    }
+
+    #[test]
+    fn merging_a_card_keeps_its_review_history() {
+        let conn = db();
+        card_types::fill_missing_prompts(&conn).unwrap();
+        conn.execute("UPDATE card_prompts SET state = 'learning', step = 1, reps = 2 WHERE card_id = 1", []).unwrap();
+        conn.execute(
+            "INSERT INTO review_log (prompt_id, card_id, grade, state_before)
+             SELECT id, card_id, 'good', 'new' FROM card_prompts WHERE card_id = 1",
+            [],
+        )
+        .unwrap();
+        let mut op = journal::Operation::new("Merge");
+        merge_into(&conn, &mut op, 1, 3).unwrap();
+        let (prompt, step): (i64, i64) =
+            conn.query_row("SELECT id, step FROM card_prompts WHERE card_id = 3", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+        assert_eq!(step, 1);
+        let logged: (i64, i64) = conn.query_row("SELECT card_id, prompt_id FROM review_log", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+        assert_eq!(logged, (3, prompt));
+        conn.execute("DELETE FROM cards WHERE id = 1", []).unwrap();
+        let kept: i64 = conn.query_row("SELECT COUNT(*) FROM review_log", [], |r| r.get(0)).unwrap();
+        assert_eq!(kept, 1);
+    }
+
+    #[test]
+    fn split_keeps_the_chosen_trees() {
+        let conn = db();
+        let split_off = split(&conn, 1, &[3, 1], "C").unwrap();
+        assert_eq!(paths(&conn, split_off.id), vec!["- None -", "Ch1", "Ch1/Sec"]);
+        assert_eq!(paths(&conn, 1), vec!["- None -", "Empty"]);
+        assert_eq!(card(&conn, 1).0, split_off.id);
+        assert_eq!(card(&conn, 2).0, split_off.id);
+    }
+
+    #[test]
+    fn split_rejects_leaves_that_would_collide() {
+        let conn = db();
+        conn.execute("INSERT INTO sub_collections (id, name, collection_id, parent_id) VALUES (7, 'Sec', 1, 5)", []).unwrap();
+        let Err(err) = split(&conn, 1, &[4, 7], "C") else { panic!("expected the split to be rejected") };
+        assert_eq!(err.code(), "validation");
+        assert!(err.to_string().contains("\"Ch1/Sec\" and \"Empty/Sec\""));
+        let collections: i64 = conn.query_row("SELECT COUNT(*) FROM collections", [], |r| r.get(0)).unwrap();
+        assert_eq!(collections, 2);
+        // Taking a parent along keeps its child below it, so names only meet at the top level.
+        split(&conn, 1, &[3, 7], "C").unwrap();
+    }
+}
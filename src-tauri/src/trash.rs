@@ -0,0 +1,263 @@
+//! Trash for deleted cards and collections.
+//!
+//! `delete_card` and `delete_collection` only set `deleted_at`; everything that lists or exports cards
+//! filters on `deleted_at IS NULL`. Trashing a collection stamps the collection and its cards with the
+//! same time, so restoring it brings back exactly those cards and not ones trashed individually before.
+//! Items older than the retention period are purged at startup and whenever the trash is listed.
+//! Purging is final: it also clears the undo history.
+
+use crate::error::AppError;
+
+/// Default days items stay in the trash; 0 keeps them until purged by hand.
+const DEFAULT_RETENTION_DAYS: u32 = 30;
+const RETENTION_SETTING: &str = "trash_retention_days";
+
+pub(crate) fn init_trash_columns(conn: &rusqlite::Connection) -> Result<(), AppError> {
+    crate::add_column_if_missing(conn, "cards", "deleted_at", "TEXT")?;
+    crate::add_column_if_missing(conn, "collections", "deleted_at", "TEXT")?;
+    // Trashed cards must not block adding the same card again, so uniqueness only covers live cards.
+    conn.execute("DROP INDEX IF EXISTS cards_uniq_collection_sub_question_answer", [])?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS cards_uniq_live_collection_sub_question_answer
+         ON cards(collection_id, sub_collection_id, question, answer) WHERE deleted_at IS NULL",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Error for a new or renamed collection whose name is taken by one in the trash (names stay unique).
+pub(crate) fn check_name_not_trashed(conn: &rusqlite::Connection, name: &str) -> Result<(), AppError> {
+    let trashed: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM collections WHERE name = ?1 AND deleted_at IS NOT NULL)",
+            rusqlite::params![name],
+            |row| row.get(0),
+        )?;
+    if trashed {
+        return Err(AppError::Duplicate(format!("A collection named \"{name}\" is in the trash. Restore or purge it first.")));
+    }
+    Ok(())
+}
+
+fn retention_days(conn: &rusqlite::Connection) -> Result<u32, AppError> {
+    Ok(crate::get_setting(conn, RETENTION_SETTING)?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS))
+}
+
+/// Permanently delete cards and collections matching the conditions (collections with all their
+/// cards and sub-collections). Purging anything clears the undo history: its entries could otherwise
+/// bring the purged rows back.
+fn purge_where(conn: &rusqlite::Connection, card_condition: &str, collection_condition: &str) -> Result<u32, AppError> {
+    let mut purged = conn
+        .execute(&format!("DELETE FROM cards WHERE deleted_at IS NOT NULL AND ({card_condition})"), [])?;
+    let collections = format!("SELECT id FROM collections WHERE deleted_at IS NOT NULL AND ({collection_condition})");
+    purged += conn.execute(&format!("DELETE FROM cards WHERE collection_id IN ({collections})"), [])?;
+    // Sub-collections and note links go with the collection (ON DELETE CASCADE).
+    purged += conn.execute(&format!("DELETE FROM collections WHERE id IN ({collections})"), [])?;
+    if purged > 0 {
+        crate::journal::clear(conn)?;
+    }
+    Ok(purged as u32)
+}
+
+/// Purge items trashed longer ago than the retention period. Called at startup.
+pub(crate) fn purge_expired(conn: &rusqlite::Connection) -> Result<u32, AppError> {
+    let days = retention_days(conn)?;
+    if days == 0 {
+        return Ok(0);
+    }
+    let expired = format!("deleted_at <= datetime('now', '-{days} days')");
+    let tx = conn.unchecked_transaction()?;
+    let purged = purge_where(&tx, &expired, &expired)?;
+    tx.commit()?;
+    Ok(purged)
+}
+
+#[derive(serde::Serialize)]
+pub struct TrashedCollection {
+    id: i64,
+    name: String,
+    deleted_at: String,
+    card_count: u32,
+}
+
+#[derive(serde::Serialize)]
+pub struct TrashedCard {
+    id: i64,
+    collection_id: i64,
+    collection_name: String,
+    question: String,
+    answer: String,
+    deleted_at: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct Trash {
+    collections: Vec<TrashedCollection>,
+    /// Cards trashed on their own; cards of trashed collections are counted under the collection.
+    cards: Vec<TrashedCard>,
+    retention_days: u32,
+}
+
+#[tauri::command]
+pub fn list_trash(app: tauri::AppHandle) -> Result<Trash, AppError> {
+    let conn = crate::open_db(&app)?;
+    purge_expired(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.id, c.name, c.deleted_at,
+                 (SELECT COUNT(*) FROM cards WHERE collection_id = c.id AND deleted_at = c.deleted_at)
+             FROM collections c WHERE c.deleted_at IS NOT NULL ORDER BY c.deleted_at DESC",
+        )?;
+    let collections = stmt
+        .query_map([], |row| {
+            Ok(TrashedCollection { id: row.get(0)?, name: row.get(1)?, deleted_at: row.get(2)?, card_count: row.get(3)? })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT k.id, k.collection_id, c.name, k.question, k.answer, k.deleted_at
+             FROM cards k JOIN collections c ON c.id = k.collection_id
+             WHERE k.deleted_at IS NOT NULL AND c.deleted_at IS NULL ORDER BY k.deleted_at DESC, k.id",
+        )?;
+    let cards = stmt
+        .query_map([], |row| {
+            Ok(TrashedCard {
+                id: row.get(0)?,
+                collection_id: row.get(1)?,
+                collection_name: row.get(2)?,
+                question: row.get(3)?,
+                answer: row.get(4)?,
+                deleted_at: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Trash { collections, cards, retention_days: retention_days(&conn)? })
+}
+
+/// Restore trashed cards and collections. A collection comes back with the cards trashed along with it.
+/// Fails without changing anything if a restored card duplicates a live one.
+#[tauri::command]
+pub fn restore_trash(
+    app: tauri::AppHandle,
+    card_ids: Option<Vec<i64>>,
+    collection_ids: Option<Vec<i64>>,
+) -> Result<(), AppError> {
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    for id in collection_ids.unwrap_or_default() {
+        tx.execute(
+            "UPDATE cards SET deleted_at = NULL
+             WHERE collection_id = ?1 AND deleted_at = (SELECT deleted_at FROM collections WHERE id = ?1)",
+            rusqlite::params![id],
+        )?;
+        tx.execute("UPDATE collections SET deleted_at = NULL WHERE id = ?1", rusqlite::params![id])?;
+    }
+    for id in card_ids.unwrap_or_default() {
+        tx.execute("UPDATE cards SET deleted_at = NULL WHERE id = ?1", rusqlite::params![id])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Permanently delete trashed items; empties the whole trash when no ids are given.
+/// Returns the number of cards and collections removed.
+#[tauri::command]
+pub fn purge_trash(
+    app: tauri::AppHandle,
+    card_ids: Option<Vec<i64>>,
+    collection_ids: Option<Vec<i64>>,
+) -> Result<u32, AppError> {
+    let id_list = |ids: &[i64]| ids.iter().map(i64::to_string).collect::<Vec<_>>().join(", ");
+    let (card_condition, collection_condition) = match (card_ids, collection_ids) {
+        (None, None) => ("1".to_string(), "1".to_string()),
+        (cards, collections) => (
+            format!("id IN ({})", id_list(&cards.unwrap_or_default())),
+            format!("id IN ({})", id_list(&collections.unwrap_or_default())),
+        ),
+    };
+    crate::backup::snapshot(&app, "purge-trash")?;
+    let mut conn = crate::open_db(&app)?;
+    let tx = conn.transaction()?;
+    let purged = purge_where(&tx, &card_condition, &collection_condition)?;
+    tx.commit()?;
+    Ok(purged)
+}
+
+/// Days trashed items are kept before being purged automatically; 0 turns automatic purging off.
+#[tauri::command]
+pub fn set_trash_retention_days(app: tauri::AppHandle, days: u32) -> Result<(), AppError> {
+    let conn = crate::open_db(&app)?;
+    crate::set_setting(&conn, RETENTION_SETTING, &days.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO collections (id, name, deleted_at) VALUES (1, 'A', NULL), (2, 'Old', datetime('now', '-40 days'));
+             INSERT INTO sub_collections (id, name, collection_id) VALUES (1, '- None -', 1), (2, '- None -', 2);
+             INSERT INTO cards (id, question, answer, collection_id, sub_collection_id, deleted_at) VALUES
+                 (1, 'Q', 'A', 1, 1, datetime('now', '-40 days')),
+                 (2, 'R', 'B', 1, 1, datetime('now', '-1 days')),
+                 (3, 'S', 'C', 1, 1, NULL),
+                 (4, 'T', 'D', 2, 2, datetime('now', '-40 days'));",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn ids(conn: &rusqlite::Connection, table: &str) -> Vec<i64> {
+        conn.prepare(&format!("SELECT id FROM {table} ORDER BY id"))
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn expired_items_are_purged_with_their_cards() {
+        let conn = db();
+        assert_eq!(purge_expired(&conn).unwrap(), 3);
+        assert_eq!(ids(&conn, "cards"), vec![2, 3]);
+        assert_eq!(ids(&conn, "collections"), vec![1]);
+        assert_eq!(ids(&conn, "sub_collections"), vec![1]);
+    }
+
+    #[test]
+    fn undo_cannot_bring_back_purged_cards() {
+        let conn = db();
+        let mut op = crate::journal::Operation::new("Delete card");
+        op.track(&conn, "cards", "id = 3", []).unwrap();
+        conn.execute("UPDATE cards SET deleted_at = datetime('now', '-40 days') WHERE id = 3", []).unwrap();
+        op.commit(&conn).unwrap();
+        purge_expired(&conn).unwrap();
+        assert!(crate::journal::apply_next(&conn, true).unwrap().is_none());
+        assert_eq!(ids(&conn, "cards"), vec![2]);
+    }
+
+    #[test]
+    fn zero_retention_keeps_everything() {
+        let conn = db();
+        crate::set_setting(&conn, RETENTION_SETTING, "0").unwrap();
+        assert_eq!(purge_expired(&conn).unwrap(), 0);
+        crate::set_setting(&conn, RETENTION_SETTING, "60").unwrap();
+        assert_eq!(purge_expired(&conn).unwrap(), 0);
+        assert_eq!(ids(&conn, "cards"), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn trashed_items_do_not_block_live_ones() {
+        let conn = db();
+        conn.execute("INSERT INTO cards (question, answer, collection_id, sub_collection_id) VALUES ('Q', 'A', 1, 1)", []).unwrap();
+        assert!(conn.execute("INSERT INTO cards (question, answer, collection_id, sub_collection_id) VALUES ('Q', 'A', 1, 1)", []).is_err());
+        assert!(check_name_not_trashed(&conn, "Old").is_err());
+        assert!(check_name_not_trashed(&conn, "A").is_ok());
+    }
+}
@@ -0,0 +1,141 @@
+//! Directory watch mode for bulk create: new screenshots are queued for generation as they appear.
+
+use crate::error::AppError;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer};
+use tauri::{Emitter, Manager};
+
+use crate::jobs::GenerationQueue;
+use crate::scan::{FileMatcher, ScanOptions};
+
+const FILES_ADDED_EVENT: &str = "directory-watch-files-added";
+const WATCH_ERROR_EVENT: &str = "directory-watch-error";
+/// Quiet period before a burst of file events is reported, so half-written files settle first.
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+
+/// The active watcher, if any. Dropping the debouncer stops watching.
+#[derive(Default)]
+pub struct DirectoryWatch(Mutex<Option<Debouncer<RecommendedWatcher>>>);
+
+/// Files in a debounced batch that match the scan options, sorted and without repeats. Created and
+/// renamed-in files both show up as events on the new path; modifications of files we already know
+/// about are deduplicated by the queue, and deleted files are dropped here.
+fn new_paths(events: Vec<DebouncedEvent>, matcher: &FileMatcher) -> Vec<String> {
+    let mut paths: Vec<String> = events
+        .into_iter()
+        .map(|event| event.path)
+        .filter(|path| path.is_file() && matcher.matches(path))
+        .filter_map(|p| p.into_os_string().into_string().ok())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Queue matching files from a debounced batch and tell the webview which ones need OCR.
+fn handle_events(app: &tauri::AppHandle, matcher: &FileMatcher, result: DebounceEventResult) {
+    let events = match result {
+        Ok(events) => events,
+        Err(e) => {
+            let _ = app.emit(WATCH_ERROR_EVENT, e.to_string());
+            return;
+        }
+    };
+    let paths = new_paths(events, matcher);
+    if paths.is_empty() {
+        return;
+    }
+
+    let result = crate::open_db(app).and_then(|conn| crate::jobs::enqueue_paths(&conn, &paths));
+    match result {
+        Ok(jobs) => {
+            let jobs: Vec<_> = jobs.into_iter().filter(|job| !job.is_dismissed()).collect();
+            if let Some(queue) = app.try_state::<Arc<GenerationQueue>>() {
+                queue.wake();
+            }
+            let _ = app.emit(FILES_ADDED_EVENT, jobs);
+        }
+        Err(e) => {
+            let _ = app.emit(WATCH_ERROR_EVENT, e.to_string());
+        }
+    }
+}
+
+/// Watch a directory for new files matching the scan options, replacing any previous watch.
+#[tauri::command]
+pub fn start_directory_watch(
+    app: tauri::AppHandle,
+    watch: tauri::State<'_, DirectoryWatch>,
+    directory: String,
+    options: Option<ScanOptions>,
+) -> Result<(), AppError> {
+    let dir = std::path::Path::new(&directory);
+    if !dir.is_dir() {
+        return Err(AppError::validation("directory", "Path is not a directory"));
+    }
+    let matcher = FileMatcher::new(dir, &options.unwrap_or_default())?;
+    let mode = if matcher.recursive() {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    let handler_app = app.clone();
+    let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| {
+        handle_events(&handler_app, &matcher, result);
+    })
+    .map_err(AppError::io)?;
+    debouncer.watcher().watch(dir, mode).map_err(AppError::io)?;
+    *watch.0.lock()? = Some(debouncer);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_directory_watch(watch: tauri::State<'_, DirectoryWatch>) -> Result<(), AppError> {
+    watch.0.lock()?.take();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_mini::DebouncedEventKind;
+
+    #[test]
+    fn batches_yield_matching_files_once() {
+        let dir = std::env::temp_dir().join(format!("watch-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        for name in ["b.png", "a.png", "notes.txt", "sub/c.png"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+        let options: ScanOptions = serde_json::from_value(serde_json::json!({ "formats": ["png"] })).unwrap();
+        let matcher = FileMatcher::new(&dir, &options).unwrap();
+        let events = ["b.png", "notes.txt", "a.png", "b.png", "sub/c.png", "gone.png", "sub"]
+            .into_iter()
+            .map(|name| DebouncedEvent::new(dir.join(name), DebouncedEventKind::Any))
+            .collect();
+        let names: Vec<String> = new_paths(events, &matcher)
+            .into_iter()
+            .map(|path| std::path::Path::new(&path).strip_prefix(&dir).unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["a.png", "b.png"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn paths_queued_again_revive_their_job_instead_of_duplicating_it() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::init_db(&conn).unwrap();
+        let paths = vec!["/watched/a.png".to_string(), "/watched/b.png".to_string()];
+        let first = crate::jobs::enqueue_paths(&conn, &paths).unwrap();
+        assert_eq!(first.len(), 2);
+        conn.execute("UPDATE generation_jobs SET status = 'cancelled' WHERE path = '/watched/b.png'", []).unwrap();
+        let again = crate::jobs::enqueue_paths(&conn, &paths[1..]).unwrap();
+        assert_eq!(again.len(), 1);
+        assert!(!again[0].is_dismissed());
+        let jobs: i64 = conn.query_row("SELECT COUNT(*) FROM generation_jobs WHERE status = 'pending'", [], |r| r.get(0)).unwrap();
+        assert_eq!(jobs, 2);
+    }
+}
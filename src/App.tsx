@@ -6,8 +6,11 @@ import { BulkCreate } from "@/pages/BulkCreate";
 import { ImportExport } from "@/pages/ImportExport";
 import { CreateCards } from "@/pages/CreateCards";
 import { EditCards } from "@/pages/EditCards";
+import { Duplicates } from "@/pages/Duplicates";
+import { Leeches } from "@/pages/Leeches";
 import { Study } from "@/pages/Study";
 import { OllamaTest } from "@/pages/OllamaTest";
+import { Trash } from "@/pages/Trash";
 
 function NavLink({
   to,
@@ -58,9 +61,18 @@ function AppLayout() {
               <NavLink to="/edit" value="/edit" disabled={sessionActive}>
                 Edit Cards
               </NavLink>
+              <NavLink to="/duplicates" value="/duplicates" disabled={sessionActive}>
+                Duplicates
+              </NavLink>
+              <NavLink to="/leeches" value="/leeches" disabled={sessionActive}>
+                Leeches
+              </NavLink>
               <NavLink to="/study" value="/study" disabled={sessionActive}>
                 Study
               </NavLink>
+              <NavLink to="/trash" value="/trash" disabled={sessionActive}>
+                Trash
+              </NavLink>
               <NavLink to="/ollama-test" value="/ollama-test" disabled={sessionActive}>
                 Ollama Test
               </NavLink>
@@ -77,7 +89,10 @@ function AppLayout() {
           <Route path="/import-export" element={<ImportExport />} />
           <Route path="/create" element={<CreateCards />} />
           <Route path="/edit" element={<EditCards />} />
+          <Route path="/duplicates" element={<Duplicates />} />
+          <Route path="/leeches" element={<Leeches />} />
           <Route path="/study" element={<Study />} />
+          <Route path="/trash" element={<Trash />} />
           <Route path="/ollama-test" element={<OllamaTest />} />
         </Routes>
       </main>
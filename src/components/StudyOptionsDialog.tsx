@@ -0,0 +1,241 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { errorMessage } from "@/lib/utils";
+
+const INHERIT = "__inherit__"; // Radix Select forbids SelectItem value=""
+const DEFAULT_OPTIONS_ID = 1;
+
+type OptionValues = {
+  name: string;
+  new_per_day: number;
+  reviews_per_day: number;
+  /** Minutes between answers while learning. */
+  learning_steps: number[];
+  desired_retention: number;
+  rollover_hour: number;
+};
+export type CollectionOptions = OptionValues & { id: number; used_by: number };
+export type StudyDay = {
+  options: CollectionOptions;
+  own_options_id: number | null;
+  new_done: number;
+  reviews_done: number;
+};
+
+/** Form fields as typed. */
+type OptionFields = {
+  name: string;
+  newPerDay: string;
+  reviewsPerDay: string;
+  steps: string;
+  retention: string;
+  rolloverHour: string;
+};
+
+function toFields(options: OptionValues): OptionFields {
+  return {
+    name: options.name,
+    newPerDay: String(options.new_per_day),
+    reviewsPerDay: String(options.reviews_per_day),
+    steps: options.learning_steps.join(" "),
+    retention: String(Math.round(options.desired_retention * 100)),
+    rolloverHour: String(options.rollover_hour),
+  };
+}
+
+function toValues(fields: OptionFields): OptionValues {
+  return {
+    name: fields.name.trim(),
+    new_per_day: Number(fields.newPerDay),
+    reviews_per_day: Number(fields.reviewsPerDay),
+    learning_steps: fields.steps.split(/[\s,]+/).filter(Boolean).map(Number),
+    desired_retention: Number(fields.retention) / 100,
+    rollover_hour: Number(fields.rolloverHour),
+  };
+}
+
+/** Choose and edit the options preset of a collection, or of a sub collection. */
+export function StudyOptionsDialog({
+  open,
+  onOpenChange,
+  collectionId,
+  subCollectionId,
+  onChanged,
+}: {
+  open: boolean;
+  onOpenChange: (open: boolean) => void;
+  collectionId: number;
+  subCollectionId: number | null;
+  onChanged: () => void;
+}) {
+  const [presets, setPresets] = useState<CollectionOptions[]>([]);
+  const [day, setDay] = useState<StudyDay | null>(null);
+  const [fields, setFields] = useState<OptionFields | null>(null);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  async function load() {
+    const [list, studyDay] = await Promise.all([
+      invoke<CollectionOptions[]>("list_collection_options"),
+      invoke<StudyDay>("get_study_day", { collectionId, subCollectionId }),
+    ]);
+    setPresets(list);
+    setDay(studyDay);
+    setFields(toFields(studyDay.options));
+  }
+
+  useEffect(() => {
+    if (!open) return;
+    setError(null);
+    load().catch((e) => setError(errorMessage(e)));
+  }, [open, collectionId, subCollectionId]);
+
+  async function run(action: () => Promise<unknown>) {
+    setBusy(true);
+    setError(null);
+    try {
+      await action();
+      await load();
+      onChanged();
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  function choosePreset(value: string) {
+    const optionsId = value === INHERIT ? null : Number(value);
+    run(() => invoke("set_collection_options", { collectionId, subCollectionId, optionsId }));
+  }
+
+  function savePreset() {
+    if (!day || !fields) return;
+    run(() => invoke("save_collection_options", { id: day.options.id, options: toValues(fields) }));
+  }
+
+  function saveAsNewPreset() {
+    if (!fields) return;
+    run(async () => {
+      const created = await invoke<CollectionOptions>("save_collection_options", {
+        options: toValues(fields),
+      });
+      await invoke("set_collection_options", { collectionId, subCollectionId, optionsId: created.id });
+    });
+  }
+
+  function deletePreset() {
+    if (!day || !confirm(`Delete the preset "${day.options.name}"? Everything using it goes back to the default.`))
+      return;
+    run(() => invoke("delete_collection_options", { id: day.options.id }));
+  }
+
+  const selected =
+    day?.own_options_id != null
+      ? String(day.own_options_id)
+      : subCollectionId != null
+        ? INHERIT
+        : String(DEFAULT_OPTIONS_ID);
+  const field = (key: keyof OptionFields) => ({
+    value: fields?.[key] ?? "",
+    onChange: (e: React.ChangeEvent<HTMLInputElement>) =>
+      setFields((prev) => (prev ? { ...prev, [key]: e.target.value } : prev)),
+  });
+
+  return (
+    <Dialog open={open} onOpenChange={onOpenChange}>
+      <DialogContent className="sm:max-w-lg">
+        <DialogHeader>
+          <DialogTitle>{subCollectionId != null ? "Sub collection options" : "Collection options"}</DialogTitle>
+        </DialogHeader>
+        <div className="grid gap-4 py-2">
+          <div className="grid gap-2">
+            <Label>Preset</Label>
+            <Select value={selected} onValueChange={choosePreset} disabled={busy}>
+              <SelectTrigger className="w-full">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                {subCollectionId != null && <SelectItem value={INHERIT}>Same as parent</SelectItem>}
+                {presets.map((p) => (
+                  <SelectItem key={p.id} value={String(p.id)}>
+                    {p.name}
+                    {p.used_by > 1 ? ` (shared by ${p.used_by})` : ""}
+                  </SelectItem>
+                ))}
+              </SelectContent>
+            </Select>
+            {day && (
+              <p className="text-muted-foreground text-xs">
+                Today: {day.new_done} of {day.options.new_per_day} new cards, {day.reviews_done} of{" "}
+                {day.options.reviews_per_day} reviews.
+              </p>
+            )}
+          </div>
+          <div className="grid grid-cols-2 gap-3">
+            <div className="col-span-2 grid gap-2">
+              <Label htmlFor="options-name">Preset name</Label>
+              <Input id="options-name" {...field("name")} />
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="options-new">New cards per day</Label>
+              <Input id="options-new" inputMode="numeric" {...field("newPerDay")} />
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="options-reviews">Maximum reviews per day</Label>
+              <Input id="options-reviews" inputMode="numeric" {...field("reviewsPerDay")} />
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="options-steps">Learning steps (minutes)</Label>
+              <Input id="options-steps" placeholder="e.g. 1 10" {...field("steps")} />
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="options-retention">Desired retention (%)</Label>
+              <Input id="options-retention" inputMode="numeric" {...field("retention")} />
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="options-rollover">Next day starts at (hour)</Label>
+              <Input id="options-rollover" inputMode="numeric" {...field("rolloverHour")} />
+            </div>
+          </div>
+          {error && <p className="text-destructive text-sm">{error}</p>}
+        </div>
+        <DialogFooter className="sm:justify-between">
+          <Button
+            type="button"
+            variant="destructive"
+            disabled={busy || !day || day.options.id === DEFAULT_OPTIONS_ID}
+            onClick={deletePreset}
+          >
+            Delete preset
+          </Button>
+          <div className="flex gap-2">
+            <Button type="button" variant="outline" disabled={busy || !fields} onClick={saveAsNewPreset}>
+              Save as new preset
+            </Button>
+            <Button type="button" disabled={busy || !fields} onClick={savePreset}>
+              Save
+            </Button>
+          </div>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
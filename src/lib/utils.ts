@@ -1,11 +1,48 @@
 import { clsx, type ClassValue } from "clsx"
 import { twMerge } from "tailwind-merge"
-import { Ollama, type Message } from "ollama/browser"
+import { invoke } from "@tauri-apps/api/core"
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
 
+/** Error returned by a backend command; `code` is stable, `message` is for display. */
+export type AppError = {
+  code: "not_found" | "duplicate" | "validation" | "io" | "db" | "reserved" | "conflict"
+  message: string
+  /** Argument that failed validation, e.g. `subCollectionId`. */
+  field?: string
+}
+
+export function isAppError(e: unknown): e is AppError {
+  return typeof e === "object" && e !== null && "code" in e && "message" in e
+}
+
+/** Message for an error thrown by `invoke` or by frontend code. */
+export function errorMessage(e: unknown): string {
+  if (e instanceof Error) return e.message
+  if (isAppError(e)) return e.message
+  return String(e)
+}
+
+/** Ids of a sub collection and of every sub collection below it. */
+export function subCollectionSubtree(
+  subCollections: { id: number; parent_id: number | null }[],
+  id: number
+): Set<number> {
+  const ids = new Set([id])
+  let grew = true
+  while (grew) {
+    grew = false
+    for (const s of subCollections) {
+      if (s.parent_id != null && ids.has(s.parent_id) && !ids.has(s.id)) {
+        ids.add(s.id)
+        grew = true
+      }
+    }
+  }
+  return ids
+}
 
 export const DEFAULT_PROMPT_PREFIX = `
 I'm making flashcards for studying. Create exactly one flashcard in question and answer format.
@@ -20,42 +57,27 @@ Rules:
 Create one flashcard with the following information:
 `.trim();
 
+export type OllamaHost = "local" | "cloud"
+
+export type Message = {
+  role: string
+  content: string
+}
+
+/** Generate one flashcard via the backend, which attaches any stored API key itself. */
 export async function generateFlashcard(
   data: string,
   prefix?: string,
   model?: string,
-  host?: string,
-  apiKey?: string,
-  fetchFn?: typeof fetch
+  host: OllamaHost = "local"
 ): Promise<Message> {
-  const content = `${prefix ?? DEFAULT_PROMPT_PREFIX}
-  ${data}`
-
-  const m = model?.trim() || 'glm-4.7-flash'
-
-  const normalizedHost = host?.replace(/\/$/, "") ?? ""
-
-  const client = host
-    ? new Ollama({
-        host: normalizedHost,
-        ...(apiKey && { headers: { Authorization: `Bearer ${apiKey}` } }),
-        ...(fetchFn && { fetch: fetchFn }),
-      })
-    : new Ollama()
-  const response = await client.chat({
-    model: m,
-    messages: [{ role: 'user', content }]
+  const content = await invoke<string>("generate_flashcard", {
+    host,
+    model: model?.trim() || 'glm-4.7-flash',
+    promptPrefix: prefix ?? DEFAULT_PROMPT_PREFIX,
+    data,
   })
-
-  const rawContent = response.message.content ?? ""
-  const strippedContent = rawContent
-    .split("\n")
-    .filter((line) => !line.trimStart().startsWith("```"))
-    .filter((line) => !line.trimStart().startsWith("["))
-    .filter((line) => !line.trimStart().startsWith("]"))
-    .join("\n")
-
-  return { ...response.message, content: strippedContent }
+  return { role: "assistant", content }
 }
 
 export type Flashcard = {
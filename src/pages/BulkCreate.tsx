@@ -1,7 +1,7 @@
 import { useEffect, useState, useRef, useCallback } from "react";
 import { useBulkCreateSession } from "@/contexts/BulkCreateSessionContext";
 import { invoke } from "@tauri-apps/api/core";
-import { fetch as tauriFetch } from "@tauri-apps/plugin-http";
+import { listen } from "@tauri-apps/api/event";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import { Label } from "@/components/ui/label";
 import { Textarea } from "@/components/ui/textarea";
@@ -22,38 +22,69 @@ import {
 } from "@/components/ui/dialog";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
-import { cn, DEFAULT_PROMPT_PREFIX, generateFlashcard, parseFlashcard } from "@/lib/utils";
+import { cn, DEFAULT_PROMPT_PREFIX, errorMessage, type OllamaHost } from "@/lib/utils";
 import { createWorker } from "tesseract.js";
 
-const OLLAMA_HOSTS = {
-  local: "http://localhost:11434",
-  cloud: "https://ollama.com",
-} as const;
-
 type StoredCollection = { id: number; name: string };
-type StoredSubCollection = { id: number; name: string; collection_id: number };
+type StoredSubCollection = {
+  id: number;
+  name: string;
+  collection_id: number;
+  parent_id: number | null;
+  /** e.g. "Chapter 1/Section 2" */
+  path: string;
+};
 
-type OcrQueueItem = {
+type GenerationJob = {
+  id: number;
   path: string;
-  text: string;
-  llmResponse?: string;
-  llmQuestion?: string;
-  llmAnswer?: string;
-  llmStatus: "idle" | "running" | "done" | "error";
+  /** PDF page the text comes from; null for image files. */
+  page: number | null;
+  status: "pending" | "running" | "done" | "failed" | "cancelled" | "dismissed";
+  /** OCR or extracted PDF text; null until the file has been read. */
+  source_text: string | null;
+  response: string;
+  question: string;
+  answer: string;
+  error: string | null;
+  attempts: number;
+  /** SHA-256 of the file when it was queued. */
+  source_hash: string | null;
+  /** Model and prompt version that produced the response. */
+  generator: string | null;
+};
+
+type GenerationQueueSettings = {
+  paused: boolean;
+  concurrency: number;
+  max_attempts: number;
+  host: OllamaHost;
+  model: string;
+  prompt_prefix: string;
 };
 
-const BULK_IMAGE_FORMAT_MIME: Record<string, string> = {
+/** MIME type by file extension, for handing images to Tesseract as data URLs. */
+const BULK_IMAGE_EXTENSION_MIME: Record<string, string> = {
   png: "image/png",
+  jpg: "image/jpeg",
   jpeg: "image/jpeg",
   webp: "image/webp",
   gif: "image/gif",
 };
 
-const BULK_IMAGE_FORMATS = [
+type ScannedFile = { path: string; size: number; modified_ms?: number };
+
+function mimeForPath(path: string): string {
+  const ext = path.split(".").pop()?.toLowerCase() ?? "";
+  return BULK_IMAGE_EXTENSION_MIME[ext] ?? "image/png";
+}
+
+const BULK_FILE_FORMATS = [
   { value: "png", label: "PNG" },
   { value: "jpeg", label: "JPEG" },
   { value: "webp", label: "WebP" },
   { value: "gif", label: "GIF" },
+  { value: "pdf", label: "PDF" },
 ] as const;
 
 const BULK_DIRECTORY_STORAGE_KEY = "bulk-create-directory";
@@ -64,7 +95,8 @@ export function BulkCreate() {
   const [newCollectionOpen, setNewCollectionOpen] = useState(false);
   const [newCollectionName, setNewCollectionName] = useState("");
   const [creatingCollection, setCreatingCollection] = useState(false);
-  const [bulkFileFormat, setBulkFileFormat] = useState<string>("png");
+  const [bulkFileFormats, setBulkFileFormats] = useState<string[]>(["png"]);
+  const [includeSubfolders, setIncludeSubfolders] = useState(false);
   const [selectedDirectory, setSelectedDirectory] = useState<string | null>(() => {
     try {
       return localStorage.getItem(BULK_DIRECTORY_STORAGE_KEY) || null;
@@ -75,17 +107,20 @@ export function BulkCreate() {
   const [llmEnabled, setLlmEnabled] = useState(true);
   const [autorunEnabled, setAutorunEnabled] = useState(true);
   const [promptPrefix, setPromptPrefix] = useState(DEFAULT_PROMPT_PREFIX);
-  const [ollamaHost, setOllamaHost] = useState<"local" | "cloud">("local");
+  const [ollamaHost, setOllamaHost] = useState<OllamaHost>("local");
   const [model, setModel] = useState("glm-4.7-flash");
   const [fileCount, setFileCount] = useState<number | null>(null);
   const [fileCountLoading, setFileCountLoading] = useState(false);
-  /** Queue of OCR results (path + text + cached LLM results) for the Card Preview side. */
-  const [ocrQueue, setOcrQueue] = useState<OcrQueueItem[]>([]);
+  /** Generation jobs from the backend queue; the source of truth for the Card Preview side. */
+  const [jobs, setJobs] = useState<GenerationJob[]>([]);
+  /** Whether the queue settings have been loaded from the backend (so we don't overwrite them). */
+  const [queueSettingsLoaded, setQueueSettingsLoaded] = useState(false);
   const [ocrProcessing, setOcrProcessing] = useState(false);
   const { sessionActive, setSessionActive } = useBulkCreateSession();
-  /** Index into ocrQueue for the Card Preview (which item we're viewing/editing). */
+  /** Index into the preview queue (which item we're viewing/editing). */
   const [previewIndex, setPreviewIndex] = useState(0);
-  /** Editable hint/question/answer for the current queue item (when tesseract text is available). */
+  /** Editable raw text/hint/question/answer for the current queue item (when tesseract text is available). */
+  const [editText, setEditText] = useState("");
   const [editHint, setEditHint] = useState("");
   const [editQuestion, setEditQuestion] = useState("");
   const [editAnswer, setEditAnswer] = useState("");
@@ -95,114 +130,130 @@ export function BulkCreate() {
   const [newSubCollectionName, setNewSubCollectionName] = useState("");
   const [creatingSubCollection, setCreatingSubCollection] = useState(false);
 
-  const currentQueueItem = ocrQueue.length > 0 && previewIndex >= 0 && previewIndex < ocrQueue.length
-    ? ocrQueue[previewIndex]
+  const scanOptions = { formats: bulkFileFormats, recursive: includeSubfolders };
+  /** Stable dependency for effects that scan or watch the directory. */
+  const scanOptionsKey = JSON.stringify(scanOptions);
+
+  /** Jobs whose files have been read, i.e. the items the user can review. */
+  const previewQueue = jobs.filter((job) => job.source_text !== null && job.status !== "dismissed");
+  const awaitingOcrCount = jobs.filter((job) => job.source_text === null && job.status !== "dismissed").length;
+  const currentQueueItem = previewQueue.length > 0 && previewIndex >= 0 && previewIndex < previewQueue.length
+    ? previewQueue[previewIndex]
     : null;
-  const rawTextRead = currentQueueItem?.text ?? "";
-  const llmResponse = currentQueueItem?.llmResponse ?? "";
-  const llmLoading = currentQueueItem?.llmStatus === "running";
+  const llmResponse = currentQueueItem?.error && currentQueueItem.status !== "done"
+    ? `Error: ${currentQueueItem.error}`
+    : currentQueueItem?.response ?? "";
+  const llmLoading = currentQueueItem?.status === "running";
   const hasProcessedText = currentQueueItem != null;
 
-  // Refs for stable access to LLM config inside async callbacks
-  const configRef = useRef({ ollamaHost, model, llmEnabled, promptPrefix });
+  // Latest jobs for use inside directory watch callbacks
+  const ocrChainRef = useRef<Promise<void>>(Promise.resolve());
+  const jobsRef = useRef<GenerationJob[]>([]);
   useEffect(() => {
-    configRef.current = { ollamaHost, model, llmEnabled, promptPrefix };
-  }, [ollamaHost, model, llmEnabled, promptPrefix]);
-
-  // Track which paths we've already kicked off LLM for (prevents double-starts from race conditions)
-  const llmStartedRef = useRef(new Set<string>());
+    jobsRef.current = jobs;
+  }, [jobs]);
+
+  const upsertJob = useCallback((job: GenerationJob) => {
+    setJobs((prev) => {
+      const index = prev.findIndex((j) => j.id === job.id);
+      if (index === -1) return [...prev, job].sort((a, b) => a.id - b.id);
+      return prev.map((j) => (j.id === job.id ? job : j));
+    });
+  }, []);
 
-  // Track all file paths already in or processed through the queue (for new-file polling)
-  const knownPathsRef = useRef(new Set<string>());
+  // Load persisted jobs and follow their progress from the backend queue
+  useEffect(() => {
+    let cancelled = false;
+    invoke<GenerationJob[]>("list_generation_jobs")
+      .then((data) => {
+        if (!cancelled) setJobs(data);
+      })
+      .catch(() => {});
+    invoke<GenerationQueueSettings>("get_generation_queue")
+      .then((settings) => {
+        if (cancelled) return;
+        setOllamaHost(settings.host);
+        if (settings.model) setModel(settings.model);
+        if (settings.prompt_prefix) setPromptPrefix(settings.prompt_prefix);
+        setQueueSettingsLoaded(true);
+      })
+      .catch(() => setQueueSettingsLoaded(true));
+    const unlisten = listen<GenerationJob>("generation-job-updated", (event) => upsertJob(event.payload));
+    const unlistenError = listen<string>("generation-queue-error", (event) => {
+      console.error("Generation queue failed:", event.payload);
+    });
+    return () => {
+      cancelled = true;
+      unlisten.then((fn) => fn());
+      unlistenError.then((fn) => fn());
+    };
+  }, [upsertJob]);
 
-  // Core: run LLM for a specific queue item by path and store the result in the queue
-  const runLlmForPath = useCallback(async (path: string, text: string) => {
-    const { ollamaHost: host_, model: model_, llmEnabled: enabled } = configRef.current;
-    if (!enabled || !text.trim()) return;
+  // Push LLM settings to the backend queue. LLM off or autorun off pauses automatic generation.
+  // Local: one job at a time. Cloud: two in parallel.
+  useEffect(() => {
+    if (!queueSettingsLoaded) return;
+    const settings: GenerationQueueSettings = {
+      paused: !llmEnabled || !autorunEnabled,
+      concurrency: ollamaHost === "cloud" ? 2 : 1,
+      max_attempts: 3,
+      host: ollamaHost,
+      model,
+      prompt_prefix: promptPrefix,
+    };
+    invoke("update_generation_queue", { settings }).catch(() => {});
+  }, [queueSettingsLoaded, llmEnabled, autorunEnabled, ollamaHost, model, promptPrefix]);
 
-    setOcrQueue((prev) =>
-      prev.map((item) =>
-        item.path === path ? { ...item, llmStatus: "running" as const } : item
-      )
-    );
+  // Keep the raw text editor in sync with the current item
+  useEffect(() => {
+    setEditText(currentQueueItem?.source_text ?? "");
+  }, [currentQueueItem?.id, currentQueueItem?.source_text]);
 
+  async function saveEditedText() {
+    if (!currentQueueItem || editText === currentQueueItem.source_text) return;
     try {
-      const host = OLLAMA_HOSTS[host_];
-      const apiKey =
-        host_ === "cloud" ? await invoke<string>("get_ollama_api_key") : undefined;
-      const prefix = configRef.current.promptPrefix.trim() || undefined;
-      const message = await generateFlashcard(
-        text,
-        prefix,
-        model_,
-        host,
-        apiKey ?? undefined,
-        host_ === "cloud" ? tauriFetch : undefined
-      );
-      const content = message.content ?? "";
-      const flashcard = parseFlashcard(content);
-      setOcrQueue((prev) =>
-        prev.map((item) =>
-          item.path === path
-            ? {
-                ...item,
-                llmStatus: "done" as const,
-                llmResponse: content,
-                llmQuestion: flashcard.question,
-                llmAnswer: flashcard.answer,
-              }
-            : item
-        )
-      );
-    } catch (e) {
-      const errorMsg = `Error: ${e instanceof Error ? e.message : String(e)}`;
-      setOcrQueue((prev) =>
-        prev.map((item) =>
-          item.path === path
-            ? { ...item, llmStatus: "error" as const, llmResponse: errorMsg }
-            : item
-        )
-      );
+      upsertJob(await invoke<GenerationJob>("set_generation_job_text", { id: currentQueueItem.id, text: editText }));
+    } catch {
+      // ignore; the editor keeps the user's text
     }
-  }, []);
-
-  // Auto-run LLM for current card + look-ahead for upcoming cards.
-  // Local: sequential (one at a time, but keeps chaining ahead).
-  // Cloud: concurrent (current + next in parallel).
-  useEffect(() => {
-    if (!llmEnabled || !autorunEnabled) return;
-
-    const tryStart = (item: OcrQueueItem | undefined) => {
-      if (!item || item.llmStatus !== "idle" || !item.text.trim()) return;
-      if (llmStartedRef.current.has(item.path)) return;
-      llmStartedRef.current.add(item.path);
-      runLlmForPath(item.path, item.text);
-    };
+  }
 
-    if (ollamaHost === "local") {
-      // Sequential: only start the next idle card when nothing is in-flight
-      const anyRunning = ocrQueue.some((item) => item.llmStatus === "running");
-      if (!anyRunning) {
-        for (let i = previewIndex; i < ocrQueue.length; i++) {
-          const item = ocrQueue[i];
-          if (item.llmStatus === "idle" && item.text.trim() && !llmStartedRef.current.has(item.path)) {
-            llmStartedRef.current.add(item.path);
-            runLlmForPath(item.path, item.text);
-            break; // one at a time
-          }
+  /** Run OCR for jobs that don't have text yet and hand the text to the backend queue. */
+  async function ocrJobs(pending: GenerationJob[], isActive: () => boolean = () => true) {
+    if (pending.length === 0) return;
+    const worker = await createWorker("eng");
+    try {
+      for (const job of pending) {
+        if (!isActive()) break;
+        let text = "";
+        let error: string | undefined;
+        try {
+          // PDF pages without a text layer come back rendered as an image.
+          const dataUrl =
+            job.page != null
+              ? await invoke<string>("read_pdf_page_image", { path: job.path, page: job.page })
+              : `data:${mimeForPath(job.path)};base64,${await invoke<string>("read_file_base64", { path: job.path })}`;
+          const { data } = await worker.recognize(dataUrl);
+          text = data.text ?? "";
+        } catch (e) {
+          // the job fails with the reason; its text can still be filled in by hand and the job rerun
+          error = errorMessage(e);
+        }
+        try {
+          upsertJob(await invoke<GenerationJob>("set_generation_job_text", { id: job.id, text, error }));
+        } catch {
+          // ignore; the job stays pending OCR and is retried next run
         }
       }
-    } else {
-      // Cloud: run current + next concurrently
-      tryStart(ocrQueue[previewIndex]);
-      tryStart(ocrQueue[previewIndex + 1]);
+    } finally {
+      await worker.terminate();
     }
-  }, [ocrQueue, previewIndex, llmEnabled, autorunEnabled, ollamaHost, runLlmForPath]);
+  }
 
   // Reset hint when card identity changes
   useEffect(() => {
     setEditHint("");
-  }, [previewIndex, currentQueueItem?.path]);
+  }, [previewIndex, currentQueueItem?.id]);
 
   // Populate question/answer from cached LLM results (or clear while running)
   useEffect(() => {
@@ -212,14 +263,14 @@ export function BulkCreate() {
       return;
     }
 
-    if (currentQueueItem.llmStatus === "done") {
-      setEditQuestion(currentQueueItem.llmQuestion ?? "");
-      setEditAnswer(currentQueueItem.llmAnswer ?? "");
+    if (currentQueueItem.status === "done") {
+      setEditQuestion(currentQueueItem.question);
+      setEditAnswer(currentQueueItem.answer);
     } else {
       setEditQuestion("");
       setEditAnswer("");
     }
-  }, [previewIndex, currentQueueItem?.path, currentQueueItem?.llmStatus]);
+  }, [previewIndex, currentQueueItem?.id, currentQueueItem?.status]);
 
   useEffect(() => {
     try {
@@ -234,6 +285,7 @@ export function BulkCreate() {
   }, [selectedDirectory]);
 
   async function handleSave() {
+    if (!currentQueueItem) return;
     const collectionIdNum = selectedCollectionId ? Number(selectedCollectionId) : collections[0]?.id;
     if (collectionIdNum == null) return;
     const q = editQuestion.trim();
@@ -246,24 +298,34 @@ export function BulkCreate() {
         collectionId: collectionIdNum,
         hint: editHint.trim() || undefined,
         subCollectionId: selectedSubCollectionId ? Number(selectedSubCollectionId) : undefined,
+        source: {
+          path: currentQueueItem.path,
+          page: currentQueueItem.page ?? undefined,
+          hash: currentQueueItem.source_hash ?? undefined,
+          generator: currentQueueItem.generator ?? undefined,
+        },
       });
-      setOcrQueue((prev) => prev.filter((_, i) => i !== previewIndex));
+      await handleDismiss();
     } catch {
       // TODO: surface error to user
     }
   }
 
-  function handleRunLlm() {
-    if (!currentQueueItem || !rawTextRead.trim() || !llmEnabled) return;
-    // Reset item so the look-ahead effect re-runs it
-    llmStartedRef.current.delete(currentQueueItem.path);
-    setOcrQueue((prev) =>
-      prev.map((item, i) =>
-        i === previewIndex
-          ? { ...item, llmStatus: "idle" as const, llmResponse: undefined, llmQuestion: undefined, llmAnswer: undefined }
-          : item
-      )
-    );
+  async function handleDismiss() {
+    if (!currentQueueItem) return;
+    const id = currentQueueItem.id;
+    await invoke("dismiss_generation_job", { id });
+    setJobs((prev) => prev.filter((job) => job.id !== id));
+  }
+
+  async function handleRunLlm() {
+    if (!currentQueueItem || !editText.trim() || !llmEnabled) return;
+    try {
+      await saveEditedText();
+      await invoke("run_generation_job", { id: currentQueueItem.id });
+    } catch {
+      // failures are reported on the job itself
+    }
   }
 
   useEffect(() => {
@@ -338,10 +400,10 @@ export function BulkCreate() {
   }
 
   useEffect(() => {
-    if (ocrQueue.length > 0 && previewIndex >= ocrQueue.length) {
-      setPreviewIndex(ocrQueue.length - 1);
+    if (previewQueue.length > 0 && previewIndex >= previewQueue.length) {
+      setPreviewIndex(previewQueue.length - 1);
     }
-  }, [ocrQueue.length, previewIndex]);
+  }, [previewQueue.length, previewIndex]);
 
   async function handleCreateCollection() {
     const name = newCollectionName.trim();
@@ -373,31 +435,17 @@ export function BulkCreate() {
     if (!selectedDirectory) return;
     setSessionActive(true);
     setOcrProcessing(true);
-    setOcrQueue([]);
-    knownPathsRef.current.clear();
     try {
-      const paths = await invoke<string[]>("list_files_in_directory", {
+      const files = await invoke<ScannedFile[]>("list_files_in_directory", {
         directory: selectedDirectory,
-        format: bulkFileFormat,
+        options: scanOptions,
       });
+      const paths = files.map((f) => f.path);
       setFileCount(paths.length);
-      paths.forEach((p) => knownPathsRef.current.add(p));
-      const mime = BULK_IMAGE_FORMAT_MIME[bulkFileFormat] ?? "image/png";
-      const worker = await createWorker("eng");
-      try {
-        for (const path of paths) {
-          try {
-            const base64 = await invoke<string>("read_file_base64", { path });
-            const dataUrl = `data:${mime};base64,${base64}`;
-            const { data } = await worker.recognize(dataUrl);
-            setOcrQueue((prev) => [...prev, { path, text: data.text ?? "", llmStatus: "idle" }]);
-          } catch {
-            setOcrQueue((prev) => [...prev, { path, text: "", llmStatus: "idle" }]);
-          }
-        }
-      } finally {
-        await worker.terminate();
-      }
+      // Files already handled in an earlier session come back dismissed and are skipped.
+      const queued = await invoke<GenerationJob[]>("enqueue_generation_jobs", { paths });
+      queued.filter((job) => job.status !== "dismissed").forEach(upsertJob);
+      await ocrJobs(queued.filter((job) => job.source_text === null && job.status !== "dismissed"));
     } finally {
       setOcrProcessing(false);
     }
@@ -412,7 +460,7 @@ export function BulkCreate() {
     setFileCountLoading(true);
     invoke<number>("count_files_in_directory", {
       directory: selectedDirectory,
-      format: bulkFileFormat,
+      options: scanOptions,
     })
       .then((count) => {
         if (!cancelled) {
@@ -432,58 +480,39 @@ export function BulkCreate() {
     return () => {
       cancelled = true;
     };
-  }, [selectedDirectory, bulkFileFormat]);
+  }, [selectedDirectory, scanOptionsKey]);
 
-  // Poll the directory every 5 s for new files until the user clicks "Stop Creating Cards"
+  // Watch the directory for new files until the user clicks "Stop Creating Cards".
+  // The backend queues new files itself; we only need to OCR them.
   useEffect(() => {
     if (!selectedDirectory || !sessionActive || ocrProcessing) return;
 
     let active = true;
-
-    const poll = async () => {
-      if (!active) return;
-      try {
-        const paths = await invoke<string[]>("list_files_in_directory", {
-          directory: selectedDirectory,
-          format: bulkFileFormat,
-        });
-
-        if (active) setFileCount(paths.length);
-
-        const newPaths = paths.filter((p) => !knownPathsRef.current.has(p));
-        if (newPaths.length === 0 || !active) return;
-
-        newPaths.forEach((p) => knownPathsRef.current.add(p));
-
-        const mime = BULK_IMAGE_FORMAT_MIME[bulkFileFormat] ?? "image/png";
-        const worker = await createWorker("eng");
-        try {
-          for (const path of newPaths) {
-            if (!active) break;
-            try {
-              const base64 = await invoke<string>("read_file_base64", { path });
-              const dataUrl = `data:${mime};base64,${base64}`;
-              const { data } = await worker.recognize(dataUrl);
-              setOcrQueue((prev) => [...prev, { path, text: data.text ?? "", llmStatus: "idle" }]);
-            } catch {
-              setOcrQueue((prev) => [...prev, { path, text: "", llmStatus: "idle" }]);
-            }
-          }
-        } finally {
-          await worker.terminate();
-        }
-      } catch {
-        // ignore polling errors
-      }
-    };
-
-    const interval = setInterval(poll, 5000);
+    const unlistenAdded = listen<GenerationJob[]>("directory-watch-files-added", (event) => {
+      const added = event.payload.filter((job) => !jobsRef.current.some((j) => j.id === job.id));
+      event.payload.forEach(upsertJob);
+      if (added.length > 0) setFileCount((count) => (count ?? 0) + added.length);
+      const pending = event.payload.filter((job) => job.source_text === null);
+      // Serialize OCR runs so bursts of new files don't each spin up a worker
+      ocrChainRef.current = ocrChainRef.current
+        .then(() => ocrJobs(pending, () => active))
+        .catch(() => {});
+    });
+    const unlistenError = listen<string>("directory-watch-error", (event) => {
+      console.error("Directory watch failed:", event.payload);
+    });
+    invoke("start_directory_watch", {
+      directory: selectedDirectory,
+      options: scanOptions,
+    }).catch((e) => console.error("Directory watch failed:", e));
 
     return () => {
       active = false;
-      clearInterval(interval);
+      invoke("stop_directory_watch").catch(() => {});
+      unlistenAdded.then((fn) => fn());
+      unlistenError.then((fn) => fn());
     };
-  }, [selectedDirectory, bulkFileFormat, sessionActive, ocrProcessing]);
+  }, [selectedDirectory, scanOptionsKey, sessionActive, ocrProcessing, upsertJob]);
 
   const fileCountMessage =
     !selectedDirectory
@@ -564,19 +593,37 @@ export function BulkCreate() {
             </div>
           </div>
           <div className="grid w-full gap-2">
-            <Label>Bulk File Format</Label>
-            <Select value={bulkFileFormat} onValueChange={setBulkFileFormat}>
-              <SelectTrigger className="w-full">
-                <SelectValue placeholder="Select format..." />
-              </SelectTrigger>
-              <SelectContent>
-                {BULK_IMAGE_FORMATS.map((f) => (
-                  <SelectItem key={f.value} value={f.value}>
+            <Label>Bulk File Formats</Label>
+            <div className="flex flex-wrap gap-2">
+              {BULK_FILE_FORMATS.map((f) => {
+                const selected = bulkFileFormats.includes(f.value);
+                return (
+                  <Button
+                    key={f.value}
+                    type="button"
+                    variant={selected ? "default" : "outline"}
+                    aria-pressed={selected}
+                    onClick={() =>
+                      setBulkFileFormats((prev) =>
+                        selected
+                          ? prev.length > 1 ? prev.filter((v) => v !== f.value) : prev
+                          : [...prev, f.value]
+                      )
+                    }
+                  >
                     {f.label}
-                  </SelectItem>
-                ))}
-              </SelectContent>
-            </Select>
+                  </Button>
+                );
+              })}
+              <Button
+                type="button"
+                variant={includeSubfolders ? "default" : "outline"}
+                aria-pressed={includeSubfolders}
+                onClick={() => setIncludeSubfolders((v) => !v)}
+              >
+                Include subfolders
+              </Button>
+            </div>
           </div>
           <div className="grid w-full gap-2">
             <Label>Directory</Label>
@@ -715,10 +762,8 @@ export function BulkCreate() {
               onClick={() => {
                 if (sessionActive) {
                   setSessionActive(false);
-                  setOcrQueue([]);
                   setPreviewIndex(0);
-                  llmStartedRef.current.clear();
-                  knownPathsRef.current.clear();
+                  invoke("cancel_generation_jobs").catch(() => {});
                 } else {
                   handleCreateCards();
                 }
@@ -735,9 +780,14 @@ export function BulkCreate() {
                 {fileCountMessage}
               </p>
             )}
-            {ocrQueue.length > 0 && !ocrProcessing && (
+            {previewQueue.length > 0 && !ocrProcessing && (
               <p className="text-muted-foreground text-sm">
-                {ocrQueue.length} in queue
+                {previewQueue.length} in queue
+              </p>
+            )}
+            {awaitingOcrCount > 0 && !ocrProcessing && (
+              <p className="text-muted-foreground text-sm">
+                {awaitingOcrCount} not yet read
               </p>
             )}
           </div>
@@ -753,24 +803,22 @@ export function BulkCreate() {
         </CardHeader>
         <CardContent className="flex flex-col gap-4">
           <div className="space-y-2">
-            <p className="text-muted-foreground text-sm font-medium">Raw Text Read</p>
+            <p className="text-muted-foreground text-sm font-medium">
+              Raw Text Read
+              {currentQueueItem?.page != null && ` (page ${currentQueueItem.page})`}
+            </p>
             {hasProcessedText ? (
               <Textarea
-                value={rawTextRead}
-                onChange={(e) =>
-                  setOcrQueue((prev) =>
-                    prev.map((item, i) =>
-                      i === previewIndex ? { ...item, text: e.target.value } : item
-                    )
-                  )
-                }
+                value={editText}
+                onChange={(e) => setEditText(e.target.value)}
+                onBlur={saveEditedText}
                 placeholder="Run OCR to see raw text from images."
                 rows={4}
                 className="min-h-[4rem] resize-y font-mono text-sm"
               />
             ) : (
               <p className="whitespace-pre-wrap break-words rounded-md border bg-muted/50 p-3 text-sm min-h-[4rem] text-muted-foreground">
-                {ocrQueue.length === 0
+                {previewQueue.length === 0
                   ? "Run OCR to see raw text from images."
                   : "No text for this item."}
               </p>
@@ -779,7 +827,12 @@ export function BulkCreate() {
           <div className="space-y-2">
             <p className="text-muted-foreground text-sm font-medium">LLM Response</p>
             <p className="whitespace-pre-wrap break-words rounded-md border bg-muted/50 p-3 text-sm min-h-[4rem]">
-              {llmResponse || (ocrQueue.length === 0 ? "—" : "No LLM response for this item.")}
+              {llmResponse ||
+                (previewQueue.length === 0
+                  ? "—"
+                  : currentQueueItem?.status === "pending"
+                    ? "Waiting in queue…"
+                    : "No LLM response for this item.")}
             </p>
           </div>
           <div className="space-y-2">
@@ -842,7 +895,7 @@ export function BulkCreate() {
                 <SelectContent>
                   {subCollections.map((s) => (
                     <SelectItem key={s.id} value={String(s.id)}>
-                      {s.name}
+                      {s.path}
                     </SelectItem>
                   ))}
                 </SelectContent>
@@ -907,7 +960,7 @@ export function BulkCreate() {
               variant="outline"
               disabled={!hasProcessedText}
               onClick={() => {
-                setOcrQueue((prev) => prev.filter((_, i) => i !== previewIndex));
+                handleDismiss().catch(() => {});
               }}
             >
               Skip
@@ -915,7 +968,7 @@ export function BulkCreate() {
             <Button
               type="button"
               variant="outline"
-              disabled={!llmEnabled || !hasProcessedText || !rawTextRead.trim() || llmLoading}
+              disabled={!llmEnabled || !hasProcessedText || !editText.trim() || llmLoading}
               onClick={handleRunLlm}
             >
               {llmLoading ? "Running…" : "Run LLM"}
@@ -20,9 +20,17 @@ import {
   DialogTrigger,
 } from "@/components/ui/dialog";
 import { Input } from "@/components/ui/input";
+import { errorMessage, isAppError } from "@/lib/utils";
 
 type StoredCollection = { id: number; name: string };
-type StoredSubCollection = { id: number; name: string; collection_id: number };
+type StoredSubCollection = {
+  id: number;
+  name: string;
+  collection_id: number;
+  parent_id: number | null;
+  /** e.g. "Chapter 1/Section 2" */
+  path: string;
+};
 
 export function CreateCards() {
   const [title, setTitle] = useState("");
@@ -116,7 +124,9 @@ export function CreateCards() {
       setFlipped(false);
       questionInputRef.current?.focus();
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(errorMessage(e));
+      // Keep the text so a duplicate can be reworded instead of retyped.
+      if (isAppError(e) && e.code === "duplicate") questionInputRef.current?.focus();
     } finally {
       setAdding(false);
     }
@@ -134,7 +144,7 @@ export function CreateCards() {
       setNewCollectionName("");
       setNewCollectionOpen(false);
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(errorMessage(e));
     } finally {
       setCreatingCollection(false);
     }
@@ -156,7 +166,7 @@ export function CreateCards() {
       setNewSubCollectionName("");
       setNewSubCollectionOpen(false);
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(errorMessage(e));
     } finally {
       setCreatingSubCollection(false);
     }
@@ -254,7 +264,7 @@ export function CreateCards() {
                 <SelectContent>
                   {subCollections.map((s) => (
                     <SelectItem key={s.id} value={String(s.id)}>
-                      {s.name}
+                      {s.path}
                     </SelectItem>
                   ))}
                 </SelectContent>
@@ -0,0 +1,181 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { errorMessage } from "@/lib/utils";
+
+type StoredCollection = { id: number; name: string };
+type DuplicateCard = {
+  id: number;
+  question: string;
+  answer: string;
+  hint: string;
+  sub_collection_id: number;
+  sub_collection_path: string;
+  created_at: string;
+};
+type DuplicateCluster = { cards: DuplicateCard[]; similarity: number };
+type DuplicateMergeReport = { kept: number; merged: number };
+
+export function Duplicates() {
+  const [collections, setCollections] = useState<StoredCollection[]>([]);
+  const [collectionId, setCollectionId] = useState("");
+  const [threshold, setThreshold] = useState("80");
+  const [clusters, setClusters] = useState<DuplicateCluster[] | null>(null);
+  /** Card to keep per cluster, keyed by the cluster's first card id. */
+  const [keepIds, setKeepIds] = useState<Record<number, number>>({});
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<StoredCollection[]>("get_collections")
+      .then((data) => {
+        setCollections(data);
+        if (data.length > 0) setCollectionId(String(data[0].id));
+      })
+      .catch((e) => setError(errorMessage(e)));
+  }, []);
+
+  useEffect(() => {
+    setClusters(null);
+    setMessage(null);
+  }, [collectionId]);
+
+  async function find() {
+    const percent = Number(threshold);
+    if (!Number.isFinite(percent) || percent <= 0 || percent > 100) {
+      setError("Enter a similarity between 1 and 100 percent.");
+      return;
+    }
+    setBusy(true);
+    setError(null);
+    try {
+      const data = await invoke<DuplicateCluster[]>("find_duplicates", {
+        collectionId: Number(collectionId),
+        threshold: percent / 100,
+      });
+      setClusters(data);
+      setKeepIds(Object.fromEntries(data.map((c) => [c.cards[0].id, c.cards[0].id])));
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function merge(cluster: DuplicateCluster) {
+    const keepId = keepIds[cluster.cards[0].id] ?? cluster.cards[0].id;
+    setBusy(true);
+    setError(null);
+    try {
+      const report = await invoke<DuplicateMergeReport>("merge_duplicate_cards", {
+        keepId,
+        cardIds: cluster.cards.map((c) => c.id),
+      });
+      setMessage(`Merged ${report.merged} card${report.merged !== 1 ? "s" : ""} into card ${report.kept}.`);
+      setClusters((prev) => prev?.filter((c) => c !== cluster) ?? null);
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  return (
+    <div className="mx-auto flex w-full max-w-3xl flex-1 flex-col gap-6 p-6">
+      <Card>
+        <CardHeader>
+          <CardTitle>Duplicates</CardTitle>
+          <CardDescription>
+            Find cards that say the same thing and merge each group into one card. Merged cards go to the
+            trash, and the kept card takes any hint, source or review progress it is missing.
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-6">
+          <div className="flex flex-wrap items-end gap-3">
+            <div className="grid min-w-48 flex-1 gap-2">
+              <Label>Collection</Label>
+              <Select value={collectionId} onValueChange={setCollectionId}>
+                <SelectTrigger className="w-full min-w-0">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  {collections.map((c) => (
+                    <SelectItem key={c.id} value={String(c.id)}>
+                      {c.name}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="duplicate-threshold">Similarity (%)</Label>
+              <Input
+                id="duplicate-threshold"
+                className="w-24"
+                inputMode="numeric"
+                value={threshold}
+                onChange={(e) => setThreshold(e.target.value)}
+              />
+            </div>
+            <Button onClick={find} disabled={busy || collectionId === ""}>
+              Find duplicates
+            </Button>
+          </div>
+          {error != null && <p className="text-sm text-destructive">{error}</p>}
+          {message != null && <p className="text-sm text-muted-foreground">{message}</p>}
+          {clusters != null && clusters.length === 0 && (
+            <p className="text-sm text-muted-foreground">No duplicates found.</p>
+          )}
+
+          {clusters?.map((cluster) => {
+            const key = cluster.cards[0].id;
+            return (
+              <div key={key} className="space-y-2 rounded-md border p-3">
+                <div className="flex items-center justify-between gap-4 text-sm">
+                  <p className="text-muted-foreground">
+                    {cluster.cards.length} cards · {Math.round(cluster.similarity * 100)}% similar
+                  </p>
+                  <Button size="sm" variant="outline" disabled={busy} onClick={() => merge(cluster)}>
+                    Merge
+                  </Button>
+                </div>
+                <ul className="divide-y">
+                  {cluster.cards.map((card) => (
+                    <li key={card.id} className="flex items-start gap-3 py-2 text-sm">
+                      <input
+                        type="radio"
+                        className="mt-1"
+                        name={`keep-${key}`}
+                        aria-label="Keep this card"
+                        checked={keepIds[key] === card.id}
+                        onChange={() => setKeepIds((prev) => ({ ...prev, [key]: card.id }))}
+                      />
+                      <div className="min-w-0">
+                        <p className="font-medium">{card.question}</p>
+                        <p className="text-muted-foreground">{card.answer}</p>
+                        <p className="text-xs text-muted-foreground">
+                          {card.sub_collection_path} · created {card.created_at}
+                        </p>
+                      </div>
+                    </li>
+                  ))}
+                </ul>
+              </div>
+            );
+          })}
+        </CardContent>
+      </Card>
+    </div>
+  );
+}
@@ -21,14 +21,61 @@ import {
   DialogTrigger,
 } from "@/components/ui/dialog";
 import { ScrollArea } from "@/components/ui/scroll-area";
-import { cn } from "@/lib/utils";
+import { cn, errorMessage, subCollectionSubtree } from "@/lib/utils";
 
 
 const SUB_COLLECTION_ALL = "__all__";
+const SUB_COLLECTION_TOP_LEVEL = "__top__";
 
-type StoredCard = { id: number; question: string; answer: string; hint: string; skipped?: boolean; sub_collection_id?: number | null };
+type CardType = "basic" | "reverse" | "cloze" | "multiple_choice";
+type StoredCard = {
+  id: number;
+  question: string;
+  answer: string;
+  hint: string;
+  /** When the card was suspended; left out of study until unsuspended. */
+  suspended_at?: string;
+  /** When the card was buried; left out of study until the next study day. */
+  buried_at?: string;
+  /** When the card became a leech (kept being forgotten). */
+  leech_at?: string;
+  card_type: CardType;
+  /** Wrong answers of a multiple choice card. */
+  choices?: string[];
+  sub_collection_id?: number | null;
+};
+
+const CARD_TYPES: { value: CardType; label: string }[] = [
+  { value: "basic", label: "Basic" },
+  { value: "reverse", label: "Basic + reverse" },
+  { value: "cloze", label: "Cloze" },
+  { value: "multiple_choice", label: "Multiple choice" },
+];
 type StoredCollection = { id: number; name: string };
-type StoredSubCollection = { id: number; name: string; collection_id: number };
+type StoredSubCollection = {
+  id: number;
+  name: string;
+  collection_id: number;
+  parent_id: number | null;
+  /** e.g. "Chapter 1/Section 2" */
+  path: string;
+};
+type UndoEntry = { id: number; label: string; created_at: string };
+type UndoState = { undo: UndoEntry | null; redo: UndoEntry | null };
+type ConflictPolicy = "skip" | "rename" | "merge";
+type BulkOperationKind = "replace" | "set_hint" | "set_suspended" | "set_buried" | "set_sub_collection";
+type CardState = "suspended" | "buried";
+type BulkCardFields = {
+  question: string;
+  answer: string;
+  hint: string;
+  suspended: boolean;
+  buried: boolean;
+  sub_collection_id: number;
+};
+type BulkCardChange = { id: number; before: BulkCardFields; after: BulkCardFields };
+type BulkUpdateReport = { changes: BulkCardChange[]; applied: boolean };
+type TransferReport = { card_ids: number[]; renamed: number; merged: number; skipped: number[] };
 
 export function EditCards() {
   const [collections, setCollections] = useState<StoredCollection[]>([]);
@@ -42,6 +89,8 @@ export function EditCards() {
   const [editHint, setEditHint] = useState("");
   const [editQuestion, setEditQuestion] = useState("");
   const [editAnswer, setEditAnswer] = useState("");
+  const [editCardType, setEditCardType] = useState<CardType>("basic");
+  const [editChoices, setEditChoices] = useState("");
   const [editCollectionId, setEditCollectionId] = useState<string>("");
   const [editSubCollectionId, setEditSubCollectionId] = useState<string>("");
   const [editSubCollections, setEditSubCollections] = useState<StoredSubCollection[]>([]);
@@ -51,7 +100,7 @@ export function EditCards() {
   const [creatingSubCollection, setCreatingSubCollection] = useState(false);
   const [saving, setSaving] = useState(false);
   const [addingCopy, setAddingCopy] = useState(false);
-  const [clearingSkips, setClearingSkips] = useState(false);
+  const [clearingState, setClearingState] = useState(false);
   const [modalError, setModalError] = useState<string | null>(null);
   const [searchQuery, setSearchQuery] = useState("");
   const [editCollectionModalOpen, setEditCollectionModalOpen] = useState(false);
@@ -59,12 +108,47 @@ export function EditCards() {
   const [editCollectionSaving, setEditCollectionSaving] = useState(false);
   const [editCollectionDeleting, setEditCollectionDeleting] = useState(false);
   const [editCollectionError, setEditCollectionError] = useState<string | null>(null);
+  const [mergeTargetId, setMergeTargetId] = useState("");
+  const [splitSubCollectionIds, setSplitSubCollectionIds] = useState<Set<number>>(new Set());
+  const [splitName, setSplitName] = useState("");
+  const [reorganizing, setReorganizing] = useState(false);
   const [editSubCollectionModalOpen, setEditSubCollectionModalOpen] = useState(false);
   const [editingSubCollectionId, setEditingSubCollectionId] = useState<string | null>(null);
   const [editSubCollectionName, setEditSubCollectionName] = useState("");
+  const [editSubCollectionParentId, setEditSubCollectionParentId] = useState(SUB_COLLECTION_TOP_LEVEL);
   const [editSubCollectionSaving, setEditSubCollectionSaving] = useState(false);
   const [editSubCollectionDeleting, setEditSubCollectionDeleting] = useState(false);
   const [editSubCollectionError, setEditSubCollectionError] = useState<string | null>(null);
+  const [undoState, setUndoState] = useState<UndoState>({ undo: null, redo: null });
+  const [undoing, setUndoing] = useState(false);
+  const [selectedCardIds, setSelectedCardIds] = useState<Set<number>>(new Set());
+  const [transferCollectionId, setTransferCollectionId] = useState<string>("");
+  const [transferPolicy, setTransferPolicy] = useState<ConflictPolicy>("skip");
+  const [transferring, setTransferring] = useState(false);
+  const [transferMessage, setTransferMessage] = useState<string | null>(null);
+  const [bulkOpen, setBulkOpen] = useState(false);
+  const [bulkKind, setBulkKind] = useState<BulkOperationKind>("replace");
+  const [bulkField, setBulkField] = useState<"question" | "answer" | "hint">("question");
+  const [bulkPattern, setBulkPattern] = useState("");
+  const [bulkReplacement, setBulkReplacement] = useState("");
+  const [bulkIgnoreCase, setBulkIgnoreCase] = useState(false);
+  const [bulkHint, setBulkHint] = useState("");
+  /** Suspend or bury (rather than restore) with the state operations. */
+  const [bulkStateOn, setBulkStateOn] = useState(true);
+  const [bulkSubCollectionId, setBulkSubCollectionId] = useState("");
+  const [bulkPreview, setBulkPreview] = useState<BulkCardChange[] | null>(null);
+  const [bulkBusy, setBulkBusy] = useState(false);
+  const [bulkError, setBulkError] = useState<string | null>(null);
+
+  function refreshUndoState() {
+    invoke<UndoState>("get_undo_state")
+      .then(setUndoState)
+      .catch(() => {});
+  }
+
+  useEffect(() => {
+    refreshUndoState();
+  }, []);
 
   useEffect(() => {
     let cancelled = false;
@@ -78,7 +162,7 @@ export function EditCards() {
         }
       })
       .catch((e) => {
-        if (!cancelled) setError(e instanceof Error ? e.message : String(e));
+        if (!cancelled) setError(errorMessage(e));
       })
       .finally(() => {
         if (!cancelled) setLoading(false);
@@ -95,12 +179,14 @@ export function EditCards() {
     }
     let cancelled = false;
     setLoading(true);
+    setSelectedCardIds(new Set());
+    setTransferMessage(null);
     invoke<StoredCard[]>("get_cards", { collectionId: Number(selectedCollectionId) })
       .then((data) => {
         if (!cancelled) setCards(data);
       })
       .catch((e) => {
-        if (!cancelled) setError(e instanceof Error ? e.message : String(e));
+        if (!cancelled) setError(errorMessage(e));
       })
       .finally(() => {
         if (!cancelled) setLoading(false);
@@ -168,6 +254,8 @@ export function EditCards() {
     setEditHint(card.hint ?? "");
     setEditQuestion(card.question);
     setEditAnswer(card.answer);
+    setEditCardType(card.card_type);
+    setEditChoices((card.choices ?? []).join("\n"));
     setEditCollectionId(selectedCollectionId);
     setEditSubCollectionId(""); // Set from card after sub-collections load (in useEffect)
     setModalError(null);
@@ -178,6 +266,8 @@ export function EditCards() {
     setEditHint("");
     setEditQuestion("");
     setEditAnswer("");
+    setEditCardType("basic");
+    setEditChoices("");
     setEditCollectionId("");
     setEditSubCollectionId("");
     setEditCollectionAction("move");
@@ -203,6 +293,13 @@ export function EditCards() {
         hint: editHint.trim() || undefined,
         subCollectionId: editSubCollectionId ? Number(editSubCollectionId) : undefined,
       });
+      const choices = editChoices.split("\n").map((c) => c.trim()).filter(Boolean);
+      const typeChanged =
+        editCardType !== editingCard.card_type ||
+        (editCardType === "multiple_choice" && choices.join("\n") !== (editingCard.choices ?? []).join("\n"));
+      if (typeChanged) {
+        await invoke("set_card_type", { cardId: editingCard.id, cardType: editCardType, choices });
+      }
       const currentId = Number(selectedCollectionId);
       if (cid === currentId) {
         setCards((prev) =>
@@ -213,6 +310,8 @@ export function EditCards() {
                   hint: editHint.trim(),
                   question: editQuestion.trim(),
                   answer: editAnswer.trim(),
+                  card_type: editCardType,
+                  choices: editCardType === "multiple_choice" ? choices : undefined,
                   sub_collection_id: editSubCollectionId ? Number(editSubCollectionId) : null,
                 }
               : c
@@ -223,7 +322,7 @@ export function EditCards() {
       }
       closeEdit();
     } catch (e) {
-      setModalError(e instanceof Error ? e.message : String(e));
+      setModalError(errorMessage(e));
     } finally {
       setSaving(false);
     }
@@ -245,7 +344,7 @@ export function EditCards() {
       });
       closeEdit();
     } catch (e) {
-      setModalError(e instanceof Error ? e.message : String(e));
+      setModalError(errorMessage(e));
     } finally {
       setAddingCopy(false);
     }
@@ -269,51 +368,215 @@ export function EditCards() {
       setNewSubCollectionName("");
       setNewSubCollectionOpen(false);
     } catch (e) {
-      setModalError(e instanceof Error ? e.message : String(e));
+      setModalError(errorMessage(e));
     } finally {
       setCreatingSubCollection(false);
     }
   }
 
   async function handleDelete(card: StoredCard) {
-    if (!confirm("Delete this card?")) return;
+    if (!confirm("Move this card to the trash?")) return;
     setError(null);
     try {
       await invoke("delete_card", { id: card.id });
       setCards((prev) => prev.filter((c) => c.id !== card.id));
+      refreshUndoState();
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(errorMessage(e));
     }
   }
 
-  async function handleSkipChange(card: StoredCard, skipped: boolean) {
+  async function handleStateChange(card: StoredCard, state: CardState, on: boolean) {
     setError(null);
     try {
-      await invoke("set_card_skipped", { cardId: card.id, skipped });
+      if (state === "suspended") {
+        await invoke("set_card_suspended", { cardId: card.id, suspended: on });
+      } else {
+        await invoke("set_card_buried", { cardId: card.id, buried: on });
+      }
+      const since = on ? new Date().toISOString().slice(0, 19).replace("T", " ") : undefined;
       setCards((prev) =>
-        prev.map((c) => (c.id === card.id ? { ...c, skipped } : c))
+        prev.map((c) => (c.id === card.id ? { ...c, [`${state}_at`]: since } : c))
       );
+      refreshUndoState();
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(errorMessage(e));
+    }
+  }
+
+  function toggleCardSelected(cardId: number, selected: boolean) {
+    setSelectedCardIds((prev) => {
+      const next = new Set(prev);
+      if (selected) next.add(cardId);
+      else next.delete(cardId);
+      return next;
+    });
+  }
+
+  /** Move or copy the selected cards to the chosen collection, keeping their sub collection names. */
+  async function handleTransfer(command: "move_cards" | "copy_cards") {
+    const targetId = Number(transferCollectionId || selectedCollectionId);
+    if (selectedCardIds.size === 0 || Number.isNaN(targetId)) return;
+    setTransferring(true);
+    setError(null);
+    setTransferMessage(null);
+    try {
+      const report = await invoke<TransferReport>(command, {
+        cardIds: [...selectedCardIds],
+        targetCollectionId: targetId,
+        onConflict: transferPolicy,
+      });
+      const [subData, cardsData] = await Promise.all([
+        invoke<StoredSubCollection[]>("get_sub_collections", {
+          collectionId: Number(selectedCollectionId),
+        }),
+        invoke<StoredCard[]>("get_cards", { collectionId: Number(selectedCollectionId) }),
+      ]);
+      setSubCollections(subData);
+      setCards(cardsData);
+      setSelectedCardIds(new Set());
+      const done = report.card_ids.length - report.merged;
+      const verb = command === "move_cards" ? "Moved" : "Copied";
+      setTransferMessage(
+        `${verb} ${done} card${done !== 1 ? "s" : ""}` +
+          (report.renamed > 0 ? `, ${report.renamed} renamed` : "") +
+          (report.merged > 0 ? `, ${report.merged} merged into existing cards` : "") +
+          (report.skipped.length > 0 ? `, ${report.skipped.length} skipped as duplicates` : "") +
+          "."
+      );
+      refreshUndoState();
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setTransferring(false);
+    }
+  }
+
+  /** The cards a bulk edit applies to: the selected cards, or else everything the list shows. */
+  function bulkFilter() {
+    return {
+      collection_id: Number(selectedCollectionId),
+      sub_collection_id:
+        selectedSubCollectionId === SUB_COLLECTION_ALL ? null : Number(selectedSubCollectionId),
+      card_ids: selectedCardIds.size > 0 ? [...selectedCardIds] : null,
+      search: selectedCardIds.size > 0 ? null : searchQuery.trim() || null,
+    };
+  }
+
+  function bulkOperation() {
+    switch (bulkKind) {
+      case "replace":
+        return {
+          kind: "replace",
+          field: bulkField,
+          pattern: bulkPattern,
+          replacement: bulkReplacement,
+          ignore_case: bulkIgnoreCase,
+        };
+      case "set_hint":
+        return { kind: "set_hint", hint: bulkHint };
+      case "set_suspended":
+        return { kind: "set_suspended", suspended: bulkStateOn };
+      case "set_buried":
+        return { kind: "set_buried", buried: bulkStateOn };
+      case "set_sub_collection":
+        return { kind: "set_sub_collection", sub_collection_id: Number(bulkSubCollectionId) };
     }
   }
 
-  async function handleClearAllSkips() {
+  function openBulkEdit() {
+    setBulkPreview(null);
+    setBulkError(null);
+    setBulkOpen(true);
+  }
+
+  /** Preview the bulk edit, or apply it and reload the cards. */
+  async function runBulkEdit(preview: boolean) {
+    setBulkBusy(true);
+    setBulkError(null);
+    try {
+      const report = await invoke<BulkUpdateReport>("bulk_update_cards", {
+        filter: bulkFilter(),
+        operation: bulkOperation(),
+        preview,
+      });
+      if (preview) {
+        setBulkPreview(report.changes);
+        return;
+      }
+      const data = await invoke<StoredCard[]>("get_cards", {
+        collectionId: Number(selectedCollectionId),
+      });
+      setCards(data);
+      setSelectedCardIds(new Set());
+      setTransferMessage(
+        `Updated ${report.changes.length} card${report.changes.length !== 1 ? "s" : ""}.`
+      );
+      setBulkOpen(false);
+      refreshUndoState();
+    } catch (e) {
+      setBulkError(errorMessage(e));
+    } finally {
+      setBulkBusy(false);
+    }
+  }
+
+  function subCollectionName(id: number) {
+    return subCollections.find((s) => s.id === id)?.name ?? String(id);
+  }
+
+  /** The fields a bulk change touches, as "before → after" lines. */
+  function describeChange(change: BulkCardChange) {
+    const { before, after } = change;
+    const lines: { label: string; before: string; after: string }[] = [];
+    if (before.question !== after.question)
+      lines.push({ label: "Question", before: before.question, after: after.question });
+    if (before.answer !== after.answer)
+      lines.push({ label: "Answer", before: before.answer, after: after.answer });
+    if (before.hint !== after.hint)
+      lines.push({ label: "Hint", before: before.hint || "(none)", after: after.hint || "(none)" });
+    if (before.suspended !== after.suspended)
+      lines.push({
+        label: "Suspended",
+        before: before.suspended ? "yes" : "no",
+        after: after.suspended ? "yes" : "no",
+      });
+    if (before.buried !== after.buried)
+      lines.push({
+        label: "Buried",
+        before: before.buried ? "yes" : "no",
+        after: after.buried ? "yes" : "no",
+      });
+    if (before.sub_collection_id !== after.sub_collection_id)
+      lines.push({
+        label: "Sub collection",
+        before: subCollectionName(before.sub_collection_id),
+        after: subCollectionName(after.sub_collection_id),
+      });
+    return lines;
+  }
+
+  /** Unsuspend or unbury every card the sub collection filter shows. */
+  async function handleClearState(state: CardState) {
     if (!selectedCollectionId) return;
-    setClearingSkips(true);
+    setClearingState(true);
     setError(null);
     try {
-      await invoke("clear_skipped_for_collection", {
+      await invoke("clear_card_state", {
         collectionId: Number(selectedCollectionId),
+        subCollectionId:
+          selectedSubCollectionId === SUB_COLLECTION_ALL ? null : Number(selectedSubCollectionId),
+        state,
       });
       const data = await invoke<StoredCard[]>("get_cards", {
         collectionId: Number(selectedCollectionId),
       });
       setCards(data);
+      refreshUndoState();
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      setError(errorMessage(e));
     } finally {
-      setClearingSkips(false);
+      setClearingState(false);
     }
   }
 
@@ -322,6 +585,9 @@ export function EditCards() {
     if (coll) {
       setEditCollectionName(coll.name);
       setEditCollectionError(null);
+      setMergeTargetId("");
+      setSplitSubCollectionIds(new Set());
+      setSplitName("");
       setEditCollectionModalOpen(true);
     }
   }
@@ -347,7 +613,7 @@ export function EditCards() {
       setCollections(data);
       closeEditCollectionModal();
     } catch (e) {
-      setEditCollectionError(e instanceof Error ? e.message : String(e));
+      setEditCollectionError(errorMessage(e));
     } finally {
       setEditCollectionSaving(false);
     }
@@ -357,7 +623,7 @@ export function EditCards() {
     if (!selectedCollectionId) return;
     if (
       !confirm(
-        "Delete this collection and all its cards? This cannot be undone."
+        "Move this collection and all its cards to the trash?"
       )
     )
       return;
@@ -370,13 +636,64 @@ export function EditCards() {
       setCards([]);
       setSelectedCollectionId(data[0] ? String(data[0].id) : "");
       closeEditCollectionModal();
+      refreshUndoState();
     } catch (e) {
-      setEditCollectionError(e instanceof Error ? e.message : String(e));
+      setEditCollectionError(errorMessage(e));
     } finally {
       setEditCollectionDeleting(false);
     }
   }
 
+  async function handleMergeCollection() {
+    if (!selectedCollectionId || !mergeTargetId) return;
+    const target = collections.find((c) => String(c.id) === mergeTargetId);
+    if (
+      !confirm(
+        `Move every card into "${target?.name}" and put this collection in the trash? Cards already in "${target?.name}" are merged.`
+      )
+    )
+      return;
+    setReorganizing(true);
+    setEditCollectionError(null);
+    try {
+      await invoke("merge_collections", {
+        sourceId: Number(selectedCollectionId),
+        targetId: Number(mergeTargetId),
+      });
+      const data = await invoke<StoredCollection[]>("get_collections");
+      setCollections(data);
+      setSelectedCollectionId(mergeTargetId);
+      closeEditCollectionModal();
+      refreshUndoState();
+    } catch (e) {
+      setEditCollectionError(errorMessage(e));
+    } finally {
+      setReorganizing(false);
+    }
+  }
+
+  async function handleSplitCollection() {
+    if (!selectedCollectionId || splitSubCollectionIds.size === 0 || !splitName.trim()) return;
+    setReorganizing(true);
+    setEditCollectionError(null);
+    try {
+      const created = await invoke<StoredCollection>("split_collection", {
+        collectionId: Number(selectedCollectionId),
+        subCollectionIds: [...splitSubCollectionIds],
+        newName: splitName.trim(),
+      });
+      const data = await invoke<StoredCollection[]>("get_collections");
+      setCollections(data);
+      setSelectedCollectionId(String(created.id));
+      closeEditCollectionModal();
+      refreshUndoState();
+    } catch (e) {
+      setEditCollectionError(errorMessage(e));
+    } finally {
+      setReorganizing(false);
+    }
+  }
+
   const selectedSubCollection = subCollections.find(
     (s) => String(s.id) === selectedSubCollectionId
   );
@@ -388,6 +705,11 @@ export function EditCards() {
     if (!canEditSubCollection) return;
     setEditingSubCollectionId(selectedSubCollectionId);
     setEditSubCollectionName(selectedSubCollection!.name);
+    setEditSubCollectionParentId(
+      selectedSubCollection!.parent_id != null
+        ? String(selectedSubCollection!.parent_id)
+        : SUB_COLLECTION_TOP_LEVEL
+    );
     setEditSubCollectionError(null);
     setEditSubCollectionModalOpen(true);
   }
@@ -410,13 +732,20 @@ export function EditCards() {
         id: Number(editingSubCollectionId),
         name,
       });
+      const editing = subCollections.find((s) => String(s.id) === editingSubCollectionId);
+      const parentId =
+        editSubCollectionParentId === SUB_COLLECTION_TOP_LEVEL ? null : Number(editSubCollectionParentId);
+      if (editing && editing.parent_id !== parentId) {
+        await invoke("move_sub_collection", { id: editing.id, parentId });
+        refreshUndoState();
+      }
       const data = await invoke<StoredSubCollection[]>("get_sub_collections", {
         collectionId: Number(selectedCollectionId),
       });
       setSubCollections(data);
       closeEditSubCollectionModal();
     } catch (e) {
-      setEditSubCollectionError(e instanceof Error ? e.message : String(e));
+      setEditSubCollectionError(errorMessage(e));
     } finally {
       setEditSubCollectionSaving(false);
     }
@@ -426,7 +755,7 @@ export function EditCards() {
     if (!editingSubCollectionId) return;
     if (
       !confirm(
-        "Delete this sub collection? Its cards will be moved to the default (None) sub collection."
+        "Delete this sub collection and the sub collections inside it? Their cards will be moved to the default (None) sub collection."
       )
     )
       return;
@@ -446,13 +775,68 @@ export function EditCards() {
       setCards(cardsData);
       setSelectedSubCollectionId(SUB_COLLECTION_ALL);
       closeEditSubCollectionModal();
+      refreshUndoState();
     } catch (e) {
-      setEditSubCollectionError(e instanceof Error ? e.message : String(e));
+      setEditSubCollectionError(errorMessage(e));
     } finally {
       setEditSubCollectionDeleting(false);
     }
   }
 
+  /** Undo or redo the last destructive operation, then reload everything it may have touched. */
+  async function handleUndoRedo(command: "undo_last" | "redo") {
+    setUndoing(true);
+    setError(null);
+    try {
+      await invoke<UndoEntry | null>(command);
+      const data = await invoke<StoredCollection[]>("get_collections");
+      setCollections(data);
+      const keep = data.some((c) => String(c.id) === selectedCollectionId);
+      const nextId = keep ? selectedCollectionId : data[0] ? String(data[0].id) : "";
+      if (nextId !== selectedCollectionId) {
+        // The collection effects load its cards and sub collections.
+        setSelectedCollectionId(nextId);
+      } else if (nextId) {
+        const [subData, cardsData] = await Promise.all([
+          invoke<StoredSubCollection[]>("get_sub_collections", { collectionId: Number(nextId) }),
+          invoke<StoredCard[]>("get_cards", { collectionId: Number(nextId) }),
+        ]);
+        setSubCollections(subData);
+        setCards(cardsData);
+      }
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setUndoing(false);
+      refreshUndoState();
+    }
+  }
+
+  const undoRedoButtons = (
+    <div className="flex gap-2">
+      <Button
+        type="button"
+        variant="outline"
+        size="sm"
+        onClick={() => handleUndoRedo("undo_last")}
+        disabled={undoing || !undoState.undo}
+        title={undoState.undo ? `Undo: ${undoState.undo.label}` : "Nothing to undo"}
+      >
+        Undo
+      </Button>
+      <Button
+        type="button"
+        variant="outline"
+        size="sm"
+        onClick={() => handleUndoRedo("redo")}
+        disabled={undoing || !undoState.redo}
+        title={undoState.redo ? `Redo: ${undoState.redo.label}` : "Nothing to redo"}
+      >
+        Redo
+      </Button>
+    </div>
+  );
+
   if (loading && collections.length === 0) {
     return (
       <div className="flex flex-1 flex-col gap-6 p-6">
@@ -477,13 +861,15 @@ export function EditCards() {
     );
   }
 
-  const cardsInSub =
+  const selectedSubtree =
     selectedSubCollectionId === SUB_COLLECTION_ALL
+      ? null
+      : subCollectionSubtree(subCollections, Number(selectedSubCollectionId));
+  const cardsInSub =
+    selectedSubtree == null
       ? cards
       : cards.filter(
-          (c) =>
-            c.sub_collection_id != null &&
-            c.sub_collection_id === Number(selectedSubCollectionId)
+          (c) => c.sub_collection_id != null && selectedSubtree.has(c.sub_collection_id)
         );
   const searchLower = searchQuery.trim().toLowerCase();
   const filteredCards =
@@ -506,10 +892,11 @@ export function EditCards() {
               Edit or delete flashcards in a collection.
             </CardDescription>
           </CardHeader>
-          <CardContent>
+          <CardContent className="space-y-4">
             <p className="text-muted-foreground text-sm">
               No collections yet. Create one on the Create Cards page.
             </p>
+            {undoState.undo && undoRedoButtons}
           </CardContent>
         </Card>
       </div>
@@ -520,10 +907,15 @@ export function EditCards() {
     <div className="flex h-full min-h-0 flex-col overflow-hidden p-6">
       {/* Header area — always visible */}
       <div className="shrink-0 pb-4">
-        <h2 className="text-2xl font-semibold tracking-tight">Edit Cards</h2>
-        <p className="text-muted-foreground text-sm">
-          Edit or delete flashcards in a collection.
-        </p>
+        <div className="flex items-start justify-between gap-4">
+          <div>
+            <h2 className="text-2xl font-semibold tracking-tight">Edit Cards</h2>
+            <p className="text-muted-foreground text-sm">
+              Edit or delete flashcards in a collection.
+            </p>
+          </div>
+          {undoRedoButtons}
+        </div>
 
         <div className="mt-4 flex flex-wrap items-end gap-4">
           <div className="flex flex-none items-end gap-2">
@@ -565,7 +957,7 @@ export function EditCards() {
                   <SelectItem value={SUB_COLLECTION_ALL}>All</SelectItem>
                   {subCollections.map((s) => (
                     <SelectItem key={s.id} value={String(s.id)}>
-                      {s.name}
+                      {s.path}
                     </SelectItem>
                   ))}
                 </SelectContent>
@@ -583,11 +975,29 @@ export function EditCards() {
             <Button
               type="button"
               variant="outline"
-              onClick={handleClearAllSkips}
-              disabled={!selectedCollectionId || clearingSkips}
+              onClick={() => handleClearState("suspended")}
+              disabled={!selectedCollectionId || clearingState}
+              className="shrink-0"
+            >
+              Unsuspend All
+            </Button>
+            <Button
+              type="button"
+              variant="outline"
+              onClick={() => handleClearState("buried")}
+              disabled={!selectedCollectionId || clearingState}
               className="shrink-0"
             >
-              {clearingSkips ? "Clearing…" : "Clear All Skips"}
+              Unbury All
+            </Button>
+            <Button
+              type="button"
+              variant="outline"
+              onClick={openBulkEdit}
+              disabled={!selectedCollectionId || cards.length === 0}
+              className="shrink-0"
+            >
+              Bulk Edit
             </Button>
           </div>
           <div className="grid w-full max-w-xs gap-2">
@@ -603,6 +1013,75 @@ export function EditCards() {
           </div>
         </div>
 
+        {selectedCardIds.size > 0 && (
+          <div className="mt-4 flex flex-wrap items-end gap-2 rounded-md border p-3">
+            <p className="self-center text-sm">
+              {selectedCardIds.size} selected
+            </p>
+            <div className="grid w-48 gap-2">
+              <Label>To collection</Label>
+              <Select
+                value={transferCollectionId || selectedCollectionId}
+                onValueChange={setTransferCollectionId}
+              >
+                <SelectTrigger className="w-full">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  {collections.map((c) => (
+                    <SelectItem key={c.id} value={String(c.id)}>
+                      {c.name}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+            </div>
+            <div className="grid w-48 gap-2">
+              <Label>If already there</Label>
+              <Select
+                value={transferPolicy}
+                onValueChange={(v) => setTransferPolicy(v as ConflictPolicy)}
+              >
+                <SelectTrigger className="w-full">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="skip">Skip the card</SelectItem>
+                  <SelectItem value="rename">Rename the card</SelectItem>
+                  <SelectItem value="merge">Merge into existing</SelectItem>
+                </SelectContent>
+              </Select>
+            </div>
+            <Button
+              type="button"
+              variant="outline"
+              onClick={() => handleTransfer("move_cards")}
+              disabled={transferring}
+            >
+              Move
+            </Button>
+            <Button
+              type="button"
+              variant="outline"
+              onClick={() => handleTransfer("copy_cards")}
+              disabled={transferring}
+            >
+              Copy
+            </Button>
+            <Button
+              type="button"
+              variant="ghost"
+              onClick={() => setSelectedCardIds(new Set())}
+              disabled={transferring}
+            >
+              Clear selection
+            </Button>
+          </div>
+        )}
+
+        {transferMessage && (
+          <p className="mt-2 text-muted-foreground text-sm">{transferMessage}</p>
+        )}
         {error && (
           <p className="mt-2 text-destructive text-sm">{error}</p>
         )}
@@ -628,7 +1107,24 @@ export function EditCards() {
                   key={card.id}
                   className="flex flex-col gap-2 rounded-lg border bg-card p-3"
                 >
-                  <p className="line-clamp-2 text-sm font-medium">{card.question}</p>
+                  <label className="flex cursor-pointer items-start gap-2">
+                    <input
+                      type="checkbox"
+                      checked={selectedCardIds.has(card.id)}
+                      onChange={(e) => toggleCardSelected(card.id, e.target.checked)}
+                      className="mt-0.5 h-4 w-4 shrink-0 rounded border-input"
+                      aria-label="Select card"
+                    />
+                    <p className="line-clamp-2 text-sm font-medium">{card.question}</p>
+                    {card.leech_at && (
+                      <span
+                        className="ml-auto shrink-0 rounded bg-destructive/15 px-1.5 py-0.5 text-xs text-destructive"
+                        title={`Leech since ${card.leech_at}`}
+                      >
+                        Leech
+                      </span>
+                    )}
+                  </label>
                   <p className="line-clamp-1 text-muted-foreground text-xs">
                     {card.answer}
                   </p>
@@ -649,16 +1145,33 @@ export function EditCards() {
                     >
                       Delete
                     </Button>
-                    <label className="flex cursor-pointer items-center gap-2 text-sm">
+                    <label
+                      className="flex cursor-pointer items-center gap-2 text-sm"
+                      title={card.suspended_at ? `Suspended since ${card.suspended_at}` : undefined}
+                    >
+                      <input
+                        type="checkbox"
+                        checked={card.suspended_at != null}
+                        onChange={(e) =>
+                          handleStateChange(card, "suspended", e.target.checked)
+                        }
+                        className="h-4 w-4 rounded border-input"
+                      />
+                      <span>Suspend</span>
+                    </label>
+                    <label
+                      className="flex cursor-pointer items-center gap-2 text-sm"
+                      title={card.buried_at ? `Buried since ${card.buried_at}` : undefined}
+                    >
                       <input
                         type="checkbox"
-                        checked={card.skipped ?? false}
+                        checked={card.buried_at != null}
                         onChange={(e) =>
-                          handleSkipChange(card, e.target.checked)
+                          handleStateChange(card, "buried", e.target.checked)
                         }
                         className="h-4 w-4 rounded border-input"
                       />
-                      <span>Skip</span>
+                      <span>Bury</span>
                     </label>
                   </div>
                 </li>
@@ -730,7 +1243,7 @@ export function EditCards() {
                 <SelectContent>
                   {editSubCollections.map((s) => (
                     <SelectItem key={s.id} value={String(s.id)}>
-                      {s.name}
+                      {s.path}
                     </SelectItem>
                   ))}
                 </SelectContent>
@@ -793,17 +1306,33 @@ export function EditCards() {
               />
             </div>
             <div className="grid gap-2">
-              <Label htmlFor="edit-question">Question</Label>
+              <Label>Type</Label>
+              <Select value={editCardType} onValueChange={(v) => setEditCardType(v as CardType)}>
+                <SelectTrigger className="w-full">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  {CARD_TYPES.map((t) => (
+                    <SelectItem key={t.value} value={t.value}>
+                      {t.label}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="edit-question">{editCardType === "cloze" ? "Text" : "Question"}</Label>
               <Textarea
                 id="edit-question"
                 value={editQuestion}
                 onChange={(e) => setEditQuestion(e.target.value)}
                 rows={3}
                 className="min-h-[4.5rem] resize-y"
+                placeholder={editCardType === "cloze" ? "e.g. The capital of France is {{c1::Paris}}." : undefined}
               />
             </div>
             <div className="grid gap-2">
-              <Label htmlFor="edit-answer">Answer</Label>
+              <Label htmlFor="edit-answer">{editCardType === "cloze" ? "Extra (optional)" : "Answer"}</Label>
               <Textarea
                 id="edit-answer"
                 value={editAnswer}
@@ -812,6 +1341,18 @@ export function EditCards() {
                 className="min-h-[4.5rem] resize-y"
               />
             </div>
+            {editCardType === "multiple_choice" && (
+              <div className="grid gap-2">
+                <Label htmlFor="edit-choices">Wrong answers (one per line)</Label>
+                <Textarea
+                  id="edit-choices"
+                  value={editChoices}
+                  onChange={(e) => setEditChoices(e.target.value)}
+                  rows={3}
+                  className="min-h-[4.5rem] resize-y"
+                />
+              </div>
+            )}
           </div>
           {modalError && (
             <p className="text-destructive text-sm">{modalError}</p>
@@ -824,7 +1365,7 @@ export function EditCards() {
               type="button"
               disabled={
                 !editQuestion.trim() ||
-                !editAnswer.trim() ||
+                (editCardType !== "cloze" && !editAnswer.trim()) ||
                 !editCollectionId ||
                 (selectedCollectionIsDifferent ? (editCollectionAction === "move" ? saving : addingCopy) : saving)
               }
@@ -840,6 +1381,201 @@ export function EditCards() {
         </DialogContent>
       </Dialog>
 
+      <Dialog open={bulkOpen} onOpenChange={(open) => !open && setBulkOpen(false)}>
+        <DialogContent className="sm:max-w-2xl">
+          <DialogHeader>
+            <DialogTitle>Bulk Edit</DialogTitle>
+          </DialogHeader>
+          <div className="grid gap-4 py-2">
+            <p className="text-muted-foreground text-sm">
+              {selectedCardIds.size > 0
+                ? `Applies to the ${selectedCardIds.size} selected card${selectedCardIds.size !== 1 ? "s" : ""}.`
+                : "Applies to every card shown in the list."}
+            </p>
+            <div className="grid gap-2">
+              <Label>Operation</Label>
+              <Select
+                value={bulkKind}
+                onValueChange={(v) => {
+                  setBulkKind(v as BulkOperationKind);
+                  setBulkPreview(null);
+                }}
+              >
+                <SelectTrigger className="w-full">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="replace">Find and replace</SelectItem>
+                  <SelectItem value="set_hint">Set hint</SelectItem>
+                  <SelectItem value="set_suspended">Suspend or unsuspend</SelectItem>
+                  <SelectItem value="set_buried">Bury or unbury</SelectItem>
+                  <SelectItem value="set_sub_collection">Move to sub collection</SelectItem>
+                </SelectContent>
+              </Select>
+            </div>
+            {bulkKind === "replace" && (
+              <>
+                <div className="grid gap-2">
+                  <Label>Field</Label>
+                  <Select
+                    value={bulkField}
+                    onValueChange={(v) => {
+                      setBulkField(v as "question" | "answer" | "hint");
+                      setBulkPreview(null);
+                    }}
+                  >
+                    <SelectTrigger className="w-full">
+                      <SelectValue />
+                    </SelectTrigger>
+                    <SelectContent>
+                      <SelectItem value="question">Question</SelectItem>
+                      <SelectItem value="answer">Answer</SelectItem>
+                      <SelectItem value="hint">Hint</SelectItem>
+                    </SelectContent>
+                  </Select>
+                </div>
+                <div className="grid gap-2">
+                  <Label htmlFor="bulk-pattern">Find (regular expression)</Label>
+                  <Input
+                    id="bulk-pattern"
+                    value={bulkPattern}
+                    onChange={(e) => {
+                      setBulkPattern(e.target.value);
+                      setBulkPreview(null);
+                    }}
+                  />
+                </div>
+                <div className="grid gap-2">
+                  <Label htmlFor="bulk-replacement">Replace with ($1 for groups)</Label>
+                  <Input
+                    id="bulk-replacement"
+                    value={bulkReplacement}
+                    onChange={(e) => {
+                      setBulkReplacement(e.target.value);
+                      setBulkPreview(null);
+                    }}
+                  />
+                </div>
+                <label className="flex cursor-pointer items-center gap-2 text-sm">
+                  <input
+                    type="checkbox"
+                    checked={bulkIgnoreCase}
+                    onChange={(e) => {
+                      setBulkIgnoreCase(e.target.checked);
+                      setBulkPreview(null);
+                    }}
+                    className="h-4 w-4 rounded border-input"
+                  />
+                  <span>Ignore case</span>
+                </label>
+              </>
+            )}
+            {bulkKind === "set_hint" && (
+              <div className="grid gap-2">
+                <Label htmlFor="bulk-hint">Hint (empty clears it)</Label>
+                <Input
+                  id="bulk-hint"
+                  value={bulkHint}
+                  onChange={(e) => {
+                    setBulkHint(e.target.value);
+                    setBulkPreview(null);
+                  }}
+                />
+              </div>
+            )}
+            {(bulkKind === "set_suspended" || bulkKind === "set_buried") && (
+              <label className="flex cursor-pointer items-center gap-2 text-sm">
+                <input
+                  type="checkbox"
+                  checked={bulkStateOn}
+                  onChange={(e) => {
+                    setBulkStateOn(e.target.checked);
+                    setBulkPreview(null);
+                  }}
+                  className="h-4 w-4 rounded border-input"
+                />
+                <span>{bulkKind === "set_suspended" ? "Suspend the cards" : "Bury the cards"}</span>
+              </label>
+            )}
+            {bulkKind === "set_sub_collection" && (
+              <div className="grid gap-2">
+                <Label>Sub collection</Label>
+                <Select
+                  value={bulkSubCollectionId}
+                  onValueChange={(v) => {
+                    setBulkSubCollectionId(v);
+                    setBulkPreview(null);
+                  }}
+                >
+                  <SelectTrigger className="w-full">
+                    <SelectValue placeholder="Sub collection…" />
+                  </SelectTrigger>
+                  <SelectContent>
+                    {subCollections.map((s) => (
+                      <SelectItem key={s.id} value={String(s.id)}>
+                        {s.path}
+                      </SelectItem>
+                    ))}
+                  </SelectContent>
+                </Select>
+              </div>
+            )}
+            {bulkPreview != null &&
+              (bulkPreview.length === 0 ? (
+                <p className="text-muted-foreground text-sm">No cards would change.</p>
+              ) : (
+                <div className="grid gap-2">
+                  <p className="text-sm font-medium">
+                    {bulkPreview.length} card{bulkPreview.length !== 1 ? "s" : ""} will change
+                  </p>
+                  <ScrollArea className="h-64 rounded-md border">
+                    <ul className="flex flex-col divide-y">
+                      {bulkPreview.map((change) => (
+                        <li key={change.id} className="grid gap-1 p-2 text-xs">
+                          {describeChange(change).map((line) => (
+                            <div key={line.label}>
+                              <span className="text-muted-foreground">{line.label}: </span>
+                              <span className="line-through">{line.before}</span>
+                              {" → "}
+                              <span>{line.after}</span>
+                            </div>
+                          ))}
+                        </li>
+                      ))}
+                    </ul>
+                  </ScrollArea>
+                </div>
+              ))}
+            {bulkError && <p className="text-destructive text-sm">{bulkError}</p>}
+          </div>
+          <DialogFooter>
+            <Button
+              type="button"
+              variant="outline"
+              onClick={() => setBulkOpen(false)}
+              disabled={bulkBusy}
+            >
+              Cancel
+            </Button>
+            <Button
+              type="button"
+              variant="outline"
+              onClick={() => runBulkEdit(true)}
+              disabled={bulkBusy || (bulkKind === "set_sub_collection" && !bulkSubCollectionId)}
+            >
+              Preview
+            </Button>
+            <Button
+              type="button"
+              onClick={() => runBulkEdit(false)}
+              disabled={bulkBusy || bulkPreview == null || bulkPreview.length === 0}
+            >
+              {bulkBusy ? "Applying…" : "Apply"}
+            </Button>
+          </DialogFooter>
+        </DialogContent>
+      </Dialog>
+
       <Dialog
         open={editCollectionModalOpen}
         onOpenChange={(open) => !open && closeEditCollectionModal()}
@@ -861,6 +1597,73 @@ export function EditCards() {
                 placeholder="Collection name"
               />
             </div>
+            <div className="grid gap-2 border-t pt-4">
+              <Label>Merge into another collection</Label>
+              <div className="flex gap-2">
+                <Select value={mergeTargetId} onValueChange={setMergeTargetId}>
+                  <SelectTrigger className="w-full min-w-0">
+                    <SelectValue placeholder="Collection…" />
+                  </SelectTrigger>
+                  <SelectContent>
+                    {collections
+                      .filter((c) => String(c.id) !== selectedCollectionId)
+                      .map((c) => (
+                        <SelectItem key={c.id} value={String(c.id)}>
+                          {c.name}
+                        </SelectItem>
+                      ))}
+                  </SelectContent>
+                </Select>
+                <Button
+                  type="button"
+                  variant="outline"
+                  onClick={handleMergeCollection}
+                  disabled={!mergeTargetId || reorganizing}
+                  className="shrink-0"
+                >
+                  Merge
+                </Button>
+              </div>
+            </div>
+            <div className="grid gap-2 border-t pt-4">
+              <Label>Split sub collections into a new collection</Label>
+              <div className="flex flex-wrap gap-x-4 gap-y-1">
+                {subCollections.map((s) => (
+                  <label key={s.id} className="flex cursor-pointer items-center gap-2 text-sm">
+                    <input
+                      type="checkbox"
+                      checked={splitSubCollectionIds.has(s.id)}
+                      onChange={(e) =>
+                        setSplitSubCollectionIds((prev) => {
+                          const next = new Set(prev);
+                          if (e.target.checked) next.add(s.id);
+                          else next.delete(s.id);
+                          return next;
+                        })
+                      }
+                      className="h-4 w-4 rounded border-input"
+                    />
+                    <span>{s.path}</span>
+                  </label>
+                ))}
+              </div>
+              <div className="flex gap-2">
+                <Input
+                  value={splitName}
+                  onChange={(e) => setSplitName(e.target.value)}
+                  placeholder="New collection name"
+                />
+                <Button
+                  type="button"
+                  variant="outline"
+                  onClick={handleSplitCollection}
+                  disabled={splitSubCollectionIds.size === 0 || !splitName.trim() || reorganizing}
+                  className="shrink-0"
+                >
+                  Split
+                </Button>
+              </div>
+            </div>
             {editCollectionError && (
               <p className="text-destructive text-sm">{editCollectionError}</p>
             )}
@@ -920,6 +1723,29 @@ export function EditCards() {
                 placeholder="Sub collection name"
               />
             </div>
+            <div className="grid gap-2">
+              <Label>Inside</Label>
+              <Select value={editSubCollectionParentId} onValueChange={setEditSubCollectionParentId}>
+                <SelectTrigger className="w-full">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value={SUB_COLLECTION_TOP_LEVEL}>Top level</SelectItem>
+                  {subCollections
+                    .filter(
+                      (s) =>
+                        s.name !== "- None -" &&
+                        editingSubCollectionId != null &&
+                        !subCollectionSubtree(subCollections, Number(editingSubCollectionId)).has(s.id)
+                    )
+                    .map((s) => (
+                      <SelectItem key={s.id} value={String(s.id)}>
+                        {s.path}
+                      </SelectItem>
+                    ))}
+                </SelectContent>
+              </Select>
+            </div>
             {editSubCollectionError && (
               <p className="text-destructive text-sm">{editSubCollectionError}</p>
             )}
@@ -18,6 +18,7 @@ import {
   DialogTitle,
 } from "@/components/ui/dialog";
 import { Input } from "@/components/ui/input";
+import { errorMessage } from "@/lib/utils";
 
 function sanitizeFilename(name: string): string {
   return name.replace(/[/\\:*?"<>|]/g, "-").trim() || "collection";
@@ -27,6 +28,24 @@ type StoredCollection = { id: number; name: string };
 
 type FileCollectionSummary = { name: string; card_count: number; sub_collection_count: number };
 
+type NotesImportResult = {
+  notes: number;
+  cards_added: number;
+  cards_updated: number;
+  cards_unchanged: number;
+  cards_missing_from_notes: number;
+  errors: string[];
+};
+
+type BackupInfo = { id: string; created_at: string; reason: string; size: number };
+
+type DatabaseReport = {
+  integrity_errors: string[];
+  issues: { kind: string; description: string; ids: string[] }[];
+  repaired: boolean;
+  trashed_duplicates: number;
+};
+
 type ImportRowState = {
   selected: boolean;
   destinationMode: "existing" | "new";
@@ -49,6 +68,18 @@ export function ImportExport() {
   const [fileCollections, setFileCollections] = useState<FileCollectionSummary[]>([]);
   const [importRows, setImportRows] = useState<ImportRowState[]>([]);
   const [importModalError, setImportModalError] = useState<string | null>(null);
+  const [notesCollectionId, setNotesCollectionId] = useState<string>("new");
+  const [notesNewName, setNotesNewName] = useState("");
+  const [notesImporting, setNotesImporting] = useState(false);
+  const [notesError, setNotesError] = useState<string | null>(null);
+  const [notesResult, setNotesResult] = useState<NotesImportResult | null>(null);
+  const [backups, setBackups] = useState<BackupInfo[]>([]);
+  const [restoringBackupId, setRestoringBackupId] = useState<string | null>(null);
+  const [backupError, setBackupError] = useState<string | null>(null);
+  const [backupSuccess, setBackupSuccess] = useState<string | null>(null);
+  const [databaseReport, setDatabaseReport] = useState<DatabaseReport | null>(null);
+  const [databaseBusy, setDatabaseBusy] = useState(false);
+  const [databaseError, setDatabaseError] = useState<string | null>(null);
 
   const isExportAll = selectedCollectionId === "all";
   const selectedCollection = collections.find((c) => String(c.id) === selectedCollectionId);
@@ -85,6 +116,50 @@ export function ImportExport() {
     };
   }, []);
 
+  useEffect(() => {
+    invoke<BackupInfo[]>("list_backups")
+      .then(setBackups)
+      .catch(() => {});
+  }, []);
+
+  async function handleRestoreBackup(backup: BackupInfo) {
+    if (
+      !confirm(
+        `Replace all collections and cards with the backup from ${backup.created_at} UTC? The current data is backed up first.`
+      )
+    )
+      return;
+    setRestoringBackupId(backup.id);
+    setBackupError(null);
+    setBackupSuccess(null);
+    try {
+      await invoke("restore_backup", { id: backup.id });
+      setBackupSuccess(`Restored the backup from ${backup.created_at} UTC.`);
+      setCollections(await invoke<StoredCollection[]>("get_collections"));
+    } catch (e) {
+      setBackupError(errorMessage(e));
+    } finally {
+      setRestoringBackupId(null);
+      invoke<BackupInfo[]>("list_backups").then(setBackups).catch(() => {});
+    }
+  }
+
+  async function handleDatabaseCommand(command: "check_database" | "repair_database") {
+    setDatabaseBusy(true);
+    setDatabaseError(null);
+    try {
+      setDatabaseReport(await invoke<DatabaseReport>(command));
+      if (command === "repair_database") {
+        setCollections(await invoke<StoredCollection[]>("get_collections"));
+        invoke<BackupInfo[]>("list_backups").then(setBackups).catch(() => {});
+      }
+    } catch (e) {
+      setDatabaseError(errorMessage(e));
+    } finally {
+      setDatabaseBusy(false);
+    }
+  }
+
   async function handleExport() {
     setExportError(null);
     setExportSuccess(false);
@@ -95,7 +170,7 @@ export function ImportExport() {
         filters: [{ name: "JSON", extensions: ["json"] }],
       });
     } catch (e) {
-      setExportError(e instanceof Error ? e.message : String(e));
+      setExportError(errorMessage(e));
       return;
     }
     if (filePath == null) return;
@@ -109,7 +184,7 @@ export function ImportExport() {
       }
       setExportSuccess(true);
     } catch (e) {
-      setExportError(e instanceof Error ? e.message : String(e));
+      setExportError(errorMessage(e));
     } finally {
       setExporting(false);
     }
@@ -126,7 +201,7 @@ export function ImportExport() {
         filters: [{ name: "JSON", extensions: ["json"] }],
       });
     } catch (e) {
-      setImportError(e instanceof Error ? e.message : String(e));
+      setImportError(errorMessage(e));
       return;
     }
     if (filePath == null || Array.isArray(filePath)) return;
@@ -162,7 +237,7 @@ export function ImportExport() {
       );
       setImportModalOpen(true);
     } catch (e) {
-      setImportError(e instanceof Error ? e.message : String(e));
+      setImportError(errorMessage(e));
     } finally {
       setImporting(false);
     }
@@ -227,12 +302,50 @@ export function ImportExport() {
       );
       invoke<StoredCollection[]>("get_collections").then(setCollections);
     } catch (e) {
-      setImportModalError(e instanceof Error ? e.message : String(e));
+      setImportModalError(errorMessage(e));
     } finally {
       setImporting(false);
     }
   }
 
+  async function handleImportNotes() {
+    setNotesError(null);
+    setNotesResult(null);
+    if (notesCollectionId === "new" && !notesNewName.trim()) {
+      setNotesError("Enter a name for the new collection.");
+      return;
+    }
+    let directory: string | null = null;
+    try {
+      directory = await invoke<string | null>("pick_directory");
+    } catch (e) {
+      setNotesError(errorMessage(e));
+      return;
+    }
+    if (directory == null) return;
+    setNotesImporting(true);
+    try {
+      const result = await invoke<NotesImportResult>("import_notes_directory", {
+        directory,
+        destinationCollectionId: notesCollectionId === "new" ? undefined : Number(notesCollectionId),
+        destinationNewName: notesCollectionId === "new" ? notesNewName.trim() : undefined,
+      });
+      setNotesResult(result);
+      const updated = await invoke<StoredCollection[]>("get_collections");
+      setCollections(updated);
+      // Re-syncing the same folder later should target the collection we just filled.
+      if (notesCollectionId === "new") {
+        const created = updated.find((c) => c.name === notesNewName.trim());
+        if (created) setNotesCollectionId(String(created.id));
+        setNotesNewName("");
+      }
+    } catch (e) {
+      setNotesError(errorMessage(e));
+    } finally {
+      setNotesImporting(false);
+    }
+  }
+
   return (
     <div className="container max-w-2xl py-6">
       <Card>
@@ -299,6 +412,148 @@ export function ImportExport() {
               <p className="text-sm text-green-600 dark:text-green-400">{importSuccess}</p>
             )}
           </div>
+          <div className="space-y-2">
+            <h3 className="font-medium">Markdown notes</h3>
+            <p className="text-sm text-muted-foreground">
+              Import cards written in notes as <code>Question :: Answer</code>, or question lines, a
+              line with only <code>?</code>, then answer lines. Folders become sub collections.
+              Importing the same folder again updates cards edited in the notes.
+            </p>
+            <div className="flex flex-wrap items-center gap-3">
+              <Select value={notesCollectionId} onValueChange={setNotesCollectionId}>
+                <SelectTrigger className="w-[200px]">
+                  <SelectValue placeholder="Collection" />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="new">New collection…</SelectItem>
+                  {collections.map((c) => (
+                    <SelectItem key={c.id} value={String(c.id)}>
+                      {c.name}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+              {notesCollectionId === "new" && (
+                <Input
+                  className="w-[200px]"
+                  placeholder="Collection name"
+                  value={notesNewName}
+                  onChange={(e) => setNotesNewName(e.target.value)}
+                />
+              )}
+              <Button variant="outline" onClick={handleImportNotes} disabled={notesImporting}>
+                {notesImporting ? "Importing…" : "Import notes folder"}
+              </Button>
+            </div>
+            {notesError != null && <p className="text-sm text-destructive">{notesError}</p>}
+            {notesResult != null && (
+              <div className="text-sm">
+                <p className="text-green-600 dark:text-green-400">
+                  {notesResult.notes} note(s): {notesResult.cards_added} added, {notesResult.cards_updated}{" "}
+                  updated, {notesResult.cards_unchanged} unchanged.
+                </p>
+                {notesResult.cards_missing_from_notes > 0 && (
+                  <p className="text-muted-foreground">
+                    {notesResult.cards_missing_from_notes} card(s) from earlier imports are no longer in
+                    the notes and were kept.
+                  </p>
+                )}
+                {notesResult.errors.map((error) => (
+                  <p key={error} className="text-destructive">
+                    {error}
+                  </p>
+                ))}
+              </div>
+            )}
+          </div>
+          <div className="space-y-2">
+            <h3 className="font-medium">Backups</h3>
+            <p className="text-sm text-muted-foreground">
+              The database is backed up automatically at startup and before imports, purging the
+              trash and deleting sub collections. The 10 most recent backups are kept.
+            </p>
+            {backups.length === 0 ? (
+              <p className="text-sm text-muted-foreground">No backups yet.</p>
+            ) : (
+              <ul className="divide-y rounded-md border">
+                {backups.map((backup) => (
+                  <li key={backup.id} className="flex items-center justify-between gap-4 p-2 text-sm">
+                    <span>
+                      {backup.created_at} UTC · {backup.reason} · {Math.ceil(backup.size / 1024)} KB
+                    </span>
+                    <Button
+                      size="sm"
+                      variant="outline"
+                      disabled={restoringBackupId != null}
+                      onClick={() => handleRestoreBackup(backup)}
+                    >
+                      {restoringBackupId === backup.id ? "Restoring…" : "Restore"}
+                    </Button>
+                  </li>
+                ))}
+              </ul>
+            )}
+            {backupError != null && <p className="text-sm text-destructive">{backupError}</p>}
+            {backupSuccess != null && (
+              <p className="text-sm text-green-600 dark:text-green-400">{backupSuccess}</p>
+            )}
+          </div>
+          <div className="space-y-2">
+            <h3 className="font-medium">Database check</h3>
+            <p className="text-sm text-muted-foreground">
+              Look for damaged data and cards filed in the wrong place. Repairing takes a backup first.
+            </p>
+            <div className="flex flex-wrap items-center gap-3">
+              <Button
+                variant="outline"
+                onClick={() => handleDatabaseCommand("check_database")}
+                disabled={databaseBusy}
+              >
+                Check database
+              </Button>
+              {databaseReport != null &&
+                !databaseReport.repaired &&
+                (databaseReport.issues.length > 0 || databaseReport.integrity_errors.length > 0) && (
+                  <Button
+                    variant="outline"
+                    onClick={() => handleDatabaseCommand("repair_database")}
+                    disabled={databaseBusy}
+                  >
+                    Repair
+                  </Button>
+                )}
+            </div>
+            {databaseError != null && <p className="text-sm text-destructive">{databaseError}</p>}
+            {databaseReport != null && (
+              <div className="text-sm">
+                {databaseReport.issues.length === 0 && databaseReport.integrity_errors.length === 0 && (
+                  <p className="text-green-600 dark:text-green-400">No problems found.</p>
+                )}
+                {databaseReport.issues.map((issue) => (
+                  <p key={issue.kind}>
+                    {databaseReport.repaired ? "Fixed: " : ""}
+                    {issue.description} ({issue.ids.length})
+                  </p>
+                ))}
+                {databaseReport.trashed_duplicates > 0 && (
+                  <p className="text-muted-foreground">
+                    {databaseReport.trashed_duplicates} card(s) duplicated another card once moved and
+                    were put in the trash.
+                  </p>
+                )}
+                {databaseReport.integrity_errors.map((error) => (
+                  <p key={error} className="text-destructive">
+                    {error}
+                  </p>
+                ))}
+                {databaseReport.repaired && databaseReport.integrity_errors.length > 0 && (
+                  <p className="text-destructive">
+                    SQLite still reports damage. Restore a backup from before the problem started.
+                  </p>
+                )}
+              </div>
+            )}
+          </div>
         </CardContent>
       </Card>
 
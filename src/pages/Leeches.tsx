@@ -0,0 +1,189 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { errorMessage } from "@/lib/utils";
+
+type StoredCollection = { id: number; name: string };
+type LeechCard = {
+  id: number;
+  question: string;
+  answer: string;
+  hint: string;
+  sub_collection_id: number;
+  lapses: number;
+  reps: number;
+  leech_at: string;
+  suspended_at: string | null;
+};
+type Leeches = { cards: LeechCard[]; threshold: number; suspend: boolean };
+
+export function Leeches() {
+  const [collections, setCollections] = useState<StoredCollection[]>([]);
+  const [collectionId, setCollectionId] = useState("");
+  const [leeches, setLeeches] = useState<LeechCard[]>([]);
+  const [threshold, setThreshold] = useState("");
+  const [suspend, setSuspend] = useState(false);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<StoredCollection[]>("get_collections")
+      .then((data) => {
+        setCollections(data);
+        if (data.length > 0) setCollectionId(String(data[0].id));
+      })
+      .catch((e) => setError(errorMessage(e)));
+  }, []);
+
+  async function load() {
+    if (collectionId === "") return;
+    try {
+      const data = await invoke<Leeches>("get_leeches", { collectionId: Number(collectionId) });
+      setLeeches(data.cards);
+      setThreshold(String(data.threshold));
+      setSuspend(data.suspend);
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  }
+
+  useEffect(() => {
+    setMessage(null);
+    load();
+  }, [collectionId]);
+
+  async function saveOptions() {
+    const lapses = Number(threshold);
+    if (!Number.isInteger(lapses) || lapses < 1) {
+      setError("Enter a number of lapses of at least 1.");
+      return;
+    }
+    setBusy(true);
+    setError(null);
+    try {
+      await invoke("set_leech_options", { threshold: lapses, suspend });
+      setMessage("Leech settings saved.");
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function run(action: () => Promise<unknown>) {
+    setBusy(true);
+    setError(null);
+    try {
+      await action();
+      await load();
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  return (
+    <div className="mx-auto flex w-full max-w-3xl flex-1 flex-col gap-6 p-6">
+      <Card>
+        <CardHeader>
+          <CardTitle>Leeches</CardTitle>
+          <CardDescription>
+            Cards you keep forgetting. A card becomes a leech when one of its sides lapses as many times as
+            the threshold; rewrite it on the Edit Cards page, then clear the mark.
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-6">
+          <div className="flex flex-wrap items-end gap-3">
+            <div className="grid min-w-48 flex-1 gap-2">
+              <Label>Collection</Label>
+              <Select value={collectionId} onValueChange={setCollectionId}>
+                <SelectTrigger className="w-full min-w-0">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  {collections.map((c) => (
+                    <SelectItem key={c.id} value={String(c.id)}>
+                      {c.name}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="leech-threshold">Lapses</Label>
+              <Input
+                id="leech-threshold"
+                className="w-24"
+                inputMode="numeric"
+                value={threshold}
+                onChange={(e) => setThreshold(e.target.value)}
+              />
+            </div>
+            <label className="flex h-9 cursor-pointer items-center gap-2 text-sm">
+              <input
+                type="checkbox"
+                checked={suspend}
+                onChange={(e) => setSuspend(e.target.checked)}
+                className="h-4 w-4 rounded border-input"
+              />
+              <span>Suspend leeches</span>
+            </label>
+            <Button variant="outline" onClick={saveOptions} disabled={busy}>
+              Save
+            </Button>
+          </div>
+          {error != null && <p className="text-sm text-destructive">{error}</p>}
+          {message != null && <p className="text-sm text-muted-foreground">{message}</p>}
+          {leeches.length === 0 && <p className="text-sm text-muted-foreground">No leeches.</p>}
+
+          <ul className="divide-y">
+            {leeches.map((card) => (
+              <li key={card.id} className="flex items-start justify-between gap-4 py-2 text-sm">
+                <div className="min-w-0">
+                  <p className="font-medium">{card.question}</p>
+                  <p className="text-muted-foreground">{card.answer}</p>
+                  <p className="text-xs text-muted-foreground">
+                    {card.lapses} lapses in {card.reps} reviews · leech since {card.leech_at}
+                    {card.suspended_at ? " · suspended" : ""}
+                  </p>
+                </div>
+                <div className="flex shrink-0 gap-2">
+                  {card.suspended_at && (
+                    <Button
+                      size="sm"
+                      variant="outline"
+                      disabled={busy}
+                      onClick={() => run(() => invoke("set_card_suspended", { cardId: card.id, suspended: false }))}
+                    >
+                      Unsuspend
+                    </Button>
+                  )}
+                  <Button
+                    size="sm"
+                    variant="outline"
+                    disabled={busy}
+                    onClick={() => run(() => invoke("clear_leech", { cardId: card.id }))}
+                  >
+                    Clear leech
+                  </Button>
+                </div>
+              </li>
+            ))}
+          </ul>
+        </CardContent>
+      </Card>
+    </div>
+  );
+}
@@ -1,4 +1,4 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
@@ -12,13 +12,9 @@ import {
 } from "@/components/ui/select";
 import { Textarea } from "@/components/ui/textarea";
 import { invoke } from "@tauri-apps/api/core";
-import { fetch as tauriFetch } from "@tauri-apps/plugin-http";
-import { DEFAULT_PROMPT_PREFIX, generateFlashcard } from "@/lib/utils";
+import { DEFAULT_PROMPT_PREFIX, generateFlashcard, errorMessage } from "@/lib/utils";
 
-const OLLAMA_HOSTS = {
-  local: "http://localhost:11434",
-  cloud: "https://ollama.com",
-} as const;
+type CredentialStatus = { provider: string; configured: boolean; source?: "stored" | "environment" };
 
 export function OllamaTest() {
   const [ollamaHost, setOllamaHost] = useState<"local" | "cloud">("local");
@@ -28,43 +24,69 @@ export function OllamaTest() {
   const [response, setResponse] = useState<string | null>(null);
   const [error, setError] = useState<string | null>(null);
   const [loading, setLoading] = useState(false);
+  const [credentialStatus, setCredentialStatus] = useState<CredentialStatus | null>(null);
+  const [apiKeyInput, setApiKeyInput] = useState("");
+  const [apiKeyMessage, setApiKeyMessage] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<CredentialStatus>("get_credential_status", { provider: "ollama" })
+      .then(setCredentialStatus)
+      .catch(() => {});
+  }, []);
 
   async function handleSend() {
     setError(null);
     setResponse(null);
     setLoading(true);
-    let apiKey: string | undefined;
     try {
-      const host = OLLAMA_HOSTS[ollamaHost];
-      const isOllamaCloud = ollamaHost === "cloud";
-      apiKey = isOllamaCloud
-        ? await invoke<string>("get_ollama_api_key")
-        : undefined;
       const prefix = promptPrefix.trim() || undefined;
       const message = await generateFlashcard(
         flashcardInfo.trim() || "Sample topic: photosynthesis",
         prefix,
         model,
-        host,
-        apiKey || undefined,
-        isOllamaCloud ? tauriFetch : undefined
+        ollamaHost
       );
       setResponse(message.content ?? "(empty response)");
     } catch (e) {
-      const errMsg = e instanceof Error ? e.message : String(e);
-      const isUnauthorized = /unauthorized/i.test(errMsg);
-      const withKey =
-        isUnauthorized && apiKey !== undefined
-          ? `${errMsg}\n\nAPI key (for verification): ${apiKey}`
-          : isUnauthorized && apiKey === undefined
-            ? `${errMsg}\n\nAPI key (for verification): (empty — not set in environment)`
-            : errMsg;
-      setError(withKey);
+      setError(errorMessage(e));
     } finally {
       setLoading(false);
     }
   }
 
+  async function handleSaveApiKey() {
+    setApiKeyMessage(null);
+    try {
+      const status = await invoke<CredentialStatus>("set_api_key", { provider: "ollama", apiKey: apiKeyInput });
+      setCredentialStatus(status);
+      setApiKeyInput("");
+      setApiKeyMessage("API key saved.");
+    } catch (e) {
+      setApiKeyMessage(errorMessage(e));
+    }
+  }
+
+  async function handleTestApiKey() {
+    setApiKeyMessage(null);
+    try {
+      await invoke("test_api_key", { provider: "ollama" });
+      setApiKeyMessage("API key works.");
+    } catch (e) {
+      setApiKeyMessage(errorMessage(e));
+    }
+  }
+
+  async function handleDeleteApiKey() {
+    setApiKeyMessage(null);
+    try {
+      const status = await invoke<CredentialStatus>("delete_api_key", { provider: "ollama" });
+      setCredentialStatus(status);
+      setApiKeyMessage("API key deleted.");
+    } catch (e) {
+      setApiKeyMessage(errorMessage(e));
+    }
+  }
+
   return (
     <div className="grid flex-1 grid-cols-1 gap-6 p-6 lg:grid-cols-2">
       <Card>
@@ -94,6 +116,39 @@ export function OllamaTest() {
               </SelectContent>
             </Select>
           </div>
+          {ollamaHost === "cloud" && (
+            <div className="grid w-full gap-2">
+              <Label htmlFor="ollama-api-key">
+                API key{" "}
+                <span className="text-muted-foreground font-normal">
+                  ({credentialStatus?.configured ? `configured, ${credentialStatus.source}` : "not configured"})
+                </span>
+              </Label>
+              <Input
+                id="ollama-api-key"
+                type="password"
+                value={apiKeyInput}
+                onChange={(e) => setApiKeyInput(e.target.value)}
+                placeholder="Paste a new key to store it encrypted"
+              />
+              <div className="flex gap-2">
+                <Button variant="outline" onClick={handleSaveApiKey} disabled={!apiKeyInput.trim()}>
+                  Save
+                </Button>
+                <Button variant="outline" onClick={handleTestApiKey} disabled={!credentialStatus?.configured}>
+                  Test
+                </Button>
+                <Button
+                  variant="outline"
+                  onClick={handleDeleteApiKey}
+                  disabled={credentialStatus?.source !== "stored"}
+                >
+                  Delete
+                </Button>
+              </div>
+              {apiKeyMessage && <p className="text-sm text-muted-foreground">{apiKeyMessage}</p>}
+            </div>
+          )}
           <div className="grid w-full gap-2">
             <Label htmlFor="model">Model</Label>
             <Input
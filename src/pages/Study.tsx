@@ -1,6 +1,6 @@
-import { useEffect, useMemo, useRef, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Pencil } from "lucide-react";
+import { Pencil, Settings2 } from "lucide-react";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import {
@@ -20,37 +20,98 @@ import {
 } from "@/components/ui/dialog";
 import { Input } from "@/components/ui/input";
 import { Textarea } from "@/components/ui/textarea";
-import { cn } from "@/lib/utils";
+import { StudyOptionsDialog } from "@/components/StudyOptionsDialog";
+import { cn, errorMessage } from "@/lib/utils";
 
 const SUB_COLLECTION_ALL = "__all__"; // Radix Select forbids SelectItem value=""
 const SUB_COLLECTION_NONE = "__none__"; // No sub-collection in edit modal
 
-function shuffle<T>(array: T[]): T[] {
-  const out = [...array];
-  for (let i = out.length - 1; i > 0; i--) {
-    const j = Math.floor(Math.random() * (i + 1));
-    [out[i], out[j]] = [out[j], out[i]];
-  }
-  return out;
-}
+const GRADES = [
+  { grade: "again", label: "Again" },
+  { grade: "hard", label: "Hard" },
+  { grade: "good", label: "Good" },
+  { grade: "easy", label: "Easy" },
+] as const;
+type Grade = (typeof GRADES)[number]["grade"];
 
-type StoredCard = { id: number; question: string; answer: string; hint: string; skipped: boolean; sub_collection_id?: number | null };
+type CardType = "basic" | "reverse" | "cloze" | "multiple_choice";
+/** One reviewable side of a card, rendered by the backend. */
+type StudyPrompt = {
+  id: number;
+  card_id: number;
+  ordinal: number;
+  card_type: CardType;
+  front: string;
+  back: string;
+  expected: string;
+  choices?: string[];
+  hint: string;
+  question: string;
+  answer: string;
+  sub_collection_id: number;
+  state: string;
+  due: string | null;
+};
+type Outcome = Grade | "seen" | "suspended" | "buried";
+const STUDY_MODES = [
+  { mode: "created", label: "Created order" },
+  { mode: "shuffle", label: "Shuffled" },
+  { mode: "hardest_first", label: "Hardest first" },
+  { mode: "oldest_reviewed", label: "Oldest reviewed first" },
+  { mode: "interleaved", label: "Interleaved sub collections" },
+] as const;
+type StudyMode = (typeof STUDY_MODES)[number]["mode"];
+type SessionCard = StudyPrompt & { outcome: Outcome | null };
+type StudySession = {
+  id: number;
+  collection_id: number;
+  sub_collection_id: number | null;
+  mode: StudyMode;
+  seed: number;
+  total: number;
+  answered: number;
+  outcomes: Record<Outcome, number>;
+  started_at: string;
+  updated_at: string;
+  ended_at: string | null;
+};
+type StudySessionState = { session: StudySession; cards: SessionCard[]; current_index: number };
+type DiffSegment = { kind: "same" | "missing" | "extra"; text: string };
+type AnswerCheck = {
+  expected: string;
+  correct: boolean;
+  similarity: number;
+  suggested_grade: Grade;
+  diff: DiffSegment[];
+};
 type StoredCollection = { id: number; name: string };
-type StoredSubCollection = { id: number; name: string; collection_id: number };
+type StoredSubCollection = {
+  id: number;
+  name: string;
+  collection_id: number;
+  parent_id: number | null;
+  /** e.g. "Chapter 1/Section 2" */
+  path: string;
+};
 
 export function Study() {
   const [collections, setCollections] = useState<StoredCollection[]>([]);
   const [selectedCollectionId, setSelectedCollectionId] = useState<string>("");
   const [subCollections, setSubCollections] = useState<StoredSubCollection[]>([]);
   const [selectedSubCollectionId, setSelectedSubCollectionId] = useState<string>(SUB_COLLECTION_ALL);
-  const [cards, setCards] = useState<StoredCard[]>([]);
+  const [session, setSession] = useState<StudySession | null>(null);
+  const [cards, setCards] = useState<SessionCard[]>([]);
   const [currentIndex, setCurrentIndex] = useState(0);
   const [flipped, setFlipped] = useState(false);
-  const [skipChecked, setSkipChecked] = useState(false);
+  const [pickedChoice, setPickedChoice] = useState<string | null>(null);
+  const [typedAnswer, setTypedAnswer] = useState("");
+  const [answerCheck, setAnswerCheck] = useState<AnswerCheck | null>(null);
+  const [grading, setGrading] = useState(false);
   const [loadingCollections, setLoadingCollections] = useState(true);
   const [loadingCards, setLoadingCards] = useState(true);
   const [error, setError] = useState<string | null>(null);
-  const [editingCard, setEditingCard] = useState<StoredCard | null>(null);
+  const [optionsOpen, setOptionsOpen] = useState(false);
+  const [editingCard, setEditingCard] = useState<StudyPrompt | null>(null);
   const [editSubCollectionId, setEditSubCollectionId] = useState<string>(SUB_COLLECTION_NONE);
   const [editHint, setEditHint] = useState("");
   const [editQuestion, setEditQuestion] = useState("");
@@ -71,7 +132,7 @@ export function Study() {
         }
       })
       .catch((e) => {
-        if (!cancelled) setError(e instanceof Error ? e.message : String(e));
+        if (!cancelled) setError(errorMessage(e));
       })
       .finally(() => {
         if (!cancelled) setLoadingCollections(false);
@@ -106,26 +167,33 @@ export function Study() {
     };
   }, [selectedCollectionId]);
 
+  const subCollectionId =
+    selectedSubCollectionId === SUB_COLLECTION_ALL ? null : Number(selectedSubCollectionId);
+
+  // Resume the open session for this collection and sub collection, or start one.
   useEffect(() => {
     if (!selectedCollectionId) {
       setLoadingCards(false);
+      setSession(null);
       setCards([]);
       return;
     }
     let cancelled = false;
     setLoadingCards(true);
     setError(null);
-    invoke<StoredCard[]>("get_cards", { collectionId: Number(selectedCollectionId) })
-      .then((data) => {
-        if (!cancelled) {
-          setCards(data);
-          setCurrentIndex(0);
-          setFlipped(false);
-          setSkipChecked(false);
-        }
+    const collectionId = Number(selectedCollectionId);
+    invoke<StudySession[]>("list_study_sessions", { collectionId })
+      .then((open) => {
+        const resumable = open.find((s) => s.sub_collection_id === subCollectionId);
+        return resumable
+          ? invoke<StudySessionState>("get_study_session", { sessionId: resumable.id })
+          : invoke<StudySessionState>("start_study_session", { collectionId, subCollectionId });
+      })
+      .then((state) => {
+        if (!cancelled) showSession(state);
       })
       .catch((e) => {
-        if (!cancelled) setError(e instanceof Error ? e.message : String(e));
+        if (!cancelled) setError(errorMessage(e));
       })
       .finally(() => {
         if (!cancelled) setLoadingCards(false);
@@ -133,28 +201,78 @@ export function Study() {
     return () => {
       cancelled = true;
     };
-  }, [selectedCollectionId]);
-
-  const filteredCards = useMemo(
-    () =>
-      selectedSubCollectionId === SUB_COLLECTION_ALL
-        ? cards
-        : cards.filter(
-            (c) => c.sub_collection_id != null && c.sub_collection_id === Number(selectedSubCollectionId)
-          ),
-    [cards, selectedSubCollectionId]
-  );
+  }, [selectedCollectionId, selectedSubCollectionId]);
 
-  const sessionCards = useMemo(
-    () => filteredCards.filter((c) => !c.skipped),
-    [filteredCards]
-  );
+  const sessionCards = cards;
+  const finished = sessionCards.length > 0 && currentIndex >= sessionCards.length;
   const currentCard = sessionCards[Math.min(currentIndex, sessionCards.length - 1)];
 
+  function resetCard() {
+    setFlipped(false);
+    setPickedChoice(null);
+    setTypedAnswer("");
+    setAnswerCheck(null);
+  }
+
+  function showSession(state: StudySessionState) {
+    setSession(state.session);
+    setCards(state.cards);
+    setCurrentIndex(state.current_index);
+    resetCard();
+  }
+
+  async function startSession(mode: StudyMode, seed?: number) {
+    try {
+      showSession(
+        await invoke<StudySessionState>("start_study_session", {
+          collectionId: Number(selectedCollectionId),
+          subCollectionId,
+          mode,
+          seed,
+        })
+      );
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  }
+
+  /** Record the outcome for the current card and move on; ends the session after the last card. */
+  async function advance(outcome: Outcome) {
+    if (!session || !currentCard) return;
+    const updated = await invoke<StudySession>("advance_study_session", {
+      sessionId: session.id,
+      promptId: currentCard.id,
+      outcome,
+    });
+    if (outcome === "suspended" || outcome === "buried") {
+      // The card's other prompts leave the session too.
+      const state = await invoke<StudySessionState>("get_study_session", { sessionId: session.id });
+      const next = state.cards.findIndex((c) => c.outcome === null && c.id !== currentCard.id);
+      setCards(state.cards);
+      setCurrentIndex(next === -1 ? state.cards.length : next);
+      setSession(state.session);
+      if (next === -1) await finish();
+    } else {
+      setCards((prev) => prev.map((c) => (c.id === currentCard.id ? { ...c, outcome } : c)));
+      setSession(updated);
+      setCurrentIndex((i) => i + 1);
+      if (currentIndex + 1 >= sessionCards.length) await finish();
+    }
+    resetCard();
+  }
+
+  async function finish() {
+    if (!session) return;
+    setSession(await invoke<StudySession>("end_study_session", { sessionId: session.id }));
+  }
+
   const handleNextRef = useRef(() => {});
   const handlePreviousRef = useRef(() => {});
+  const handleGradeRef = useRef((_grade: Grade) => {});
+  const flippedRef = useRef(flipped);
+  flippedRef.current = flipped;
   useEffect(() => {
-    if (sessionCards.length === 0) return;
+    if (sessionCards.length === 0 || finished) return;
     function onKeyDown(e: KeyboardEvent) {
       if (editingCard) return;
       const target = e.target as HTMLElement;
@@ -168,61 +286,78 @@ export function Study() {
       } else if (e.key === "ArrowLeft") {
         e.preventDefault();
         handlePreviousRef.current();
-      } else if (e.key === " ") {
+      } else if (["1", "2", "3", "4"].includes(e.key) && flippedRef.current) {
         e.preventDefault();
-        setSkipChecked((s) => !s);
+        handleGradeRef.current(GRADES[Number(e.key) - 1].grade);
       }
     }
     window.addEventListener("keydown", onKeyDown);
     return () => window.removeEventListener("keydown", onKeyDown);
-  }, [sessionCards.length, editingCard]);
+  }, [sessionCards.length, finished, editingCard]);
 
-  async function handleNext() {
-    setFlipped(false);
-    setSkipChecked(false);
-    if (skipChecked && currentCard) {
-      try {
-        await invoke("set_card_skipped", { cardId: currentCard.id, skipped: true });
-        setCards((prev) =>
-          prev.map((c) => (c.id === currentCard.id ? { ...c, skipped: true } : c))
-        );
-        setCurrentIndex((i) => Math.max(0, Math.min(i, sessionCards.length - 2)));
-      } catch (e) {
-        setError(e instanceof Error ? e.message : String(e));
-      }
-    } else {
-      setCurrentIndex((i) =>
-        i >= sessionCards.length - 1 ? 0 : i + 1
-      );
+  /** Move on with a grade, or without one ("seen"), or suspending or burying the card. */
+  async function handleAdvance(outcome: Outcome) {
+    if (!currentCard || grading) return;
+    setGrading(true);
+    try {
+      await advance(outcome);
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setGrading(false);
+    }
+  }
+  handleNextRef.current = () => handleAdvance("seen");
+  handleGradeRef.current = handleAdvance;
+
+  function handlePick(choice: string) {
+    setPickedChoice(choice);
+    setFlipped(true);
+  }
+
+  async function handleCheckAnswer() {
+    if (!currentCard || !typedAnswer.trim()) return;
+    try {
+      const check = await invoke<AnswerCheck>("check_answer", {
+        cardId: currentCard.card_id,
+        typed: typedAnswer,
+        ordinal: currentCard.ordinal,
+      });
+      setAnswerCheck(check);
+      setFlipped(true);
+    } catch (e) {
+      setError(errorMessage(e));
     }
   }
-  handleNextRef.current = handleNext;
 
   function handlePrevious() {
-    setFlipped(false);
-    setSkipChecked(false);
-    setCurrentIndex((i) => (i <= 0 ? sessionCards.length - 1 : i - 1));
+    resetCard();
+    setCurrentIndex((i) => Math.max(0, i - 1));
   }
   handlePreviousRef.current = handlePrevious;
 
+  /** Same cards in the same order, from the start. */
   function handleRestart() {
-    setFlipped(false);
-    setCurrentIndex(0);
-    setSkipChecked(false);
+    if (session) startSession(session.mode, session.seed);
   }
 
+  /** A new shuffle, even when already shuffled. */
   function handleShuffle() {
-    setCards((prev) => shuffle(prev));
-    setCurrentIndex(0);
-    setFlipped(false);
-    setSkipChecked(false);
+    startSession("shuffle");
   }
 
-  function openEdit(card: StoredCard) {
+  async function reloadQueue() {
+    if (!session) return;
+    const state = await invoke<StudySessionState>("get_study_session", { sessionId: session.id });
+    setCards(state.cards);
+    setCurrentIndex((i) => Math.max(0, Math.min(i, state.cards.length - 1)));
+  }
+
+  function openEdit(card: StudyPrompt) {
     setEditingCard(card);
     const sub = subCollections.find((s) => s.id === card.sub_collection_id);
-    const isNone = card.sub_collection_id == null || sub?.name === "- None -";
-    setEditSubCollectionId(isNone ? SUB_COLLECTION_NONE : String(card.sub_collection_id!));
+    const isNone = sub?.name === "- None -";
+    setEditSubCollectionId(isNone ? SUB_COLLECTION_NONE : String(card.sub_collection_id));
     setEditHint(card.hint ?? "");
     setEditQuestion(card.question);
     setEditAnswer(card.answer);
@@ -258,13 +393,10 @@ export function Study() {
               ? Number(editSubCollectionId)
               : undefined,
         });
-        const data = await invoke<StoredCard[]>("get_cards", {
-          collectionId: Number(selectedCollectionId),
-        });
-        setCards(data);
+        await reloadQueue();
       } else {
         await invoke("update_card", {
-          id: editingCard.id,
+          id: editingCard.card_id,
           question: q,
           answer: a,
           collectionId: Number(selectedCollectionId),
@@ -274,32 +406,36 @@ export function Study() {
               ? Number(editSubCollectionId)
               : undefined,
         });
-        const newSubId =
-          editSubCollectionId && editSubCollectionId !== SUB_COLLECTION_NONE
-            ? Number(editSubCollectionId)
-            : null;
-        setCards((prev) =>
-          prev.map((c) =>
-            c.id === editingCard.id
-              ? {
-                  ...c,
-                  hint: editHint.trim(),
-                  question: q,
-                  answer: a,
-                  sub_collection_id: newSubId,
-                }
-              : c
-          )
-        );
+        // The rendered sides follow from the text, so fetch them again.
+        await reloadQueue();
       }
       closeEdit();
     } catch (e) {
-      setModalError(e instanceof Error ? e.message : String(e));
+      setModalError(errorMessage(e));
     } finally {
       setSaving(false);
     }
   }
 
+  const optionsButton = (
+    <Button variant="outline" size="sm" className="w-fit" onClick={() => setOptionsOpen(true)}>
+      <Settings2 className="size-4" />
+      Study options
+    </Button>
+  );
+  const optionsDialog = selectedCollectionId && (
+    <StudyOptionsDialog
+      open={optionsOpen}
+      onOpenChange={setOptionsOpen}
+      collectionId={Number(selectedCollectionId)}
+      subCollectionId={subCollectionId}
+      onChanged={() => {
+        // Other limits may let more cards in today; an ongoing pass keeps its cards.
+        if (sessionCards.length === 0 || finished) startSession(session?.mode ?? "created");
+      }}
+    />
+  );
+
   if (loadingCollections) {
     return (
       <div className="mx-auto flex w-full max-w-2xl flex-1 flex-col gap-6 p-6">
@@ -356,61 +492,6 @@ export function Study() {
     );
   }
 
-  if (filteredCards.length === 0) {
-    return (
-      <div className="mx-auto flex w-full max-w-2xl flex-1 flex-col gap-6 p-6">
-        <Card>
-          <CardHeader>
-            <CardTitle>Study</CardTitle>
-            <CardDescription>
-              Review your flashcards. Flip to reveal answers and track what you know.
-            </CardDescription>
-          </CardHeader>
-          <CardContent className="flex flex-col gap-4">
-            <div className="grid max-w-xs grid-cols-[auto_1fr] gap-x-3 gap-y-2 items-center">
-              <Label className="shrink-0">Collection</Label>
-              <Select value={selectedCollectionId} onValueChange={setSelectedCollectionId}>
-                <SelectTrigger className="w-full min-w-0">
-                  <SelectValue />
-                </SelectTrigger>
-                <SelectContent>
-                  {collections.map((c) => (
-                    <SelectItem key={c.id} value={String(c.id)}>
-                      {c.name}
-                    </SelectItem>
-                  ))}
-                </SelectContent>
-              </Select>
-              <Label className="shrink-0">Sub Collection</Label>
-              <Select
-                value={selectedSubCollectionId}
-                onValueChange={setSelectedSubCollectionId}
-                disabled={!selectedCollectionId}
-              >
-                <SelectTrigger className="w-full min-w-0">
-                  <SelectValue placeholder="All" />
-                </SelectTrigger>
-                <SelectContent>
-                  <SelectItem value={SUB_COLLECTION_ALL}>All</SelectItem>
-                  {subCollections.map((s) => (
-                    <SelectItem key={s.id} value={String(s.id)}>
-                      {s.name}
-                    </SelectItem>
-                  ))}
-                </SelectContent>
-              </Select>
-            </div>
-            <p className="text-muted-foreground text-sm">
-              {cards.length === 0
-                ? "No cards in this collection. Add some on the Create Cards page."
-                : "No cards in this sub collection."}
-            </p>
-          </CardContent>
-        </Card>
-      </div>
-    );
-  }
-
   if (sessionCards.length === 0) {
     return (
       <div className="mx-auto flex w-full max-w-2xl flex-1 flex-col gap-6 p-6">
@@ -449,17 +530,21 @@ export function Study() {
                   <SelectItem value={SUB_COLLECTION_ALL}>All</SelectItem>
                   {subCollections.map((s) => (
                     <SelectItem key={s.id} value={String(s.id)}>
-                      {s.name}
+                      {s.path}
                     </SelectItem>
                   ))}
                 </SelectContent>
               </Select>
             </div>
             <p className="text-muted-foreground text-sm">
-              You&apos;ve skipped all cards in this collection. Clear skips on the Edit Cards page to continue studying.
+              {subCollectionId === null
+                ? "No cards due in this collection today. Add some on the Create Cards page, unsuspend cards on the Edit Cards page, or raise the daily limits."
+                : "No cards due in this sub collection today."}
             </p>
+            {optionsButton}
           </CardContent>
         </Card>
+        {optionsDialog}
       </div>
     );
   }
@@ -501,113 +586,239 @@ export function Study() {
                 <SelectItem value={SUB_COLLECTION_ALL}>All</SelectItem>
                 {subCollections.map((s) => (
                   <SelectItem key={s.id} value={String(s.id)}>
-                    {s.name}
+                    {s.path}
+                  </SelectItem>
+                ))}
+              </SelectContent>
+            </Select>
+            <Label className="shrink-0">Order</Label>
+            <Select
+              value={session?.mode ?? "created"}
+              onValueChange={(mode) => startSession(mode as StudyMode)}
+            >
+              <SelectTrigger className="w-full min-w-0">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                {STUDY_MODES.map(({ mode, label }) => (
+                  <SelectItem key={mode} value={mode}>
+                    {label}
                   </SelectItem>
                 ))}
               </SelectContent>
             </Select>
           </div>
+          {optionsButton}
 
-          <p className="text-muted-foreground text-sm">
-            Card {currentIndex + 1} of {sessionCards.length}
-            {sessionCards.length < filteredCards.length ? (
-              <span className="ml-1">({filteredCards.length - sessionCards.length} skipped)</span>
-            ) : null}
-          </p>
-
-          <button
-            type="button"
-            onClick={() => setFlipped((f) => !f)}
-            className="relative h-[220px] w-full cursor-pointer [perspective:1000px]"
-            aria-label={flipped ? "Show question" : "Show answer"}
-          >
-            <div
-              className="relative h-full w-full transition-transform duration-500 [transform-style:preserve-3d]"
-              style={{ transform: flipped ? "rotateY(180deg)" : undefined }}
-            >
-              <div
-                className="absolute inset-0 flex flex-col rounded-xl border bg-card p-4 shadow-md [backface-visibility:hidden]"
-                style={{ transform: "rotateY(0deg)" }}
+          {finished && session ? (
+            <div className="flex flex-col gap-4">
+              <p className="text-sm">
+                Session complete: {session.answered} of {session.total} cards answered.
+              </p>
+              <ul className="text-muted-foreground grid grid-cols-3 gap-1 text-sm">
+                {GRADES.map(({ grade, label }) => (
+                  <li key={grade}>
+                    {label}: {session.outcomes[grade]}
+                  </li>
+                ))}
+                <li>Seen: {session.outcomes.seen}</li>
+                <li>Suspended: {session.outcomes.suspended}</li>
+                <li>Buried: {session.outcomes.buried}</li>
+              </ul>
+              <div>
+                <Button onClick={() => startSession(session.mode)}>Study again</Button>
+              </div>
+            </div>
+          ) : (
+            <>
+              <p className="text-muted-foreground text-sm">
+                Card {currentIndex + 1} of {sessionCards.length}
+              </p>
+
+              <button
+                type="button"
+                onClick={() => setFlipped((f) => !f)}
+                className="relative h-[220px] w-full cursor-pointer [perspective:1000px]"
+                aria-label={flipped ? "Show question" : "Show answer"}
               >
-                {currentCard.hint ? (
-                  <p className="text-muted-foreground absolute left-3 top-3 text-xs font-medium">
-                    {currentCard.hint}
-                  </p>
-                ) : null}
+                <div
+                  className="relative h-full w-full transition-transform duration-500 [transform-style:preserve-3d]"
+                  style={{ transform: flipped ? "rotateY(180deg)" : undefined }}
+                >
+                  <div
+                    className="absolute inset-0 flex flex-col rounded-xl border bg-card p-4 shadow-md [backface-visibility:hidden]"
+                    style={{ transform: "rotateY(0deg)" }}
+                  >
+                    {currentCard.hint ? (
+                      <p className="text-muted-foreground absolute left-3 top-3 text-xs font-medium">
+                        {currentCard.hint}
+                      </p>
+                    ) : null}
+                    <Button
+                      type="button"
+                      variant="ghost"
+                      size="icon"
+                      className="absolute right-3 top-3 size-8 shrink-0"
+                      aria-label="Edit card"
+                      onClick={(e) => {
+                        e.stopPropagation();
+                        openEdit(currentCard);
+                      }}
+                    >
+                      <Pencil className="size-4" />
+                    </Button>
+                    <p className="whitespace-pre-wrap break-words text-center text-sm flex-1 flex items-center justify-center">
+                      {currentCard.front}
+                    </p>
+                  </div>
+                  <div
+                    className="absolute inset-0 flex flex-col rounded-xl border bg-muted p-4 shadow-md [backface-visibility:hidden]"
+                    style={{ transform: "rotateY(180deg)" }}
+                  >
+                    {currentCard.hint ? (
+                      <p className="text-muted-foreground absolute left-3 top-3 text-xs font-medium">
+                        {currentCard.hint}
+                      </p>
+                    ) : null}
+                    <Button
+                      type="button"
+                      variant="ghost"
+                      size="icon"
+                      className="absolute right-3 top-3 size-8 shrink-0"
+                      aria-label="Edit card"
+                      onClick={(e) => {
+                        e.stopPropagation();
+                        openEdit(currentCard);
+                      }}
+                    >
+                      <Pencil className="size-4" />
+                    </Button>
+                    <p className="whitespace-pre-wrap break-words text-center text-sm flex-1 flex items-center justify-center">
+                      {currentCard.back}
+                    </p>
+                  </div>
+                </div>
+              </button>
+
+              {!currentCard.choices && !flipped && (
+                <form
+                  className="flex gap-2"
+                  onSubmit={(e) => {
+                    e.preventDefault();
+                    handleCheckAnswer();
+                  }}
+                >
+                  <Input
+                    placeholder="Type your answer (optional)"
+                    value={typedAnswer}
+                    onChange={(e) => setTypedAnswer(e.target.value)}
+                  />
+                  <Button type="submit" variant="outline" disabled={!typedAnswer.trim()}>
+                    Check
+                  </Button>
+                </form>
+              )}
+
+              {answerCheck && flipped && (
+                <p className="text-sm" aria-label="Your answer compared with the card">
+                  {answerCheck.diff.map((segment, i) => (
+                    <span
+                      key={i}
+                      className={cn(
+                        segment.kind === "missing" && "bg-green-500/20 text-green-700 dark:text-green-400",
+                        segment.kind === "extra" && "bg-destructive/20 text-destructive line-through"
+                      )}
+                    >
+                      {segment.text}
+                    </span>
+                  ))}
+                  <span className="text-muted-foreground ml-2">
+                    {answerCheck.correct ? "Correct" : `${Math.round(answerCheck.similarity * 100)}% match`}
+                  </span>
+                </p>
+              )}
+
+              {currentCard.choices && (
+                <div className="grid gap-2 sm:grid-cols-2">
+                  {currentCard.choices.map((choice) => (
+                    <Button
+                      key={choice}
+                      type="button"
+                      variant="outline"
+                      disabled={flipped}
+                      className={cn(
+                        "h-auto whitespace-normal py-2",
+                        flipped && choice === currentCard.expected && "border-green-600 text-green-700 dark:text-green-400",
+                        flipped && choice === pickedChoice && choice !== currentCard.expected && "border-destructive text-destructive"
+                      )}
+                      onClick={() => handlePick(choice)}
+                    >
+                      {choice}
+                    </Button>
+                  ))}
+                </div>
+              )}
+
+              {flipped && (
+                <div className="grid grid-cols-4 gap-2" role="group" aria-label="How well did you know it?">
+                  {GRADES.map(({ grade, label }, i) => (
+                    <Button
+                      key={grade}
+                      type="button"
+                      variant={
+                        answerCheck?.suggested_grade === grade ? "default" : grade === "again" ? "destructive" : "secondary"
+                      }
+                      disabled={grading}
+                      onClick={() => handleAdvance(grade)}
+                      title={`Press ${i + 1}`}
+                    >
+                      {label}
+                    </Button>
+                  ))}
+                </div>
+              )}
+
+              <div className="flex gap-2">
                 <Button
-                  type="button"
                   variant="ghost"
-                  size="icon"
-                  className="absolute right-3 top-3 size-8 shrink-0"
-                  aria-label="Edit card"
-                  onClick={(e) => {
-                    e.stopPropagation();
-                    openEdit(currentCard);
-                  }}
+                  size="sm"
+                  disabled={grading}
+                  onClick={() => handleAdvance("buried")}
+                  title="Hide this card until tomorrow"
                 >
-                  <Pencil className="size-4" />
+                  Bury
                 </Button>
-                <p className="whitespace-pre-wrap break-words text-center text-sm flex-1 flex items-center justify-center">
-                  {currentCard.question}
-                </p>
-              </div>
-              <div
-                className="absolute inset-0 flex flex-col rounded-xl border bg-muted p-4 shadow-md [backface-visibility:hidden]"
-                style={{ transform: "rotateY(180deg)" }}
-              >
-                {currentCard.hint ? (
-                  <p className="text-muted-foreground absolute left-3 top-3 text-xs font-medium">
-                    {currentCard.hint}
-                  </p>
-                ) : null}
                 <Button
-                  type="button"
                   variant="ghost"
-                  size="icon"
-                  className="absolute right-3 top-3 size-8 shrink-0"
-                  aria-label="Edit card"
-                  onClick={(e) => {
-                    e.stopPropagation();
-                    openEdit(currentCard);
-                  }}
+                  size="sm"
+                  disabled={grading}
+                  onClick={() => handleAdvance("suspended")}
+                  title="Hide this card until you unsuspend it on the Edit Cards page"
                 >
-                  <Pencil className="size-4" />
+                  Suspend
                 </Button>
-                <p className="whitespace-pre-wrap break-words text-center text-sm flex-1 flex items-center justify-center">
-                  {currentCard.answer}
-                </p>
               </div>
-            </div>
-          </button>
-
-          <label className="flex cursor-pointer items-center gap-2 text-sm">
-            <input
-              type="checkbox"
-              checked={skipChecked}
-              onChange={() => setSkipChecked((s) => !s)}
-              className="h-4 w-4 rounded border-input"
-            />
-            <span>Skip this card (I know this)</span>
-          </label>
-
-          <div className="flex justify-between">
-            <div className="flex gap-2">
-              <Button variant="outline" onClick={handleRestart}>
-                Restart
-              </Button>
-              <Button variant="outline" onClick={handleShuffle}>
-                Shuffle
-              </Button>
-            </div>
-            <div className="flex gap-2">
-              <Button variant="outline" onClick={handlePrevious}>
-                Previous
-              </Button>
-              <Button onClick={handleNext}>
-                Next
-              </Button>
-            </div>
-          </div>
+
+              <div className="flex justify-between">
+                <div className="flex gap-2">
+                  <Button variant="outline" onClick={handleRestart}>
+                    Restart
+                  </Button>
+                  <Button variant="outline" onClick={handleShuffle}>
+                    Shuffle
+                  </Button>
+                </div>
+                <div className="flex gap-2">
+                  <Button variant="outline" onClick={handlePrevious}>
+                    Previous
+                  </Button>
+                  <Button onClick={() => handleAdvance("seen")} disabled={grading}>
+                    Next
+                  </Button>
+                </div>
+              </div>
+            </>
+          )}
         </CardContent>
       </Card>
 
@@ -663,7 +874,7 @@ export function Study() {
                     .filter((s) => s.name !== "- None -")
                     .map((s) => (
                       <SelectItem key={s.id} value={String(s.id)}>
-                        {s.name}
+                        {s.path}
                       </SelectItem>
                     ))}
                 </SelectContent>
@@ -728,6 +939,7 @@ export function Study() {
           </DialogFooter>
         </DialogContent>
       </Dialog>
+      {optionsDialog}
     </div>
   );
 }
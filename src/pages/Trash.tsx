@@ -0,0 +1,180 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { errorMessage } from "@/lib/utils";
+
+type TrashedCollection = { id: number; name: string; deleted_at: string; card_count: number };
+type TrashedCard = {
+  id: number;
+  collection_id: number;
+  collection_name: string;
+  question: string;
+  answer: string;
+  deleted_at: string;
+};
+type TrashContents = { collections: TrashedCollection[]; cards: TrashedCard[]; retention_days: number };
+
+export function Trash() {
+  const [trash, setTrash] = useState<TrashContents | null>(null);
+  const [retentionDays, setRetentionDays] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  async function load() {
+    try {
+      const data = await invoke<TrashContents>("list_trash");
+      setTrash(data);
+      setRetentionDays(String(data.retention_days));
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  }
+
+  useEffect(() => {
+    load();
+  }, []);
+
+  async function run(command: string, args: Record<string, unknown>) {
+    setBusy(true);
+    setError(null);
+    try {
+      await invoke(command, args);
+      await load();
+    } catch (e) {
+      setError(errorMessage(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  function handleEmptyTrash() {
+    if (!confirm("Permanently delete everything in the trash? This cannot be undone.")) return;
+    run("purge_trash", {});
+  }
+
+  function handleSaveRetention() {
+    const days = Number(retentionDays);
+    if (!Number.isInteger(days) || days < 0) {
+      setError("Enter a whole number of days (0 keeps items until you empty the trash).");
+      return;
+    }
+    run("set_trash_retention_days", { days });
+  }
+
+  const empty = trash != null && trash.collections.length === 0 && trash.cards.length === 0;
+
+  return (
+    <div className="mx-auto flex w-full max-w-3xl flex-1 flex-col gap-6 p-6">
+      <Card>
+        <CardHeader>
+          <CardTitle>Trash</CardTitle>
+          <CardDescription>
+            Deleted cards and collections stay here until they are restored, purged, or older than the
+            retention period.
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-6">
+          <div className="flex flex-wrap items-end gap-3">
+            <div className="grid gap-2">
+              <Label htmlFor="trash-retention">Keep deleted items for (days)</Label>
+              <Input
+                id="trash-retention"
+                className="w-32"
+                inputMode="numeric"
+                value={retentionDays}
+                onChange={(e) => setRetentionDays(e.target.value)}
+              />
+            </div>
+            <Button variant="outline" onClick={handleSaveRetention} disabled={busy}>
+              Save
+            </Button>
+            <Button variant="destructive" onClick={handleEmptyTrash} disabled={busy || empty}>
+              Empty trash
+            </Button>
+          </div>
+          {error != null && <p className="text-sm text-destructive">{error}</p>}
+          {empty && <p className="text-sm text-muted-foreground">The trash is empty.</p>}
+
+          {trash != null && trash.collections.length > 0 && (
+            <div className="space-y-2">
+              <h3 className="font-medium">Collections</h3>
+              <ul className="divide-y rounded-md border">
+                {trash.collections.map((c) => (
+                  <li key={c.id} className="flex items-center justify-between gap-4 p-3 text-sm">
+                    <div>
+                      <p className="font-medium">{c.name}</p>
+                      <p className="text-muted-foreground">
+                        {c.card_count} card{c.card_count !== 1 ? "s" : ""} · deleted {c.deleted_at}
+                      </p>
+                    </div>
+                    <div className="flex shrink-0 gap-2">
+                      <Button
+                        size="sm"
+                        variant="outline"
+                        disabled={busy}
+                        onClick={() => run("restore_trash", { collectionIds: [c.id] })}
+                      >
+                        Restore
+                      </Button>
+                      <Button
+                        size="sm"
+                        variant="outline"
+                        disabled={busy}
+                        onClick={() => {
+                          if (confirm(`Permanently delete "${c.name}" and its cards?`)) {
+                            run("purge_trash", { collectionIds: [c.id] });
+                          }
+                        }}
+                      >
+                        Delete forever
+                      </Button>
+                    </div>
+                  </li>
+                ))}
+              </ul>
+            </div>
+          )}
+
+          {trash != null && trash.cards.length > 0 && (
+            <div className="space-y-2">
+              <h3 className="font-medium">Cards</h3>
+              <ul className="divide-y rounded-md border">
+                {trash.cards.map((card) => (
+                  <li key={card.id} className="flex items-center justify-between gap-4 p-3 text-sm">
+                    <div className="min-w-0">
+                      <p className="truncate font-medium">{card.question}</p>
+                      <p className="truncate text-muted-foreground">
+                        {card.collection_name} · deleted {card.deleted_at}
+                      </p>
+                    </div>
+                    <div className="flex shrink-0 gap-2">
+                      <Button
+                        size="sm"
+                        variant="outline"
+                        disabled={busy}
+                        onClick={() => run("restore_trash", { cardIds: [card.id] })}
+                      >
+                        Restore
+                      </Button>
+                      <Button
+                        size="sm"
+                        variant="outline"
+                        disabled={busy}
+                        onClick={() => run("purge_trash", { cardIds: [card.id] })}
+                      >
+                        Delete forever
+                      </Button>
+                    </div>
+                  </li>
+                ))}
+              </ul>
+            </div>
+          )}
+        </CardContent>
+      </Card>
+    </div>
+  );
+}